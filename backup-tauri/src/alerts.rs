@@ -0,0 +1,242 @@
+use crate::scan::{format_size, HistoryItem, Item, ScanResult, SizeUnit};
+use lettre::message::Mailbox;
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use utoipa::ToSchema;
+
+// 通知中最多列出的被标记条目数
+const MAX_FLAGGED_ITEMS: usize = 20;
+// 单个目标的发送超时；计划扫描依次执行，无响应的目标不能拖住之后的扫描
+const DELIVERY_TIMEOUT: Duration = Duration::from_secs(30);
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum WebhookKind {
+    // 发送 {"text": ...}，适用于 Slack 及兼容的 incoming webhook
+    Slack,
+    // 以 JSON 发送完整的通知内容
+    #[default]
+    Generic,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct Webhook {
+    pub url: String,
+    #[serde(default)]
+    pub kind: WebhookKind,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SmtpSettings {
+    pub host: String,
+    #[serde(default = "default_smtp_port")]
+    pub port: u16,
+    #[serde(default)]
+    pub username: Option<String>,
+    // 为空时读取 SEARCH_TOOL_SMTP_PASSWORD 环境变量，避免把密码写入设置文件
+    #[serde(default)]
+    pub password: Option<String>,
+    pub from: String,
+    pub to: Vec<String>,
+}
+
+fn default_smtp_port() -> u16 {
+    587
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+#[serde(default)]
+pub struct AlertSettings {
+    pub webhooks: Vec<Webhook>,
+    pub smtp: Option<SmtpSettings>,
+    // 总大小较上次扫描增长超过该百分比时发送增长告警
+    pub growth_alert_percent: Option<f64>,
+    // 每次计划扫描完成后都发送摘要；为 false 时只在有告警时发送
+    pub send_summary: bool,
+}
+
+// 地址中的主机（含端口），不含用户信息、路径和查询，可以写入日志
+pub fn url_host(url: &str) -> &str {
+    let rest = url.find("://").map_or(url, |i| &url[i + 3..]);
+    let authority = rest.split(['/', '?', '#']).next().unwrap_or_default();
+    authority.rsplit('@').next().unwrap_or_default()
+}
+
+impl AlertSettings {
+    pub fn has_targets(&self) -> bool {
+        !self.webhooks.is_empty() || self.smtp.is_some()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ScanNotification {
+    pub path: String,
+    pub total_size: i64,
+    pub total_size_formatted: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub previous_size: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub growth_percent: Option<f64>,
+    // 增长超过阈值时为 true
+    pub growth_alert: bool,
+    // 命中大小阈值规则的条目（最大的若干项）
+    pub flagged: Vec<Item>,
+}
+
+impl ScanNotification {
+    pub fn subject(&self) -> String {
+        if self.growth_alert {
+            format!("[Search-tool] 增长告警: {}", self.path)
+        } else {
+            format!("[Search-tool] 扫描摘要: {}", self.path)
+        }
+    }
+
+    pub fn text(&self, unit: SizeUnit) -> String {
+        let mut lines = vec![format!("{}: {}", self.path, self.total_size_formatted)];
+        if let (Some(previous), Some(growth)) = (self.previous_size, self.growth_percent) {
+            lines.push(format!(
+                "上次扫描: {} ({:+.1}%)",
+                format_size(previous, unit),
+                growth
+            ));
+        }
+        if !self.flagged.is_empty() {
+            lines.push("超过阈值的条目:".to_string());
+            for item in &self.flagged {
+                lines.push(format!(
+                    "  {} {} [{}]",
+                    format_size(item.size, unit),
+                    item.path,
+                    item.flags.join(", ")
+                ));
+            }
+        }
+        lines.join("\n")
+    }
+}
+
+// 根据本次结果和上次的历史记录生成通知；不需要发送时返回 None
+pub fn build_notification(
+    result: &ScanResult,
+    previous: Option<&HistoryItem>,
+    settings: &AlertSettings,
+    unit: SizeUnit,
+) -> Option<ScanNotification> {
+    let previous_size = previous.map(|item| item.total_size);
+    let growth_percent = previous_size
+        .filter(|&size| size > 0)
+        .map(|size| (result.total_size - size) as f64 / size as f64 * 100.0);
+    let growth_alert = matches!(
+        (growth_percent, settings.growth_alert_percent),
+        (Some(growth), Some(threshold)) if growth > threshold
+    );
+
+    let mut flagged: Vec<Item> = result
+        .items
+        .iter()
+        .filter(|item| !item.flags.is_empty())
+        .cloned()
+        .collect();
+    flagged.sort_by_key(|item| std::cmp::Reverse(item.size));
+    flagged.truncate(MAX_FLAGGED_ITEMS);
+
+    if !settings.send_summary && !growth_alert && flagged.is_empty() {
+        return None;
+    }
+
+    Some(ScanNotification {
+        path: result.path.clone(),
+        total_size: result.total_size,
+        total_size_formatted: format_size(result.total_size, unit),
+        previous_size,
+        growth_percent,
+        growth_alert,
+        flagged,
+    })
+}
+
+// 发送到所有配置的目标，返回失败信息，单个目标失败不影响其他目标
+pub async fn deliver(
+    notification: &ScanNotification,
+    settings: &AlertSettings,
+    unit: SizeUnit,
+) -> Vec<String> {
+    let mut errors = Vec::new();
+    let text = notification.text(unit);
+
+    let client = reqwest::Client::builder()
+        .timeout(DELIVERY_TIMEOUT)
+        .connect_timeout(CONNECT_TIMEOUT)
+        .build();
+    for (i, webhook) in settings.webhooks.iter().enumerate() {
+        let client = match &client {
+            Ok(client) => client,
+            Err(e) => {
+                errors.push(format!("webhook #{}: {}", i + 1, e));
+                continue;
+            }
+        };
+        let request = match webhook.kind {
+            WebhookKind::Slack => client
+                .post(&webhook.url)
+                .json(&serde_json::json!({ "text": text })),
+            WebhookKind::Generic => client.post(&webhook.url).json(notification),
+        };
+        let sent = request
+            .send()
+            .await
+            .and_then(|response| response.error_for_status());
+        // 地址的路径可能就是凭据，错误信息中只写序号、类型和主机
+        if let Err(e) = sent {
+            errors.push(format!(
+                "webhook #{} ({:?}, {}): {}",
+                i + 1,
+                webhook.kind,
+                url_host(&webhook.url),
+                e.without_url()
+            ));
+        }
+    }
+
+    if let Some(smtp) = &settings.smtp {
+        if let Err(e) = send_mail(smtp, &notification.subject(), text).await {
+            errors.push(format!("smtp {}: {}", smtp.host, e));
+        }
+    }
+
+    errors
+}
+
+async fn send_mail(
+    smtp: &SmtpSettings,
+    subject: &str,
+    body: String,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let mut builder = Message::builder()
+        .from(smtp.from.parse::<Mailbox>()?)
+        .subject(subject);
+    for to in &smtp.to {
+        builder = builder.to(to.parse::<Mailbox>()?);
+    }
+    let message = builder.body(body)?;
+
+    let mut transport =
+        AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&smtp.host)?
+            .port(smtp.port)
+            .timeout(Some(DELIVERY_TIMEOUT));
+    if let Some(username) = &smtp.username {
+        let password = smtp
+            .password
+            .clone()
+            .or_else(|| std::env::var("SEARCH_TOOL_SMTP_PASSWORD").ok())
+            .unwrap_or_default();
+        transport = transport.credentials(Credentials::new(username.clone(), password));
+    }
+    transport.build().send(message).await?;
+
+    Ok(())
+}
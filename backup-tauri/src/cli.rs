@@ -1,8 +1,38 @@
-use search_tool::scan::{scan_directory, format_size};
+use search_tool::diff::{compare_trees, diff_items};
+use search_tool::filter::{self, FileCategory, Filter};
+use search_tool::observer::{JsonlAuditObserver, ScanObserver};
+use search_tool::report::render_html;
+use search_tool::scan::{
+    scan_directory_observed, format_size, parse_size, shape_result, top_by_extension, FlagRule,
+    FlagTarget, HistoryItem, Item, ScanOptions, SizeUnit, SortKey,
+};
+use search_tool::settings::{settings_path, Settings};
 use std::io::{self, Write};
+use std::sync::{Arc, LazyLock};
 
 #[tokio::main]
 async fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    // 没有子命令时是交互模式或 --preset，只有它们输出可过滤的列表
+    if args.first().is_some_and(|arg| !arg.starts_with("--")) {
+        reject_filter_flags(&args);
+    }
+
+    match args.first().map(String::as_str) {
+        Some("baseline") => run_baseline(&args[1..]).await,
+        Some("diff") => run_diff(&args[1..]).await,
+        Some("compare") => run_compare(&args[1..]).await,
+        Some("top") => run_top(&args[1..]).await,
+        Some("du") => run_du(&args[1..]).await,
+        Some("bench") => run_bench(&args[1..]).await,
+        Some("report") => run_report(&args[1..]).await,
+        Some("--preset") => run_preset(&args[1..]).await,
+        _ => run_interactive().await,
+    }
+}
+
+async fn run_interactive() {
     // 获取用户输入目录路径
     print!("Enter directory path: ");
     io::stdout().flush().unwrap();
@@ -21,16 +51,17 @@ async fn main() {
     }
 
     // 扫描目录
-    match scan_directory(path).await {
+    match scan_directory_observed(path, &default_options(), &cli_observers()).await {
         Ok(result) => {
             // 格式化输出结果
-            for item in &result.items {
+            for item in &filter::filter_items(&result.items, &cli_filters()) {
                 let suffix = if item.is_dir { " (dir)" } else { " (file)" };
                 println!(
-                    "{:10} {}{}",
-                    format_size(item.size),
+                    "{:10} {}{}{}",
+                    format_size(item.size, size_unit()),
                     item.path,
-                    suffix
+                    suffix,
+                    flag_suffix(item)
                 );
             }
         }
@@ -40,3 +71,427 @@ async fn main() {
         }
     }
 }
+
+// search-tool-cli --preset <name>：按设置中的预设扫描
+async fn run_preset(args: &[String]) {
+    let name = match args.first() {
+        Some(name) => name.as_str(),
+        None => usage("--preset <name>"),
+    };
+    let Some(preset) = SETTINGS.preset(name).cloned() else {
+        eprintln!("Error: unknown preset '{}'", name);
+        let names: Vec<&str> = SETTINGS.presets.iter().map(|p| p.name.as_str()).collect();
+        eprintln!("Available presets: {}", names.join(", "));
+        std::process::exit(1);
+    };
+
+    let mut options = preset.options;
+    apply_cli_flags(&mut options);
+    SETTINGS.apply_scan_defaults(&mut options);
+    let observers = cli_observers();
+    let scan = scan_directory_observed(preset.path.trim(), &options, &observers);
+    let mut result = match scan.await {
+        Ok(result) => result,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+    };
+    shape_result(&mut result, preset.depth, SortKey::Size);
+    let result = filter::apply(&result, &cli_filters());
+
+    for item in &result.items {
+        let suffix = if item.is_dir { " (dir)" } else { " (file)" };
+        println!(
+            "{:10} {}{}{}",
+            format_size(item.size, size_unit()),
+            item.path,
+            suffix,
+            flag_suffix(item)
+        );
+    }
+}
+
+// search-tool-cli baseline <path> <file>：扫描并保存基线快照
+async fn run_baseline(args: &[String]) {
+    let (path, file) = match args {
+        [path, file] => (path.as_str(), file.as_str()),
+        _ => usage("baseline <path> <file>"),
+    };
+
+    let result = scan_or_exit(path).await;
+    let snapshot = HistoryItem {
+        path: path.to_string(),
+        scan_time: chrono::Utc::now(),
+        total_size: result.total_size,
+        size_format: result.total_size_formatted,
+        items: result.items,
+        note: None,
+    };
+
+    let json = serde_json::to_string(&snapshot).expect("Failed to serialize snapshot");
+    if let Err(e) = std::fs::write(file, json) {
+        eprintln!("Error: {}", e);
+        std::process::exit(1);
+    }
+    println!("Baseline saved to {} ({} items)", file, snapshot.items.len());
+}
+
+// search-tool-cli diff <path> <file>：重新扫描并与基线快照比较
+async fn run_diff(args: &[String]) {
+    let (path, file) = match args {
+        [path, file] => (path.as_str(), file.as_str()),
+        _ => usage("diff <path> <baseline-file>"),
+    };
+
+    let baseline: HistoryItem = match std::fs::read_to_string(file)
+        .map_err(|e| e.to_string())
+        .and_then(|json| serde_json::from_str(&json).map_err(|e| e.to_string()))
+    {
+        Ok(snapshot) => snapshot,
+        Err(e) => {
+            eprintln!("Error: failed to load baseline {}: {}", file, e);
+            std::process::exit(1);
+        }
+    };
+
+    let current = scan_or_exit(path).await;
+    let diff = diff_items(&baseline.items, &current.items);
+
+    println!("Baseline: {}", baseline.scan_time.format("%Y-%m-%d %H:%M:%S"));
+    for item in &diff.added {
+        println!("+ {:10} {}", format_size(item.size, size_unit()), item.path);
+    }
+    for item in &diff.removed {
+        println!("- {:10} {}", format_size(item.size, size_unit()), item.path);
+    }
+    for item in &diff.resized {
+        let sign = if item.delta >= 0 { "+" } else { "-" };
+        println!(
+            "~ {:10} {} ({}{})",
+            format_size(item.new_size, size_unit()),
+            item.path,
+            sign,
+            format_size(item.delta.abs(), size_unit())
+        );
+    }
+
+    let sign = if diff.size_delta >= 0 { "+" } else { "-" };
+    println!(
+        "{} added, {} removed, {} resized, total {}{}",
+        diff.added.len(),
+        diff.removed.len(),
+        diff.resized.len(),
+        sign,
+        format_size(diff.size_delta.abs(), size_unit())
+    );
+}
+
+// search-tool-cli compare <A> <B>：扫描两棵目录树并列出差异，可用于校验备份或镜像
+async fn run_compare(args: &[String]) {
+    let (left, right) = match args {
+        [left, right, ..] if !left.starts_with("--") && !right.starts_with("--") => {
+            (left.as_str(), right.as_str())
+        }
+        _ => usage("compare <A> <B>"),
+    };
+
+    let (left, right) = tokio::join!(scan_or_exit(left), scan_or_exit(right));
+    let comparison = compare_trees(&left, &right);
+
+    for item in &comparison.only_in_left {
+        let suffix = if item.is_dir { "/" } else { "" };
+        println!("< {:10} {}{}", format_size(item.size, size_unit()), item.path, suffix);
+    }
+    for item in &comparison.only_in_right {
+        let suffix = if item.is_dir { "/" } else { "" };
+        println!("> {:10} {}{}", format_size(item.size, size_unit()), item.path, suffix);
+    }
+    for item in &comparison.mismatched {
+        println!(
+            "~ {:10} {} ({} in A)",
+            format_size(item.new_size, size_unit()),
+            item.path,
+            format_size(item.old_size, size_unit())
+        );
+    }
+
+    println!(
+        "{} only in A, {} only in B, {} size mismatches",
+        comparison.only_in_left.len(),
+        comparison.only_in_right.len(),
+        comparison.mismatched.len()
+    );
+    // 存在差异时以非零状态退出，便于脚本判断
+    if !comparison.is_identical() {
+        std::process::exit(1);
+    }
+}
+
+// search-tool-cli top <path> --ext <ext> [--top N]：列出指定扩展名的最大文件
+async fn run_top(args: &[String]) {
+    let usage_text = "top <path> --ext <ext> [--top N]";
+    let path = match args.first() {
+        Some(path) if !path.starts_with("--") => path.as_str(),
+        _ => usage(usage_text),
+    };
+    let ext = flag_value(args, "--ext").unwrap_or_else(|| usage(usage_text));
+    let n = match flag_value(args, "--top") {
+        Some(n) => n.parse().unwrap_or_else(|_| usage(usage_text)),
+        None => 20,
+    };
+
+    let result = scan_or_exit(path).await;
+    let report = top_by_extension(&result, ext, n, size_unit());
+
+    for item in &report.items {
+        println!(
+            "{:10} {}{}",
+            format_size(item.size, size_unit()),
+            item.path,
+            flag_suffix(item)
+        );
+    }
+    println!(
+        "{} .{} files, total {}",
+        report.count, report.extension, report.total_size_formatted
+    );
+}
+
+// search-tool-cli du <path> [--depth N] [--threshold SIZE]：类似 du 的按目录汇总输出
+async fn run_du(args: &[String]) {
+    let usage_text = "du <path> [--depth N] [--threshold SIZE]";
+    let path = match args.first() {
+        Some(path) if !path.starts_with("--") => path.as_str(),
+        _ => usage(usage_text),
+    };
+    let depth = flag_value(args, "--depth")
+        .map(|n| n.parse::<usize>().ok().filter(|&n| n > 0).unwrap_or_else(|| usage(usage_text)));
+    let threshold = match flag_value(args, "--threshold") {
+        Some(size) => parse_size(size).unwrap_or_else(|| usage(usage_text)),
+        None => 0,
+    };
+
+    let mut result = scan_or_exit(path).await;
+    shape_result(&mut result, depth, SortKey::Size);
+
+    let rows: Vec<(String, String)> = result
+        .items
+        .iter()
+        .filter(|item| item.is_dir && item.size >= threshold)
+        .map(|item| {
+            let full = std::path::Path::new(&result.path).join(&item.path);
+            (format_size(item.size, size_unit()), full.to_string_lossy().to_string())
+        })
+        .collect();
+    let total = format_size(result.total_size, size_unit());
+    let width = rows.iter().map(|(size, _)| size.len()).fold(total.len(), usize::max);
+
+    for (size, path) in &rows {
+        println!("{:>width$}  {}", size, path);
+    }
+    println!("{:>width$}  total", total);
+}
+
+// search-tool-cli bench <path> [--runs N]：重复扫描并输出各阶段耗时
+async fn run_bench(args: &[String]) {
+    let usage_text = "bench <path> [--runs N]";
+    let path = match args.first() {
+        Some(path) if !path.starts_with("--") => path.as_str(),
+        _ => usage(usage_text),
+    };
+    let runs: usize = match flag_value(args, "--runs") {
+        Some(n) => n.parse().ok().filter(|&n| n > 0).unwrap_or_else(|| usage(usage_text)),
+        None => 3,
+    };
+
+    let mut wall_times = Vec::with_capacity(runs);
+    for run in 1..=runs {
+        let result = scan_or_exit(path).await;
+        let Some(stats) = result.stats else { continue };
+        println!(
+            "run {}: {} files, {} dirs, {} errors, {:.0} files/s, walk {:.3}s, aggregate {:.3}s, sort {:.3}s, wall {:.3}s",
+            run,
+            stats.files,
+            stats.dirs_visited,
+            stats.errors,
+            stats.files_per_sec,
+            stats.walk_time,
+            stats.aggregate_time,
+            stats.sort_time,
+            stats.wall_time
+        );
+        wall_times.push(stats.wall_time);
+        if run == runs {
+            if let Some(peak) = stats.peak_memory {
+                println!("peak memory: {}", format_size(peak as i64, size_unit()));
+            }
+        }
+    }
+
+    if !wall_times.is_empty() {
+        let mean = wall_times.iter().sum::<f64>() / wall_times.len() as f64;
+        let min = wall_times.iter().copied().fold(f64::INFINITY, f64::min);
+        let max = wall_times.iter().copied().fold(0.0, f64::max);
+        println!("wall time: mean {:.3}s, min {:.3}s, max {:.3}s", mean, min, max);
+    }
+}
+
+// search-tool-cli report <path> <output.html>：扫描并导出 HTML 报告
+async fn run_report(args: &[String]) {
+    let (path, output) = match args {
+        [path, output, ..] => (path.as_str(), output.as_str()),
+        _ => usage("report <path> <output.html>"),
+    };
+
+    let result = scan_or_exit(path).await;
+    if let Err(e) = std::fs::write(output, render_html(&result, size_unit())) {
+        eprintln!("Error: {}", e);
+        std::process::exit(1);
+    }
+    println!("Report saved to {}", output);
+}
+
+// 读取形如 `--name value` 的参数值
+fn flag_value<'a>(args: &'a [String], name: &str) -> Option<&'a str> {
+    args.iter()
+        .position(|arg| arg == name)
+        .and_then(|i| args.get(i + 1))
+        .map(String::as_str)
+}
+
+// 与服务端共用设置文件
+static SETTINGS: LazyLock<Settings> = LazyLock::new(|| Settings::load(&settings_path()));
+
+// 命令行参数之外使用设置中的默认排除项、符号链接策略和阈值规则
+fn default_options() -> ScanOptions {
+    let mut options = ScanOptions::default();
+    apply_cli_flags(&mut options);
+    SETTINGS.apply_scan_defaults(&mut options);
+    options
+}
+
+// --flag-over SIZE：标记超过该大小的条目，可出现在任意子命令后
+fn apply_cli_flags(options: &mut ScanOptions) {
+    let args: Vec<String> = std::env::args().collect();
+    let Some(size) = flag_value(&args, "--flag-over") else {
+        return;
+    };
+    let Some(threshold) = parse_size(size) else {
+        eprintln!("Error: invalid size '{}'", size);
+        std::process::exit(2);
+    };
+    options.flag_rules = Some(vec![FlagRule {
+        name: format!("over {}", size),
+        target: FlagTarget::Any,
+        threshold,
+    }]);
+}
+
+// 结果过滤参数，只用于交互模式和 --preset 的列表输出
+const FILTER_FLAGS: &[&str] = &[
+    "--min-size",
+    "--exts",
+    "--name",
+    "--older-than",
+    "--category",
+    "--dirs-only",
+    "--files-only",
+];
+
+// 子命令不过滤结果，带过滤参数时报错而不是静默忽略
+fn reject_filter_flags(args: &[String]) {
+    if let Some(flag) = args.iter().find(|arg| FILTER_FLAGS.contains(&arg.as_str())) {
+        eprintln!("Error: {} only applies to the interactive listing and --preset", flag);
+        std::process::exit(2);
+    }
+}
+
+// --min-size SIZE、--exts a,b、--name TEXT、--older-than DAYS、--category NAME、
+// --dirs-only、--files-only；--exts 与 top 子命令的 --ext 区分
+fn cli_filters() -> Vec<Filter> {
+    let args: Vec<String> = std::env::args().collect();
+    let mut filters = Vec::new();
+    if let Some(size) = flag_value(&args, "--min-size") {
+        match parse_size(size) {
+            Some(size) => filters.push(Filter::MinSize(size)),
+            None => {
+                eprintln!("Error: invalid size '{}'", size);
+                std::process::exit(2);
+            }
+        }
+    }
+    if let Some(exts) = flag_value(&args, "--exts") {
+        filters.push(Filter::ExtIn(exts.split(',').map(String::from).collect()));
+    }
+    if let Some(name) = flag_value(&args, "--name") {
+        filters.push(Filter::NameMatches(name.to_string()));
+    }
+    if let Some(days) = flag_value(&args, "--older-than") {
+        match days.parse() {
+            Ok(days) => filters.push(Filter::OlderThan(days)),
+            Err(_) => {
+                eprintln!("Error: invalid number of days '{}'", days);
+                std::process::exit(2);
+            }
+        }
+    }
+    if let Some(category) = flag_value(&args, "--category") {
+        match category.parse::<FileCategory>() {
+            Ok(category) => filters.push(Filter::Category(category)),
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                std::process::exit(2);
+            }
+        }
+    }
+    if args.iter().any(|arg| arg == "--dirs-only") {
+        filters.push(Filter::IsDir(true));
+    }
+    if args.iter().any(|arg| arg == "--files-only") {
+        filters.push(Filter::IsDir(false));
+    }
+    filters
+}
+
+// --audit-log FILE：把扫描事件以 JSONL 追加到文件，可出现在任意子命令后
+fn cli_observers() -> Vec<Arc<dyn ScanObserver>> {
+    let args: Vec<String> = std::env::args().collect();
+    let Some(file) = flag_value(&args, "--audit-log") else {
+        return Vec::new();
+    };
+    match JsonlAuditObserver::open(std::path::Path::new(file)) {
+        Ok(observer) => vec![Arc::new(observer)],
+        Err(e) => {
+            eprintln!("Error: cannot open audit log '{}': {}", file, e);
+            std::process::exit(2);
+        }
+    }
+}
+
+fn flag_suffix(item: &Item) -> String {
+    if item.flags.is_empty() {
+        String::new()
+    } else {
+        format!(" [{}]", item.flags.join(", "))
+    }
+}
+
+fn size_unit() -> SizeUnit {
+    SETTINGS.size_unit
+}
+
+async fn scan_or_exit(path: &str) -> search_tool::scan::ScanResult {
+    match scan_directory_observed(path.trim(), &default_options(), &cli_observers()).await {
+        Ok(result) => result,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+fn usage(command: &str) -> ! {
+    eprintln!("Usage: search-tool-cli {}", command);
+    std::process::exit(2);
+}
@@ -0,0 +1,164 @@
+use crate::scan::{Item, ScanResult};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use utoipa::ToSchema;
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ResizedItem {
+    pub path: String,
+    pub old_size: i64,
+    pub new_size: i64,
+    pub delta: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SnapshotDiff {
+    pub added: Vec<Item>,
+    pub removed: Vec<Item>,
+    pub resized: Vec<ResizedItem>,
+    pub size_delta: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct TreeComparison {
+    pub left: String,
+    pub right: String,
+    pub only_in_left: Vec<Item>,
+    pub only_in_right: Vec<Item>,
+    pub mismatched: Vec<ResizedItem>,
+}
+
+impl TreeComparison {
+    pub fn is_identical(&self) -> bool {
+        self.only_in_left.is_empty()
+            && self.only_in_right.is_empty()
+            && self.mismatched.is_empty()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct BaselineComparison {
+    pub path: String,
+    #[serde(with = "chrono::serde::ts_seconds")]
+    #[schema(value_type = i64)]
+    pub baseline_time: chrono::DateTime<chrono::Utc>,
+    #[serde(flatten)]
+    pub diff: SnapshotDiff,
+}
+
+// 比较两次快照中的文件（目录大小由文件派生，不单独比较）
+pub fn diff_items(old: &[Item], new: &[Item]) -> SnapshotDiff {
+    let old_files: HashMap<&str, &Item> = old
+        .iter()
+        .filter(|item| !item.is_dir)
+        .map(|item| (item.path.as_str(), item))
+        .collect();
+    let new_files: HashMap<&str, &Item> = new
+        .iter()
+        .filter(|item| !item.is_dir)
+        .map(|item| (item.path.as_str(), item))
+        .collect();
+
+    let mut added = Vec::new();
+    let mut resized = Vec::new();
+    let mut size_delta = 0i64;
+
+    for (path, item) in new_files.iter() {
+        match old_files.get(path) {
+            None => {
+                size_delta += item.size;
+                added.push((*item).clone());
+            }
+            Some(old_item) if old_item.size != item.size => {
+                let delta = item.size - old_item.size;
+                size_delta += delta;
+                resized.push(ResizedItem {
+                    path: path.to_string(),
+                    old_size: old_item.size,
+                    new_size: item.size,
+                    delta,
+                });
+            }
+            Some(_) => {}
+        }
+    }
+
+    let mut removed: Vec<Item> = old_files
+        .iter()
+        .filter(|(path, _)| !new_files.contains_key(*path))
+        .map(|(_, item)| (*item).clone())
+        .collect();
+    size_delta -= removed.iter().map(|item| item.size).sum::<i64>();
+
+    added.sort_by_key(|item| std::cmp::Reverse(item.size));
+    removed.sort_by_key(|item| std::cmp::Reverse(item.size));
+    resized.sort_by_key(|item| std::cmp::Reverse(item.delta.abs()));
+
+    SnapshotDiff {
+        added,
+        removed,
+        resized,
+        size_delta,
+    }
+}
+
+// 比较两棵目录树（按相对路径）：目录整体缺失时只报告该目录，不再逐个列出其下条目
+pub fn compare_trees(left: &ScanResult, right: &ScanResult) -> TreeComparison {
+    let left_items: HashMap<&str, &Item> =
+        left.items.iter().map(|item| (item.path.as_str(), item)).collect();
+    let right_items: HashMap<&str, &Item> =
+        right.items.iter().map(|item| (item.path.as_str(), item)).collect();
+
+    let mut mismatched: Vec<ResizedItem> = left_items
+        .iter()
+        .filter_map(|(path, a)| {
+            let b = right_items.get(path)?;
+            // 目录大小由文件派生，只比较文件；同名但类型不同也视为不一致
+            if (a.is_dir && b.is_dir) || (a.is_dir == b.is_dir && a.size == b.size) {
+                return None;
+            }
+            Some(ResizedItem {
+                path: path.to_string(),
+                old_size: a.size,
+                new_size: b.size,
+                delta: b.size - a.size,
+            })
+        })
+        .collect();
+    mismatched.sort_by(|a, b| a.path.cmp(&b.path));
+
+    TreeComparison {
+        left: left.path.clone(),
+        right: right.path.clone(),
+        only_in_left: only_in(&left_items, &right_items),
+        only_in_right: only_in(&right_items, &left_items),
+        mismatched,
+    }
+}
+
+fn only_in(this: &HashMap<&str, &Item>, other: &HashMap<&str, &Item>) -> Vec<Item> {
+    let mut missing: Vec<&Item> = this
+        .iter()
+        .filter(|(path, _)| !other.contains_key(*path))
+        .map(|(_, item)| *item)
+        .collect();
+    // 按路径分量排序，保证目录之后紧跟其子条目
+    missing.sort_by(|a, b| a.path.split(['/', '\\']).cmp(b.path.split(['/', '\\'])));
+
+    let mut result: Vec<Item> = Vec::new();
+    let mut missing_dir: Option<String> = None;
+    for item in missing {
+        let covered = missing_dir.as_deref().is_some_and(|dir| {
+            let rest = item.path.strip_prefix(dir).unwrap_or("");
+            rest.starts_with('/') || rest.starts_with('\\')
+        });
+        if covered {
+            continue;
+        }
+        if item.is_dir {
+            missing_dir = Some(item.path.clone());
+        }
+        result.push(item.clone());
+    }
+    result
+}
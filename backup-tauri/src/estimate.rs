@@ -0,0 +1,149 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use utoipa::ToSchema;
+
+// 快速估算模式下，条目数超过该值的目录只统计随机抽取的部分条目
+pub const SAMPLE_THRESHOLD: usize = 1000;
+pub const SAMPLE_SIZE: usize = 200;
+
+// 约 95% 置信水平对应的正态分位数
+const Z_95: f64 = 1.96;
+
+// 按抽样推算的大小的置信区间（约 95%）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct SizeEstimate {
+    pub low: i64,
+    pub high: i64,
+}
+
+// 被抽样的目录：entries 个条目中只统计了 sampled 中的条目
+struct SampledDir {
+    path: PathBuf,
+    entries: usize,
+    sampled: Vec<PathBuf>,
+    // 该目录自身的权重，即上层抽样累积的放大倍数
+    weight: f64,
+}
+
+// xorshift 随机数，抽样不需要密码学强度
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+}
+
+// 遍历期间记录抽样信息。抽样目录下的文件按权重（条目数 / 抽样数，逐层相乘）
+// 计入上层目录，目录的估算大小为累计值除以目录自身的权重
+pub struct Sampling {
+    rng: Mutex<Rng>,
+    sampled: Mutex<Vec<SampledDir>>,
+    // 权重不为 1 的目录
+    weights: Mutex<HashMap<PathBuf, f64>>,
+}
+
+impl Sampling {
+    pub fn new(root: &Path) -> Self {
+        let mut hasher = DefaultHasher::new();
+        root.hash(&mut hasher);
+        chrono::Utc::now().timestamp_nanos_opt().hash(&mut hasher);
+        Sampling {
+            rng: Mutex::new(Rng(hasher.finish() | 1)),
+            sampled: Mutex::new(Vec::new()),
+            weights: Mutex::new(HashMap::new()),
+        }
+    }
+
+    // 条目过多时不放回地随机保留 SAMPLE_SIZE 个，返回子条目的权重
+    pub fn sample<T>(
+        &self,
+        dir: &Path,
+        entries: &mut Vec<T>,
+        path_of: impl Fn(&T) -> PathBuf,
+    ) -> f64 {
+        let weight = self.weight(dir);
+        let total = entries.len();
+        if total <= SAMPLE_THRESHOLD {
+            return weight;
+        }
+
+        let mut rng = self.rng.lock().unwrap();
+        for i in 0..SAMPLE_SIZE {
+            let j = i + (rng.next() % (total - i) as u64) as usize;
+            entries.swap(i, j);
+        }
+        entries.truncate(SAMPLE_SIZE);
+
+        self.sampled.lock().unwrap().push(SampledDir {
+            path: dir.to_path_buf(),
+            entries: total,
+            sampled: entries.iter().map(path_of).collect(),
+            weight,
+        });
+        weight * total as f64 / SAMPLE_SIZE as f64
+    }
+
+    // 进入子目录前记录其权重
+    pub fn enter(&self, dir: &Path, weight: f64) {
+        if weight != 1.0 {
+            self.weights.lock().unwrap().insert(dir.to_path_buf(), weight);
+        }
+    }
+
+    pub fn weight(&self, dir: &Path) -> f64 {
+        self.weights.lock().unwrap().get(dir).copied().unwrap_or(1.0)
+    }
+
+    // 受抽样影响的目录（含根目录）的置信区间。size_of 返回文件的大小或目录的估算大小，
+    // 被过滤掉的条目返回 None 并按 0 计
+    pub fn bounds(
+        &self,
+        root: &Path,
+        size_of: impl Fn(&Path) -> Option<i64>,
+    ) -> HashMap<PathBuf, SizeEstimate> {
+        let mut variance: HashMap<PathBuf, f64> = HashMap::new();
+        for dir in self.sampled.lock().unwrap().iter() {
+            let n = dir.entries as f64;
+            let k = dir.sampled.len() as f64;
+            let values: Vec<f64> = dir
+                .sampled
+                .iter()
+                .map(|path| size_of(path).unwrap_or(0) as f64)
+                .collect();
+            let mean = values.iter().sum::<f64>() / k;
+            let sample_variance =
+                values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / (k - 1.0).max(1.0);
+            // 有限总体校正后的总量估计方差
+            let dir_variance = n * n * (1.0 - k / n) * sample_variance / k;
+
+            for ancestor in dir.path.ancestors() {
+                let scale = dir.weight / self.weight(ancestor);
+                *variance.entry(ancestor.to_path_buf()).or_default() +=
+                    dir_variance * scale * scale;
+                if ancestor == root {
+                    break;
+                }
+            }
+        }
+
+        variance
+            .into_iter()
+            .map(|(path, variance)| {
+                let size = size_of(&path).unwrap_or(0);
+                let margin = (Z_95 * variance.sqrt()).round() as i64;
+                let estimate = SizeEstimate {
+                    low: (size - margin).max(0),
+                    high: size + margin,
+                };
+                (path, estimate)
+            })
+            .collect()
+    }
+}
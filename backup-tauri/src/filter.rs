@@ -0,0 +1,147 @@
+use crate::scan::{Item, ScanResult};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use utoipa::ToSchema;
+
+// 按扩展名划分的文件大类
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum FileCategory {
+    Image,
+    Video,
+    Audio,
+    Document,
+    Archive,
+    Code,
+    Executable,
+    Other,
+}
+
+impl FileCategory {
+    pub fn of(path: &str) -> Self {
+        let extension = Path::new(path)
+            .extension()
+            .map(|e| e.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        match extension.as_str() {
+            "jpg" | "jpeg" | "png" | "gif" | "bmp" | "webp" | "svg" | "ico" | "tif" | "tiff"
+            | "heic" | "raw" | "psd" => FileCategory::Image,
+            "mp4" | "mkv" | "avi" | "mov" | "wmv" | "flv" | "webm" | "m4v" | "mpg" | "mpeg" => {
+                FileCategory::Video
+            }
+            "mp3" | "wav" | "flac" | "aac" | "ogg" | "m4a" | "wma" | "opus" => FileCategory::Audio,
+            "pdf" | "doc" | "docx" | "xls" | "xlsx" | "ppt" | "pptx" | "odt" | "ods" | "odp"
+            | "txt" | "md" | "rtf" | "csv" | "epub" => FileCategory::Document,
+            "zip" | "rar" | "7z" | "tar" | "gz" | "tgz" | "bz2" | "xz" | "zst" | "iso" | "dmg"
+            | "cab" => FileCategory::Archive,
+            "rs" | "c" | "h" | "cpp" | "hpp" | "cs" | "java" | "kt" | "go" | "py" | "js" | "jsx"
+            | "ts" | "tsx" | "vue" | "html" | "css" | "json" | "toml" | "yaml" | "yml" | "xml"
+            | "sh" | "ps1" | "sql" => FileCategory::Code,
+            "exe" | "msi" | "dll" | "so" | "dylib" | "bin" | "app" | "apk" | "deb" | "rpm" => {
+                FileCategory::Executable
+            }
+            _ => FileCategory::Other,
+        }
+    }
+}
+
+impl std::str::FromStr for FileCategory {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        serde_json::from_value(serde_json::Value::String(s.trim().to_lowercase()))
+            .map_err(|_| format!("未知的文件类别: {}", s))
+    }
+}
+
+// 结果过滤条件，多个条件同时满足才保留条目。界面、命令行和 API 共用
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(tag = "type", content = "value", rename_all = "snake_case")]
+pub enum Filter {
+    // 大小不小于该值（字节）
+    MinSize(i64),
+    // true 只保留目录，false 只保留文件
+    IsDir(bool),
+    // 扩展名（不含点，不区分大小写）在列表中的文件，目录不匹配
+    ExtIn(Vec<String>),
+    // 扩展名不在列表中的条目，目录总是保留
+    ExtNotIn(Vec<String>),
+    // 名称包含该文本（不区分大小写）
+    NameMatches(String),
+    // 修改时间早于该天数之前的文件，没有修改时间的条目不匹配
+    OlderThan(u64),
+    Category(FileCategory),
+}
+
+impl Filter {
+    // 扩展名和名称统一为小写，匹配时不再逐条转换
+    fn normalized(&self) -> Filter {
+        let normalize = |exts: &[String]| {
+            exts.iter()
+                .map(|ext| ext.trim().trim_start_matches('.').to_lowercase())
+                .collect()
+        };
+        match self {
+            Filter::ExtIn(exts) => Filter::ExtIn(normalize(exts)),
+            Filter::ExtNotIn(exts) => Filter::ExtNotIn(normalize(exts)),
+            Filter::NameMatches(text) => Filter::NameMatches(text.trim().to_lowercase()),
+            other => other.clone(),
+        }
+    }
+
+    fn matches(&self, item: &Item, now: i64) -> bool {
+        let path = Path::new(&item.path);
+        let extension = || {
+            path.extension()
+                .map(|e| e.to_string_lossy().to_lowercase())
+                .unwrap_or_default()
+        };
+        match self {
+            Filter::MinSize(size) => item.size >= *size,
+            Filter::IsDir(is_dir) => item.is_dir == *is_dir,
+            Filter::ExtIn(exts) => !item.is_dir && exts.contains(&extension()),
+            Filter::ExtNotIn(exts) => item.is_dir || !exts.contains(&extension()),
+            Filter::NameMatches(text) => path
+                .file_name()
+                .is_some_and(|name| name.to_string_lossy().to_lowercase().contains(text)),
+            Filter::OlderThan(days) => {
+                let cutoff = now - *days as i64 * 86400;
+                item.modified.is_some_and(|modified| modified < cutoff)
+            }
+            Filter::Category(category) => {
+                !item.is_dir && FileCategory::of(&item.path) == *category
+            }
+        }
+    }
+}
+
+// 满足全部条件的条目，保持原有顺序
+pub fn filter_items<'a>(
+    items: impl IntoIterator<Item = &'a Item>,
+    filters: &[Filter],
+) -> Vec<Item> {
+    let filters: Vec<Filter> = filters.iter().map(Filter::normalized).collect();
+    let now = chrono::Utc::now().timestamp();
+    items
+        .into_iter()
+        .filter(|item| filters.iter().all(|filter| filter.matches(item, now)))
+        .cloned()
+        .collect()
+}
+
+// 只保留满足条件的条目；总大小和根目录统计仍是整次扫描的，树图不再适用而移除
+pub fn apply(result: &ScanResult, filters: &[Filter]) -> ScanResult {
+    ScanResult {
+        items: filter_items(&result.items, filters),
+        total_size: result.total_size,
+        total_size_formatted: result.total_size_formatted.clone(),
+        scan_time: result.scan_time,
+        path: result.path.clone(),
+        treemap: None,
+        result_id: result.result_id.clone(),
+        stats: result.stats.clone(),
+        root: result.root.clone(),
+        audit: result.audit.clone(),
+        estimate: result.estimate,
+    }
+}
@@ -0,0 +1,84 @@
+use crate::scan::ScanProgress;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use utoipa::ToSchema;
+
+// 正在进行的扫描任务，files 只在带进度的扫描中提供
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct JobInfo {
+    pub id: String,
+    pub path: String,
+    #[serde(with = "chrono::serde::ts_seconds")]
+    #[schema(value_type = i64)]
+    pub started: chrono::DateTime<chrono::Utc>,
+    pub elapsed_secs: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub files: Option<usize>,
+}
+
+struct Job {
+    path: String,
+    started: chrono::DateTime<chrono::Utc>,
+    instant: Instant,
+    progress: Option<Arc<ScanProgress>>,
+}
+
+// 登记进行中的扫描，便于排查长时间未结束的任务
+#[derive(Default)]
+pub struct JobRegistry {
+    jobs: Mutex<HashMap<String, Job>>,
+}
+
+impl JobRegistry {
+    // 返回的守卫被丢弃时（扫描完成、失败或被取消）任务自动移除
+    pub fn start(
+        self: &Arc<Self>,
+        path: &str,
+        progress: Option<Arc<ScanProgress>>,
+    ) -> JobGuard {
+        let id = uuid::Uuid::new_v4().to_string();
+        let job = Job {
+            path: path.to_string(),
+            started: chrono::Utc::now(),
+            instant: Instant::now(),
+            progress,
+        };
+        self.jobs.lock().unwrap().insert(id.clone(), job);
+        JobGuard {
+            registry: Arc::clone(self),
+            id,
+        }
+    }
+
+    // 按开始时间排列，最早的在前
+    pub fn list(&self) -> Vec<JobInfo> {
+        let mut jobs: Vec<JobInfo> = self
+            .jobs
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(id, job)| JobInfo {
+                id: id.clone(),
+                path: job.path.clone(),
+                started: job.started,
+                elapsed_secs: job.instant.elapsed().as_secs_f64(),
+                files: job.progress.as_ref().map(|progress| progress.files()),
+            })
+            .collect();
+        jobs.sort_by_key(|job| job.started);
+        jobs
+    }
+}
+
+pub struct JobGuard {
+    registry: Arc<JobRegistry>,
+    id: String,
+}
+
+impl Drop for JobGuard {
+    fn drop(&mut self) {
+        self.registry.jobs.lock().unwrap().remove(&self.id);
+    }
+}
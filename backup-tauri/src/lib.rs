@@ -1 +1,16 @@
+pub mod alerts;
+pub mod audit;
+pub mod diff;
+pub mod estimate;
+pub mod filter;
+pub mod jobs;
+pub mod observer;
+pub mod paths;
+pub mod priority;
+pub mod report;
 pub mod scan;
+pub mod settings;
+pub mod storage;
+pub mod store;
+pub mod stream;
+pub mod treemap;
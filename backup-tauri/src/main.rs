@@ -1,35 +1,591 @@
 use axum::{
-    extract::State,
-    http::StatusCode,
-    response::{Html, IntoResponse, Json},
-    routing::{get, post},
+    body::Body,
+    extract::{Extension, Path, Query, Request, State},
+    http::{header, HeaderMap, HeaderValue, Method, StatusCode},
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        Html, IntoResponse, Json, Response,
+    },
+    middleware::{self, Next},
+    routing::{delete, get, post, put},
     Router,
 };
-use search_tool::scan::{scan_directory, HistoryItem, ScanResult};
+use search_tool::scan::{
+    build_trend, configured_threads, format_item_sizes, format_size, format_sizes, scan_by_top_level, scan_directory, scan_directory_with_progress, shape_result, top_by_extension, unused_since, parse_size, ExtensionReport,
+    FlagRule, FlagTarget, HistoryItem, Item, ScanOptions, ScanProgress, RootSummary, ScanResult, ScanSnapshot, ScanStats, SizeFormat, SizeUnit, SortKey, Trend, TrendPoint,
+    UnusedReport,
+};
+use search_tool::audit::{AuditFinding, AuditIssue, AuditReport};
+use search_tool::alerts::{
+    build_notification, deliver, AlertSettings, SmtpSettings, Webhook, WebhookKind,
+};
+use search_tool::diff::{diff_items, ResizedItem, SnapshotDiff};
+use search_tool::estimate::SizeEstimate;
+use search_tool::filter::{self, FileCategory, Filter};
+use search_tool::jobs::{JobInfo, JobRegistry};
+use search_tool::settings::{self, ScanPreset, ScheduledScan, Settings, SymlinkPolicy};
+use search_tool::storage::{
+    self, CacheEntry, CacheStats, CacheStore, HistoryStore, Storage, StorageSettings,
+};
+use search_tool::store::{self, ResultPage};
+use search_tool::treemap::{build_treemap, TreemapNode, TreemapOptions};
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::path::PathBuf;
 use std::sync::Arc;
-use tokio::sync::RwLock;
-use tower_http::{cors::CorsLayer, services::ServeDir};
+use std::time::{Duration, Instant};
+use tokio::sync::{mpsc, watch, RwLock};
+use tokio_stream::wrappers::ReceiverStream;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use tower_http::{compression::CompressionLayer, cors::CorsLayer, services::ServeDir};
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
+use utoipa::{IntoParams, OpenApi, ToSchema};
+
+// 检查计划扫描是否到期的间隔
+const SCHEDULE_TICK: Duration = Duration::from_secs(60);
+
+// 流式扫描默认的阶段性结果推送间隔（秒）
+const DEFAULT_SNAPSHOT_INTERVAL: u64 = 2;
 
-// 历史记录存储
+const NDJSON: &str = "application/x-ndjson";
+
+// 收到关闭信号后等待进行中请求完成的最长时间
+const SHUTDOWN_GRACE: Duration = Duration::from_secs(10);
+
+// 历史记录与扫描结果存储，后端由设置中的 storage 决定
 #[derive(Clone)]
 struct AppState {
-    history: Arc<RwLock<Vec<HistoryItem>>>,
+    storage: Arc<dyn Storage>,
+    settings: Arc<RwLock<Settings>>,
+    settings_path: Arc<PathBuf>,
+    jobs: Arc<JobRegistry>,
+    // 管理接口的令牌，来自环境变量 SEARCH_TOOL_ADMIN_TOKEN；未设置时管理接口不可用
+    admin_token: Option<Arc<String>>,
+    // 以 --read-only 启动时为 true，修改状态的接口全部拒绝
+    read_only: bool,
+    // 收到关闭信号后变为 true
+    shutdown: watch::Receiver<bool>,
+}
+
+impl AppState {
+    fn history(&self, session: &Session) -> Arc<dyn HistoryStore> {
+        self.storage.history(&session.0)
+    }
+
+    fn results(&self, session: &Session) -> Arc<dyn CacheStore> {
+        self.storage.results(&session.0)
+    }
+
+    // 收到关闭信号后完成
+    async fn shutdown_requested(&self) {
+        let mut shutdown = self.shutdown.clone();
+        if shutdown.wait_for(|requested| *requested).await.is_err() {
+            std::future::pending::<()>().await;
+        }
+    }
+}
+
+// 会话 Cookie 名称，脚本也可以用 Authorization: Bearer <令牌> 指定会话
+const SESSION_COOKIE: &str = "search_tool_session";
+
+// 请求所属的存储命名空间，未开启会话隔离时所有请求共享同一空间
+#[derive(Clone)]
+struct Session(String);
+
+impl Session {
+    fn shared() -> Self {
+        Session(String::new())
+    }
+}
+
+// 令牌只接受字母、数字、- 和 _，避免影响存储键
+fn session_token(headers: &HeaderMap) -> Option<String> {
+    let bearer = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    let cookie = || {
+        headers
+            .get_all(header::COOKIE)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(';'))
+            .find_map(|pair| pair.trim().strip_prefix(SESSION_COOKIE)?.strip_prefix('='))
+    };
+    bearer
+        .or_else(cookie)
+        .map(str::trim)
+        .filter(|token| {
+            !token.is_empty()
+                && token.len() <= 128
+                && token
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        })
+        .map(String::from)
+}
+
+// 管理接口要求 Authorization: Bearer <管理令牌>
+async fn admin_layer(State(state): State<AppState>, request: Request, next: Next) -> Response {
+    let Some(expected) = &state.admin_token else {
+        return api_error(StatusCode::NOT_FOUND, "管理接口未启用").into_response();
+    };
+    let token = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(str::trim);
+    if !token.is_some_and(|token| tokens_match(token, expected)) {
+        return api_error(StatusCode::UNAUTHORIZED, "管理令牌无效").into_response();
+    }
+    next.run(request).await
+}
+
+// 比较两个令牌的摘要，耗时与令牌内容无关，无法逐字节试出令牌
+fn tokens_match(token: &str, expected: &str) -> bool {
+    let token = Sha256::digest(token.as_bytes());
+    let expected = Sha256::digest(expected.as_bytes());
+    token
+        .iter()
+        .zip(expected.iter())
+        .fold(0u8, |diff, (a, b)| diff | (a ^ b))
+        == 0
+}
+
+// 会话令牌即会话 Cookie，管理接口只显示其摘要的前几位用于区分会话
+fn session_label(session: &str) -> String {
+    if session.is_empty() {
+        return String::new();
+    }
+    Sha256::digest(session.as_bytes())[..6]
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+// 只读模式下仍然允许的 POST 接口：只查询或扫描，不修改设置、历史注释和文件
+const READ_ONLY_POSTS: &[&str] = &[
+    "/api/scan",
+    "/api/history-item",
+    "/api/trend",
+    "/api/top-by-extension",
+    "/api/stale-files",
+    "/api/unused-files",
+    "/api/presets/:name/run",
+    "/api/results/:id/filter",
+];
+
+// 路由写法中以 : 开头的段匹配任意值
+fn route_matches(pattern: &str, path: &str) -> bool {
+    let mut pattern = pattern.split('/');
+    let mut path = path.trim_end_matches('/').split('/');
+    loop {
+        match (pattern.next(), path.next()) {
+            (None, None) => return true,
+            (Some(expected), Some(actual)) => {
+                if !expected.starts_with(':') && expected != actual {
+                    return false;
+                }
+            }
+            _ => return false,
+        }
+    }
 }
 
-#[derive(Deserialize)]
+// 只读模式下拒绝 GET、HEAD、OPTIONS 以外的请求，READ_ONLY_POSTS 中的接口除外。
+// 挂在整个路由上，之后新增的接口默认同样受限
+async fn read_only_layer(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let method = request.method();
+    let allowed = method == Method::GET
+        || method == Method::HEAD
+        || method == Method::OPTIONS
+        || (method == Method::POST
+            && READ_ONLY_POSTS
+                .iter()
+                .any(|pattern| route_matches(pattern, request.uri().path())));
+    if state.read_only && !allowed {
+        return api_error(StatusCode::FORBIDDEN, "服务以只读模式运行").into_response();
+    }
+    next.run(request).await
+}
+
+// 为请求确定会话；没有有效令牌时分配新会话并通过 Cookie 返回
+async fn session_layer(
+    State(state): State<AppState>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    if !state.settings.read().await.session_isolation {
+        request.extensions_mut().insert(Session::shared());
+        return next.run(request).await;
+    }
+
+    let token = session_token(request.headers());
+    let issued = token.is_none();
+    let session = token.unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+    let cookie = format!(
+        "{}={}; Path=/; HttpOnly; SameSite=Lax; Max-Age=31536000",
+        SESSION_COOKIE, session
+    );
+    request.extensions_mut().insert(Session(session));
+
+    let mut response = next.run(request).await;
+    if issued {
+        if let Ok(value) = HeaderValue::from_str(&cookie) {
+            response.headers_mut().append(header::SET_COOKIE, value);
+        }
+    }
+    response
+}
+
+#[derive(Deserialize, ToSchema)]
 struct ScanRequest {
     path: String,
+    #[serde(default)]
+    treemap: Option<TreemapOptions>,
+    // 只返回不超过该层级的条目
+    #[serde(default)]
+    depth: Option<usize>,
+    #[serde(default)]
+    sort: SortKey,
+    // 格式化大小使用的单位，为空时使用设置中的单位
+    #[serde(default)]
+    unit: Option<SizeUnit>,
+    // none 时省略所有格式化的大小字符串
+    #[serde(default)]
+    format: SizeFormat,
+    #[serde(flatten)]
+    options: ScanOptions,
+}
+
+// GET /api/scan 的查询参数（查询字符串无法与 flatten 配合解析数字，逐项列出）
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct ScanQuery {
+    path: String,
+    depth: Option<usize>,
+    #[serde(default)]
+    sort: SortKey,
+    unit: Option<SizeUnit>,
+    #[serde(default)]
+    format: SizeFormat,
+    older_than_days: Option<u64>,
+    newer_than_days: Option<u64>,
+    #[serde(default)]
+    low_priority: bool,
+    // 逗号分隔的排除名称
+    excludes: Option<String>,
+    follow_symlinks: Option<bool>,
+    // 逗号分隔的扩展名，只统计/不统计这些类型的文件
+    include_ext: Option<String>,
+    exclude_ext: Option<String>,
+    #[serde(default)]
+    record_access_time: bool,
+    // 检查权限过宽的文件和目录
+    #[serde(default)]
+    audit: bool,
+    // 抽样估算条目很多的目录
+    #[serde(default)]
+    quick_estimate: bool,
+    // 流式扫描推送阶段性结果的间隔（秒）
+    interval: Option<u64>,
+}
+
+impl From<ScanQuery> for ScanRequest {
+    fn from(query: ScanQuery) -> Self {
+        ScanRequest {
+            path: query.path,
+            treemap: None,
+            depth: query.depth,
+            sort: query.sort,
+            unit: query.unit,
+            format: query.format,
+            options: ScanOptions {
+                older_than_days: query.older_than_days,
+                newer_than_days: query.newer_than_days,
+                low_priority: query.low_priority,
+                excludes: query.excludes.as_deref().map(split_list),
+                follow_symlinks: query.follow_symlinks,
+                flag_rules: None,
+                include_ext: query.include_ext.as_deref().map(split_list),
+                exclude_ext: query.exclude_ext.as_deref().map(split_list),
+                record_access_time: query.record_access_time,
+                audit: query.audit,
+                quick_estimate: query.quick_estimate,
+                stream_items: false,
+            },
+        }
+    }
+}
+
+// 解析逗号分隔的查询参数
+fn split_list(list: &str) -> Vec<String> {
+    list.split(',')
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+#[derive(Deserialize, ToSchema)]
+struct TopExtensionRequest {
+    path: String,
+    ext: String,
+    #[serde(default = "default_top_n")]
+    n: usize,
+    #[serde(default)]
+    unit: Option<SizeUnit>,
+    #[serde(default)]
+    format: SizeFormat,
+}
+
+fn default_top_n() -> usize {
+    20
+}
+
+#[derive(Deserialize, ToSchema)]
+struct StaleFilesRequest {
+    path: String,
+    days: u64,
+    #[serde(default)]
+    min_size: i64,
+    #[serde(default)]
+    unit: Option<SizeUnit>,
+    #[serde(default)]
+    format: SizeFormat,
 }
 
-#[derive(Serialize)]
+#[derive(Deserialize, ToSchema)]
+struct AnnotateRequest {
+    path: String,
+    // 历史记录的扫描时间（Unix 秒）
+    timestamp: i64,
+    // 为空时删除备注
+    note: String,
+}
+
+#[derive(Deserialize, ToSchema)]
+struct UnusedFilesRequest {
+    path: String,
+    days: u64,
+    #[serde(default)]
+    min_size: i64,
+    #[serde(default)]
+    unit: Option<SizeUnit>,
+    #[serde(default)]
+    format: SizeFormat,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct PageQuery {
+    #[serde(default)]
+    offset: usize,
+    #[serde(default = "default_page_limit")]
+    limit: usize,
+    unit: Option<SizeUnit>,
+    #[serde(default)]
+    format: SizeFormat,
+}
+
+fn default_page_limit() -> usize {
+    500
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct SearchQuery {
+    q: String,
+    unit: Option<SizeUnit>,
+    #[serde(default)]
+    format: SizeFormat,
+    // 逗号分隔的扩展名；指定 include_ext 时只返回匹配的文件
+    include_ext: Option<String>,
+    exclude_ext: Option<String>,
+    // 最小大小，可带单位，如 10MB
+    min_size: Option<String>,
+    // 只返回修改时间早于该天数之前的文件
+    older_than: Option<u64>,
+    category: Option<FileCategory>,
+    // true 只返回目录，false 只返回文件
+    is_dir: Option<bool>,
+}
+
+impl SearchQuery {
+    fn filters(&self) -> Result<Vec<Filter>, String> {
+        let mut filters = vec![Filter::NameMatches(self.q.clone())];
+        if let Some(include) = &self.include_ext {
+            filters.push(Filter::ExtIn(split_list(include)));
+        }
+        if let Some(exclude) = &self.exclude_ext {
+            filters.push(Filter::ExtNotIn(split_list(exclude)));
+        }
+        if let Some(min_size) = &self.min_size {
+            let size = parse_size(min_size).ok_or_else(|| format!("无效的大小: {}", min_size))?;
+            filters.push(Filter::MinSize(size));
+        }
+        filters.extend(self.older_than.map(Filter::OlderThan));
+        filters.extend(self.category.map(Filter::Category));
+        filters.extend(self.is_dir.map(Filter::IsDir));
+        Ok(filters)
+    }
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct SizeQuery {
+    unit: Option<SizeUnit>,
+    #[serde(default)]
+    format: SizeFormat,
+}
+
+#[derive(Serialize, ToSchema)]
 struct ErrorResponse {
     error: String,
 }
 
-#[tokio::main]
-async fn main() {
+type ApiError = (StatusCode, Json<ErrorResponse>);
+
+// NDJSON 流式扫描的最后一行
+#[derive(Serialize, ToSchema)]
+struct StreamSummary {
+    path: String,
+    total_size: i64,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    total_size_formatted: String,
+    scan_time: f64,
+    root: RootSummary,
+}
+
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "Search-tool API", description = "目录占用扫描与历史记录接口"),
+    paths(
+        scan_handler,
+        scan_query_handler,
+        scan_stream_handler,
+        result_handler,
+        result_page_handler,
+        result_search_handler,
+        result_filter_handler,
+        result_treemap_handler,
+        result_diff_handler,
+        top_by_extension_handler,
+        stale_files_handler,
+        unused_files_handler,
+        settings_handler,
+        update_settings_handler,
+        presets_handler,
+        run_preset_handler,
+        history_handler,
+        history_item_handler,
+        annotate_history_handler,
+        trend_handler,
+        admin_cache_handler,
+        admin_clear_cache_handler,
+        admin_jobs_handler,
+    ),
+    components(schemas(
+        ScanRequest,
+        TopExtensionRequest,
+        StaleFilesRequest,
+        UnusedFilesRequest,
+        AnnotateRequest,
+        ErrorResponse,
+        StreamSummary,
+        Filter,
+        FileCategory,
+        Item,
+        ScanOptions,
+        FlagRule,
+        FlagTarget,
+        ScanResult,
+        ScanSnapshot,
+        ScanStats,
+        RootSummary,
+        AuditReport,
+        AuditFinding,
+        AuditIssue,
+        SizeEstimate,
+        SortKey,
+        HistoryItem,
+        Trend,
+        TrendPoint,
+        ExtensionReport,
+        UnusedReport,
+        ResultPage,
+        SnapshotDiff,
+        ResizedItem,
+        TreemapNode,
+        TreemapOptions,
+        Settings,
+        ScanPreset,
+        ScheduledScan,
+        AlertSettings,
+        StorageSettings,
+        CacheStats,
+        CacheEntry,
+        ClearCacheResponse,
+        JobInfo,
+        Webhook,
+        WebhookKind,
+        SmtpSettings,
+        SizeUnit,
+        SizeFormat,
+        SymlinkPolicy,
+    ))
+)]
+struct ApiDoc;
+
+// Swagger UI 静态资源从 CDN 加载，规范由 /api/openapi.json 提供
+const SWAGGER_UI_HTML: &str = r##"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>Search-tool API</title>
+<link rel="stylesheet" href="https://cdn.jsdelivr.net/npm/swagger-ui-dist@5/swagger-ui.css">
+</head>
+<body>
+<div id="swagger-ui"></div>
+<script src="https://cdn.jsdelivr.net/npm/swagger-ui-dist@5/swagger-ui-bundle.js"></script>
+<script>
+window.onload = () => {
+    window.ui = SwaggerUIBundle({ url: "/api/openapi.json", dom_id: "#swagger-ui" });
+};
+</script>
+</body>
+</html>"##;
+
+fn api_error(status: StatusCode, error: impl Into<String>) -> ApiError {
+    (
+        status,
+        Json(ErrorResponse {
+            error: error.into(),
+        }),
+    )
+}
+
+fn main() {
+    // 工作线程数可通过 SEARCH_TOOL_THREADS 调整，默认与 CPU 核心数一致
+    let mut runtime = tokio::runtime::Builder::new_multi_thread();
+    if let Some(threads) = configured_threads() {
+        runtime.worker_threads(threads);
+    }
+    runtime
+        .enable_all()
+        .build()
+        .expect("failed to build tokio runtime")
+        .block_on(serve());
+}
+
+async fn serve() {
     tracing_subscriber::registry()
         .with(
             tracing_subscriber::EnvFilter::try_from_default_env()
@@ -39,26 +595,126 @@ async fn main() {
         .init();
 
     // 初始化状态
+    let settings_path = settings::settings_path();
+    let settings = Settings::load(&settings_path);
+    let storage = storage::open(&settings.storage, settings.cache_max_entries)
+        .await
+        .expect("failed to open storage backend");
+    let (shutdown_tx, shutdown) = watch::channel(false);
     let state = AppState {
-        history: Arc::new(RwLock::new(Vec::new())),
+        storage,
+        settings: Arc::new(RwLock::new(settings)),
+        settings_path: Arc::new(settings_path),
+        jobs: Arc::default(),
+        admin_token: std::env::var("SEARCH_TOOL_ADMIN_TOKEN")
+            .ok()
+            .filter(|token| !token.trim().is_empty())
+            .map(|token| Arc::new(token.trim().to_string())),
+        read_only: std::env::args().skip(1).any(|arg| arg == "--read-only"),
+        shutdown,
     };
+    if state.read_only {
+        tracing::info!("只读模式：修改类接口已禁用");
+    }
+    tokio::spawn(run_schedules(state.clone()));
+
+    // 管理接口单独校验令牌
+    let admin = Router::new()
+        .route("/api/admin/cache", get(admin_cache_handler))
+        .route("/api/admin/cache", delete(admin_clear_cache_handler))
+        .route("/api/admin/jobs", get(admin_jobs_handler))
+        .layer(middleware::from_fn_with_state(state.clone(), admin_layer));
+
+    // 修改设置、历史等状态的接口；设置中包含通知凭据、webhook 地址、计划扫描和存储后端，
+    // 修改需要管理令牌
+    let mutating = Router::new()
+        .route(
+            "/api/settings",
+            put(update_settings_handler)
+                .layer(middleware::from_fn_with_state(state.clone(), admin_layer)),
+        )
+        .route("/api/history/annotate", post(annotate_history_handler));
 
     // 构建路由
     let app = Router::new()
         .route("/", get(index_handler))
-        .route("/api/scan", post(scan_handler))
+        .route("/api/openapi.json", get(openapi_handler))
+        .route("/api/docs", get(docs_handler))
+        .route("/api/scan", get(scan_query_handler).post(scan_handler))
+        .route("/api/scan/stream", get(scan_stream_handler))
         .route("/api/history", get(history_handler))
         .route("/api/history-item", post(history_item_handler))
+        .route("/api/trend", post(trend_handler))
+        .route("/api/top-by-extension", post(top_by_extension_handler))
+        .route("/api/stale-files", post(stale_files_handler))
+        .route("/api/unused-files", post(unused_files_handler))
+        .route("/api/settings", get(settings_handler))
+        .route("/api/presets", get(presets_handler))
+        .route("/api/presets/:name/run", post(run_preset_handler))
+        .route("/api/results/:id", get(result_handler))
+        .route("/api/results/:id/items", get(result_page_handler))
+        .route("/api/results/:id/search", get(result_search_handler))
+        .route("/api/results/:id/filter", post(result_filter_handler))
+        .route("/api/results/:id/treemap", get(result_treemap_handler))
+        .route("/api/results/:id/diff/:other", get(result_diff_handler))
+        .merge(mutating)
+        .merge(admin)
         .nest_service("/static", ServeDir::new("static"))
+        .layer(middleware::from_fn_with_state(state.clone(), read_only_layer))
+        .layer(middleware::from_fn_with_state(state.clone(), session_layer))
         .layer(CorsLayer::permissive())
-        .with_state(state);
+        .layer(CompressionLayer::new())
+        .with_state(state.clone());
 
     let listener = tokio::net::TcpListener::bind("0.0.0.0:8080")
         .await
         .unwrap();
 
     tracing::info!("服务器启动在 http://localhost:8080");
-    axum::serve(listener, app).await.unwrap();
+    // 关闭时先取消进行中的扫描，再停止接受连接并等待已有请求完成
+    let server = axum::serve(listener, app).with_graceful_shutdown(async move {
+        shutdown_signal().await;
+        tracing::info!("收到关闭信号，正在取消进行中的扫描");
+        let _ = shutdown_tx.send(true);
+    });
+    let server = tokio::spawn(async move { server.await });
+    state.shutdown_requested().await;
+    match tokio::time::timeout(SHUTDOWN_GRACE, server).await {
+        Ok(Ok(Ok(()))) => {}
+        Ok(Ok(Err(e))) => tracing::warn!("服务器异常退出: {}", e),
+        Ok(Err(e)) => tracing::warn!("服务器异常退出: {}", e),
+        Err(_) => tracing::warn!("等待请求完成超时，强制关闭"),
+    }
+
+    // 所有请求结束后把存储中的写入落盘
+    if let Err(e) = state.storage.flush().await {
+        tracing::warn!("存储写入失败: {}", e);
+    }
+    tracing::info!("服务器已关闭");
+}
+
+// Ctrl-C，或 Unix 下的 SIGTERM
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        let _ = tokio::signal::ctrl_c().await;
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(_) => std::future::pending::<()>().await,
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
 }
 
 // 主页处理器
@@ -76,11 +732,302 @@ async fn index_handler() -> Html<String> {
     Html(html)
 }
 
+// OpenAPI 规范处理器
+async fn openapi_handler() -> Json<utoipa::openapi::OpenApi> {
+    Json(ApiDoc::openapi())
+}
+
+// Swagger UI 处理器
+async fn docs_handler() -> Html<&'static str> {
+    Html(SWAGGER_UI_HTML)
+}
+
 // 扫描处理器
+#[utoipa::path(
+    post,
+    path = "/api/scan",
+    request_body = ScanRequest,
+    responses(
+        (status = 200, description = "扫描结果", body = ScanResult),
+        (status = 400, description = "路径无效或扫描失败", body = ErrorResponse)
+    )
+)]
 async fn scan_handler(
     State(state): State<AppState>,
+    Extension(session): Extension<Session>,
     Json(payload): Json<ScanRequest>,
-) -> Result<Json<ScanResult>, (StatusCode, Json<ErrorResponse>)> {
+) -> Result<Json<ScanResult>, ApiError> {
+    run_scan(&state, &session, payload, None).await
+}
+
+// 扫描处理器（查询参数形式，便于 curl 和书签调用）
+#[utoipa::path(
+    get,
+    path = "/api/scan",
+    params(ScanQuery),
+    responses(
+        (status = 200, description = "扫描结果", body = ScanResult),
+        (status = 304, description = "内容与 If-None-Match 一致"),
+        (status = 400, description = "路径无效或扫描失败", body = ErrorResponse)
+    )
+)]
+async fn scan_query_handler(
+    State(state): State<AppState>,
+    Extension(session): Extension<Session>,
+    headers: HeaderMap,
+    Query(query): Query<ScanQuery>,
+) -> Result<Response, ApiError> {
+    let request = ScanRequest::from(query);
+    let shape = ResponseShape::of(&state, &request).await;
+    let options = scan_options(&state, request.options.clone()).await;
+    let Json(result) = run_scan(&state, &session, request, None).await?;
+    let etag = scan_etag(&result, &options, &shape).await;
+    Ok(json_with_etag(&headers, etag, &result))
+}
+
+// 流式扫描：扫描期间按间隔推送 snapshot 事件，结束时推送 result 或 error 事件。
+// Accept 为 application/x-ndjson 时改为逐行输出条目，见 scan_ndjson
+#[utoipa::path(
+    get,
+    path = "/api/scan/stream",
+    params(ScanQuery),
+    responses(
+        (status = 200, description = "SSE 事件流，snapshot 事件为阶段性结果，result 事件为最终结果", content_type = "text/event-stream", body = ScanSnapshot),
+        (status = 200, description = "NDJSON：每行一个条目，每统计完一个一级子目录输出一批；最后一行为汇总，出错时为 ErrorResponse", content_type = "application/x-ndjson", body = StreamSummary)
+    )
+)]
+async fn scan_stream_handler(
+    State(state): State<AppState>,
+    Extension(session): Extension<Session>,
+    headers: HeaderMap,
+    Query(query): Query<ScanQuery>,
+) -> Response {
+    let ndjson = headers
+        .get(header::ACCEPT)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.contains(NDJSON));
+    if ndjson {
+        scan_ndjson(state, query).await.into_response()
+    } else {
+        scan_sse(state, session, query).into_response()
+    }
+}
+
+fn scan_sse(
+    state: AppState,
+    session: Session,
+    query: ScanQuery,
+) -> Sse<ReceiverStream<Result<Event, Infallible>>> {
+    let (tx, rx) = mpsc::channel(16);
+    let interval = Duration::from_secs(query.interval.unwrap_or(DEFAULT_SNAPSHOT_INTERVAL).max(1));
+
+    tokio::spawn(async move {
+        let unit = size_unit(&state, query.unit).await;
+        let format = query.format;
+        let progress = Arc::new(ScanProgress::default());
+        let scan = run_scan(&state, &session, query.into(), Some(progress.clone()));
+        tokio::pin!(scan);
+        let mut ticker = tokio::time::interval(interval);
+        ticker.tick().await;
+
+        let outcome = loop {
+            tokio::select! {
+                outcome = &mut scan => break outcome,
+                _ = ticker.tick() => {
+                    let event = sse_event("snapshot", &progress.snapshot(unit, format));
+                    // 客户端断开后放弃扫描
+                    if tx.send(Ok(event)).await.is_err() {
+                        return;
+                    }
+                }
+            }
+        };
+
+        let event = match outcome {
+            Ok(Json(result)) => sse_event("result", &result),
+            Err((_, Json(error))) => sse_event("error", &error),
+        };
+        let _ = tx.send(Ok(event)).await;
+    });
+
+    Sse::new(ReceiverStream::new(rx)).keep_alive(KeepAlive::default())
+}
+
+// 条目逐批写出，不计入历史和结果存储
+async fn scan_ndjson(state: AppState, query: ScanQuery) -> Response {
+    let unit = size_unit(&state, query.unit).await;
+    let format = query.format;
+    let request: ScanRequest = query.into();
+    let path = request.path.trim().to_string();
+    let options = scan_options(&state, request.options).await;
+
+    let (tx, rx) = mpsc::channel::<Result<String, Infallible>>(16);
+    tokio::spawn(async move {
+        let start_time = Instant::now();
+        let _job = state.jobs.start(&path, None);
+        let (items_tx, mut items_rx) = mpsc::channel::<Vec<Item>>(4);
+        let scan = scan_by_top_level(&path, &options, items_tx);
+        tokio::pin!(scan);
+        let lines = |mut batch: Vec<Item>| -> String {
+            format_item_sizes(&mut batch, unit, format);
+            batch.iter().map(ndjson_line).collect()
+        };
+
+        let outcome = loop {
+            tokio::select! {
+                biased;
+                Some(batch) = items_rx.recv() => {
+                    // 客户端断开后放弃扫描
+                    if tx.send(Ok(lines(batch))).await.is_err() {
+                        return;
+                    }
+                }
+                outcome = &mut scan => break outcome,
+                _ = state.shutdown_requested() => {
+                    let error = ErrorResponse { error: "服务正在关闭，扫描已取消".to_string() };
+                    let _ = tx.send(Ok(ndjson_line(&error))).await;
+                    return;
+                }
+            }
+        };
+        // 扫描结束时通道中可能还有未写出的批次，全部写出后再写汇总
+        while let Some(batch) = items_rx.recv().await {
+            if tx.send(Ok(lines(batch))).await.is_err() {
+                return;
+            }
+        }
+
+        let last = match outcome {
+            Ok(root) => ndjson_line(&StreamSummary {
+                path: path.clone(),
+                total_size: root.subtree_size,
+                total_size_formatted: match format {
+                    SizeFormat::Human => format_size(root.subtree_size, unit),
+                    SizeFormat::None => String::new(),
+                },
+                scan_time: start_time.elapsed().as_secs_f64(),
+                root,
+            }),
+            Err(e) => ndjson_line(&ErrorResponse { error: e.to_string() }),
+        };
+        let _ = tx.send(Ok(last)).await;
+    });
+
+    (
+        [(header::CONTENT_TYPE, NDJSON)],
+        Body::from_stream(ReceiverStream::new(rx)),
+    )
+        .into_response()
+}
+
+fn ndjson_line<T: Serialize>(value: &T) -> String {
+    let mut line = serde_json::to_string(value).unwrap_or_default();
+    line.push('\n');
+    line
+}
+
+fn sse_event<T: Serialize>(name: &str, data: &T) -> Event {
+    Event::default()
+        .event(name)
+        .json_data(data)
+        .unwrap_or_default()
+}
+
+// 同一结果的不同响应形式（层级、排序、树图、大小格式）使用不同的 ETag
+#[derive(Default, Serialize)]
+struct ResponseShape {
+    depth: Option<usize>,
+    sort: SortKey,
+    treemap: Option<TreemapOptions>,
+    unit: SizeUnit,
+    format: SizeFormat,
+}
+
+impl ResponseShape {
+    async fn of(state: &AppState, request: &ScanRequest) -> Self {
+        ResponseShape {
+            depth: request.depth,
+            sort: request.sort,
+            treemap: request.treemap.clone(),
+            unit: size_unit(state, request.unit).await,
+            format: request.format,
+        }
+    }
+}
+
+// 每次扫描都会生成新的结果 ID，重复的 GET /api/scan 因此改用扫描根目录（路径和修改时间）、
+// 扫描选项和响应形式作为 ETag，再加上总大小和条目数以反映深层目录中的变化。
+// 弱校验器：压缩编码不同的响应共用同一 ETag
+async fn scan_etag(result: &ScanResult, options: &ScanOptions, shape: &ResponseShape) -> String {
+    let modified = tokio::fs::metadata(&result.path)
+        .await
+        .and_then(|metadata| metadata.modified())
+        .ok();
+    let key = (
+        &result.path,
+        modified,
+        options,
+        shape,
+        result.total_size,
+        result.items.len(),
+    );
+    format!("W/\"{:016x}\"", fingerprint(&key))
+}
+
+fn fingerprint<T: Serialize>(value: &T) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    serde_json::to_vec(value)
+        .unwrap_or_default()
+        .hash(&mut hasher);
+    hasher.finish()
+}
+
+// 客户端携带的 If-None-Match 与当前 ETag 一致时返回 304，不再传输响应体；
+// 按弱比较，忽略 W/ 前缀。304 只适用于 GET，POST 路由不经过这里
+fn json_with_etag<T: Serialize>(headers: &HeaderMap, etag: String, body: &T) -> Response {
+    let opaque = |tag: &str| tag.trim().trim_start_matches("W/").to_string();
+    let not_modified = headers
+        .get(header::IF_NONE_MATCH)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| {
+            value
+                .split(',')
+                .any(|tag| opaque(tag) == opaque(&etag) || tag.trim() == "*")
+        })
+        .unwrap_or(false);
+
+    let mut response = if not_modified {
+        StatusCode::NOT_MODIFIED.into_response()
+    } else {
+        Json(body).into_response()
+    };
+
+    if let Ok(value) = HeaderValue::from_str(&etag) {
+        response.headers_mut().insert(header::ETAG, value);
+    }
+    response
+}
+
+// 未指定的扫描选项使用设置中的默认值
+async fn scan_options(state: &AppState, mut options: ScanOptions) -> ScanOptions {
+    state.settings.read().await.apply_scan_defaults(&mut options);
+    options
+}
+
+// 请求未指定单位时使用设置中的单位
+async fn size_unit(state: &AppState, unit: Option<SizeUnit>) -> SizeUnit {
+    match unit {
+        Some(unit) => unit,
+        None => state.settings.read().await.size_unit,
+    }
+}
+
+async fn run_scan(
+    state: &AppState,
+    session: &Session,
+    mut payload: ScanRequest,
+    progress: Option<Arc<ScanProgress>>,
+) -> Result<Json<ScanResult>, ApiError> {
     let path = payload.path.trim();
 
     if path.is_empty() {
@@ -92,29 +1039,60 @@ async fn scan_handler(
         ));
     }
 
-    match scan_directory(path).await {
+    payload.options = scan_options(state, payload.options).await;
+    let _job = state.jobs.start(path, progress.clone());
+
+    let outcome = tokio::select! {
+        outcome = scan_directory_with_progress(path, &payload.options, progress) => outcome,
+        // 服务关闭时放弃扫描，未完成的结果不写入历史
+        _ = state.shutdown_requested() => {
+            return Err(api_error(StatusCode::SERVICE_UNAVAILABLE, "服务正在关闭，扫描已取消"));
+        }
+    };
+
+    match outcome {
         Ok(mut result) => {
-            // 添加到历史记录
-            let history_item = HistoryItem {
-                path: path.to_string(),
-                scan_time: chrono::Utc::now(),
-                total_size: result.total_size,
-                size_format: result.total_size_formatted.clone(),
-                items: result.items.clone(),
-            };
-
-            // 保存到历史记录
-            let mut history = state.history.write().await;
-            history.push(history_item);
-
-            // 保持历史记录在合理范围内（最多保存50条）
-            if history.len() > 50 {
-                history.remove(0);
+            // 过滤后的结果只反映部分文件，不计入历史记录
+            if payload.options.is_unfiltered() {
+                // 添加到历史记录
+                let history_item = HistoryItem {
+                    path: path.to_string(),
+                    scan_time: chrono::Utc::now(),
+                    total_size: result.total_size,
+                    size_format: result.total_size_formatted.clone(),
+                    items: result.items.clone(),
+                    note: None,
+                };
+
+                // 保持历史记录在设置的条数以内
+                let limit = state.settings.read().await.history_limit;
+
+                // 保存到历史记录，存储不可用时只记录日志，不影响本次结果
+                if let Err(e) = state.history(session).push(history_item, limit).await {
+                    tracing::warn!("历史记录保存失败: {}", e);
+                }
             }
 
             // 更新结果中的路径为规范路径
             result.path = path.to_string();
 
+            // 保存到结果存储，后续操作通过 ID 引用
+            let result_id = state
+                .results(session)
+                .insert(result.clone())
+                .await
+                .map_err(|e| api_error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+            result.result_id = Some(result_id);
+
+            // 按需生成树图结构，避免前端从扁平路径重建层级
+            if let Some(options) = &payload.treemap {
+                result.treemap = Some(build_treemap(&result, options));
+            }
+
+            shape_result(&mut result, payload.depth, payload.sort);
+            let unit = size_unit(state, payload.unit).await;
+            format_sizes(&mut result, unit, payload.format);
+
             Ok(Json(result))
         }
         Err(e) => Err((
@@ -126,41 +1104,611 @@ async fn scan_handler(
     }
 }
 
+// 按设置中的计划定期扫描，每次检查时重新读取设置，修改后无需重启
+async fn run_schedules(state: AppState) {
+    // 路径 -> 上次执行时间
+    let mut last_run: HashMap<String, Instant> = HashMap::new();
+    let mut ticker = tokio::time::interval(SCHEDULE_TICK);
+
+    loop {
+        tokio::select! {
+            _ = ticker.tick() => {},
+            _ = state.shutdown_requested() => return,
+        }
+        let schedules = state.settings.read().await.scheduled_scans.clone();
+        for schedule in schedules {
+            let interval = Duration::from_secs(schedule.interval_minutes.max(1) * 60);
+            if last_run
+                .get(&schedule.path)
+                .is_some_and(|last| last.elapsed() < interval)
+            {
+                continue;
+            }
+            last_run.insert(schedule.path.clone(), Instant::now());
+            run_scheduled_scan(&state, schedule).await;
+        }
+    }
+}
+
+async fn run_scheduled_scan(state: &AppState, schedule: ScheduledScan) {
+    // 在本次扫描写入历史之前取出上一次的记录，用于计算增长
+    let session = Session::shared();
+    let previous = match state.history(&session).latest(&schedule.path).await {
+        Ok(previous) => previous,
+        Err(e) => {
+            tracing::warn!("读取历史记录失败: {}", e);
+            None
+        }
+    };
+
+    let request = ScanRequest {
+        path: schedule.path.clone(),
+        treemap: None,
+        depth: None,
+        sort: SortKey::default(),
+        unit: None,
+        format: SizeFormat::default(),
+        options: schedule.options,
+    };
+    let result = match run_scan(state, &session, request, None).await {
+        Ok(Json(result)) => result,
+        Err((_, Json(error))) => {
+            tracing::warn!("计划扫描 {} 失败: {}", schedule.path, error.error);
+            return;
+        }
+    };
+
+    let (alerts, unit) = {
+        let settings = state.settings.read().await;
+        (settings.alerts.clone(), settings.size_unit)
+    };
+    if !alerts.has_targets() {
+        return;
+    }
+    let Some(notification) = build_notification(&result, previous.as_ref(), &alerts, unit) else {
+        return;
+    };
+    for error in deliver(&notification, &alerts, unit).await {
+        tracing::warn!("通知发送失败: {}", error);
+    }
+}
+
+async fn stored_result(
+    state: &AppState,
+    session: &Session,
+    id: &str,
+) -> Result<Arc<ScanResult>, ApiError> {
+    state
+        .results(session)
+        .get(id)
+        .await
+        .map_err(|e| api_error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .ok_or_else(|| api_error(StatusCode::NOT_FOUND, "扫描结果已过期，请重新扫描"))
+}
+
+// 扫描结果处理器
+#[utoipa::path(
+    get,
+    path = "/api/results/{id}",
+    params(("id" = String, Path, description = "扫描结果 ID"), SizeQuery),
+    responses(
+        (status = 200, description = "完整扫描结果", body = ScanResult),
+        (status = 304, description = "内容与 If-None-Match 一致"),
+        (status = 404, description = "结果不存在或已过期", body = ErrorResponse)
+    )
+)]
+async fn result_handler(
+    State(state): State<AppState>,
+    Extension(session): Extension<Session>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+    Query(query): Query<SizeQuery>,
+) -> Result<Response, ApiError> {
+    let mut result = (*stored_result(&state, &session, &id).await?).clone();
+    result.result_id = Some(id.clone());
+    let shape = ResponseShape {
+        unit: size_unit(&state, query.unit).await,
+        format: query.format,
+        ..ResponseShape::default()
+    };
+    format_sizes(&mut result, shape.unit, shape.format);
+    // 结果 ID 由客户端给出，同一 ID 的内容不会变化
+    let etag = format!("W/\"{id}-{:016x}\"", fingerprint(&shape));
+    Ok(json_with_etag(&headers, etag, &result))
+}
+
+// 扫描结果分页处理器
+#[utoipa::path(
+    get,
+    path = "/api/results/{id}/items",
+    params(("id" = String, Path, description = "扫描结果 ID"), PageQuery),
+    responses(
+        (status = 200, description = "分页条目", body = ResultPage),
+        (status = 404, description = "结果不存在或已过期", body = ErrorResponse)
+    )
+)]
+async fn result_page_handler(
+    State(state): State<AppState>,
+    Extension(session): Extension<Session>,
+    Path(id): Path<String>,
+    Query(query): Query<PageQuery>,
+) -> Result<Json<ResultPage>, ApiError> {
+    let result = stored_result(&state, &session, &id).await?;
+    let mut page = store::page(&id, &result, query.offset, query.limit);
+    let unit = size_unit(&state, query.unit).await;
+    format_item_sizes(&mut page.items, unit, query.format);
+    Ok(Json(page))
+}
+
+// 扫描结果搜索处理器
+#[utoipa::path(
+    get,
+    path = "/api/results/{id}/search",
+    params(("id" = String, Path, description = "扫描结果 ID"), SearchQuery),
+    responses(
+        (status = 200, description = "名称匹配的条目", body = Vec<Item>),
+        (status = 400, description = "过滤参数无效", body = ErrorResponse),
+        (status = 404, description = "结果不存在或已过期", body = ErrorResponse)
+    )
+)]
+async fn result_search_handler(
+    State(state): State<AppState>,
+    Extension(session): Extension<Session>,
+    Path(id): Path<String>,
+    Query(query): Query<SearchQuery>,
+) -> Result<Json<Vec<Item>>, ApiError> {
+    let result = stored_result(&state, &session, &id).await?;
+    let filters = query.filters().map_err(|e| api_error(StatusCode::BAD_REQUEST, e))?;
+    let mut items = filter::filter_items(&result.items, &filters);
+    let unit = size_unit(&state, query.unit).await;
+    format_item_sizes(&mut items, unit, query.format);
+    Ok(Json(items))
+}
+
+// 按过滤条件筛选扫描结果处理器，条件全部满足的条目才保留
+#[utoipa::path(
+    post,
+    path = "/api/results/{id}/filter",
+    params(("id" = String, Path, description = "扫描结果 ID"), SizeQuery),
+    request_body = Vec<Filter>,
+    responses(
+        (status = 200, description = "筛选后的扫描结果", body = ScanResult),
+        (status = 404, description = "结果不存在或已过期", body = ErrorResponse)
+    )
+)]
+async fn result_filter_handler(
+    State(state): State<AppState>,
+    Extension(session): Extension<Session>,
+    Path(id): Path<String>,
+    Query(query): Query<SizeQuery>,
+    Json(filters): Json<Vec<Filter>>,
+) -> Result<Json<ScanResult>, ApiError> {
+    let stored = stored_result(&state, &session, &id).await?;
+    let mut result = filter::apply(&stored, &filters);
+    result.result_id = Some(id);
+    format_sizes(&mut result, size_unit(&state, query.unit).await, query.format);
+    Ok(Json(result))
+}
+
+// 扫描结果树图处理器
+#[utoipa::path(
+    get,
+    path = "/api/results/{id}/treemap",
+    params(("id" = String, Path, description = "扫描结果 ID"), TreemapOptions),
+    responses(
+        (status = 200, description = "树图结构", body = TreemapNode),
+        (status = 404, description = "结果不存在或已过期", body = ErrorResponse)
+    )
+)]
+async fn result_treemap_handler(
+    State(state): State<AppState>,
+    Extension(session): Extension<Session>,
+    Path(id): Path<String>,
+    Query(options): Query<TreemapOptions>,
+) -> Result<Json<TreemapNode>, ApiError> {
+    let result = stored_result(&state, &session, &id).await?;
+    Ok(Json(build_treemap(&result, &options)))
+}
+
+// 两次扫描结果比较处理器
+#[utoipa::path(
+    get,
+    path = "/api/results/{id}/diff/{other}",
+    params(
+        ("id" = String, Path, description = "旧的扫描结果 ID"),
+        ("other" = String, Path, description = "新的扫描结果 ID")
+    ),
+    responses(
+        (status = 200, description = "文件差异", body = SnapshotDiff),
+        (status = 404, description = "结果不存在或已过期", body = ErrorResponse)
+    )
+)]
+async fn result_diff_handler(
+    State(state): State<AppState>,
+    Extension(session): Extension<Session>,
+    Path((id, other)): Path<(String, String)>,
+) -> Result<Json<SnapshotDiff>, ApiError> {
+    let old = stored_result(&state, &session, &id).await?;
+    let new = stored_result(&state, &session, &other).await?;
+    Ok(Json(diff_items(&old.items, &new.items)))
+}
+
+// 按扩展名统计最大文件处理器
+#[utoipa::path(
+    post,
+    path = "/api/top-by-extension",
+    request_body = TopExtensionRequest,
+    responses(
+        (status = 200, description = "指定扩展名的最大文件", body = ExtensionReport),
+        (status = 400, description = "路径无效或扫描失败", body = ErrorResponse)
+    )
+)]
+async fn top_by_extension_handler(
+    State(state): State<AppState>,
+    Json(payload): Json<TopExtensionRequest>,
+) -> Result<Json<ExtensionReport>, (StatusCode, Json<ErrorResponse>)> {
+    let options = scan_options(&state, ScanOptions::default()).await;
+    let unit = size_unit(&state, payload.unit).await;
+    match scan_directory(payload.path.trim(), &options).await {
+        Ok(result) => {
+            let mut report = top_by_extension(&result, &payload.ext, payload.n, unit);
+            if payload.format == SizeFormat::None {
+                report.total_size_formatted.clear();
+                format_item_sizes(&mut report.items, unit, payload.format);
+            }
+            Ok(Json(report))
+        }
+        Err(e) => Err((
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse {
+                error: e.to_string(),
+            }),
+        )),
+    }
+}
+
+// 长期未修改的大文件处理器
+#[utoipa::path(
+    post,
+    path = "/api/stale-files",
+    request_body = StaleFilesRequest,
+    responses(
+        (status = 200, description = "长期未修改的文件", body = Vec<Item>),
+        (status = 400, description = "路径无效或扫描失败", body = ErrorResponse)
+    )
+)]
+async fn stale_files_handler(
+    State(state): State<AppState>,
+    Json(payload): Json<StaleFilesRequest>,
+) -> Result<Json<Vec<Item>>, (StatusCode, Json<ErrorResponse>)> {
+    let options = ScanOptions {
+        older_than_days: Some(payload.days),
+        ..ScanOptions::default()
+    };
+    let options = scan_options(&state, options).await;
+    let unit = size_unit(&state, payload.unit).await;
+
+    match scan_directory(payload.path.trim(), &options).await {
+        Ok(result) => {
+            let filters = [Filter::IsDir(false), Filter::MinSize(payload.min_size)];
+            let mut items = filter::filter_items(&result.items, &filters);
+            format_item_sizes(&mut items, unit, payload.format);
+            Ok(Json(items))
+        }
+        Err(e) => Err((
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse {
+                error: e.to_string(),
+            }),
+        )),
+    }
+}
+
+// 长期未被读取的大文件处理器，依赖文件系统的访问时间，结果只是尽力而为
+#[utoipa::path(
+    post,
+    path = "/api/unused-files",
+    request_body = UnusedFilesRequest,
+    responses(
+        (status = 200, description = "长期未访问的文件；访问时间不可靠时带有 warning", body = UnusedReport),
+        (status = 400, description = "路径无效或扫描失败", body = ErrorResponse)
+    )
+)]
+async fn unused_files_handler(
+    State(state): State<AppState>,
+    Json(payload): Json<UnusedFilesRequest>,
+) -> Result<Json<UnusedReport>, ApiError> {
+    let options = ScanOptions {
+        record_access_time: true,
+        ..ScanOptions::default()
+    };
+    let options = scan_options(&state, options).await;
+    let unit = size_unit(&state, payload.unit).await;
+
+    let result = scan_directory(payload.path.trim(), &options)
+        .await
+        .map_err(|e| api_error(StatusCode::BAD_REQUEST, e.to_string()))?;
+    let mut report = unused_since(&result, payload.days, payload.min_size, unit);
+    if payload.format == SizeFormat::None {
+        report.total_size_formatted.clear();
+    }
+    format_item_sizes(&mut report.items, unit, payload.format);
+    Ok(Json(report))
+}
+
+// 设置处理器
+#[utoipa::path(
+    get,
+    path = "/api/settings",
+    responses((status = 200, description = "当前设置，密码以占位符代替", body = Settings))
+)]
+async fn settings_handler(State(state): State<AppState>) -> Json<Settings> {
+    Json(state.settings.read().await.redacted())
+}
+
+// 更新设置处理器：需要管理令牌；只覆盖请求中出现的字段，并写入设置文件。
+// 密码字段传回占位符时保留原值
+#[utoipa::path(
+    put,
+    path = "/api/settings",
+    request_body = Object,
+    responses(
+        (status = 200, description = "更新后的设置", body = Settings),
+        (status = 400, description = "设置无效", body = ErrorResponse),
+        (status = 401, description = "管理令牌无效", body = ErrorResponse),
+        (status = 404, description = "管理接口未启用", body = ErrorResponse),
+        (status = 500, description = "设置文件写入失败", body = ErrorResponse)
+    )
+)]
+async fn update_settings_handler(
+    State(state): State<AppState>,
+    Json(patch): Json<serde_json::Value>,
+) -> Result<Json<Settings>, ApiError> {
+    let mut settings = state.settings.write().await;
+    let mut updated = settings
+        .merge(patch)
+        .map_err(|e| api_error(StatusCode::BAD_REQUEST, e.to_string()))?;
+    updated.keep_secrets(&settings);
+    updated
+        .save(&state.settings_path)
+        .map_err(|e| api_error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    state
+        .storage
+        .apply_limits(updated.history_limit, updated.cache_max_entries)
+        .await
+        .map_err(|e| api_error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    *settings = updated.clone();
+    Ok(Json(updated.redacted()))
+}
+
+// 扫描预设列表处理器
+#[utoipa::path(
+    get,
+    path = "/api/presets",
+    responses((status = 200, description = "设置中的扫描预设", body = Vec<ScanPreset>))
+)]
+async fn presets_handler(State(state): State<AppState>) -> Json<Vec<ScanPreset>> {
+    Json(state.settings.read().await.presets.clone())
+}
+
+// 运行扫描预设处理器
+#[utoipa::path(
+    post,
+    path = "/api/presets/{name}/run",
+    params(("name" = String, Path, description = "预设名称（不区分大小写）"), SizeQuery),
+    responses(
+        (status = 200, description = "扫描结果", body = ScanResult),
+        (status = 400, description = "路径无效或扫描失败", body = ErrorResponse),
+        (status = 404, description = "预设不存在", body = ErrorResponse)
+    )
+)]
+async fn run_preset_handler(
+    State(state): State<AppState>,
+    Extension(session): Extension<Session>,
+    Path(name): Path<String>,
+    Query(query): Query<SizeQuery>,
+) -> Result<Json<ScanResult>, ApiError> {
+    let preset = state
+        .settings
+        .read()
+        .await
+        .preset(&name)
+        .cloned()
+        .ok_or_else(|| api_error(StatusCode::NOT_FOUND, "未找到该扫描预设"))?;
+
+    let request = ScanRequest {
+        path: preset.path,
+        treemap: None,
+        depth: preset.depth,
+        sort: SortKey::default(),
+        unit: query.unit,
+        format: query.format,
+        options: preset.options,
+    };
+    run_scan(&state, &session, request, None).await
+}
+
 // 历史记录处理器
-async fn history_handler(State(state): State<AppState>) -> Json<Vec<HistoryItem>> {
-    let history = state.history.read().await;
+#[utoipa::path(
+    get,
+    path = "/api/history",
+    responses(
+        (status = 200, description = "历史记录（最新的在前）", body = Vec<HistoryItem>),
+        (status = 304, description = "内容与 If-None-Match 一致"),
+        (status = 500, description = "存储不可用", body = ErrorResponse)
+    )
+)]
+async fn history_handler(
+    State(state): State<AppState>,
+    Extension(session): Extension<Session>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    let mut history = state
+        .history(&session)
+        .list()
+        .await
+        .map_err(|e| api_error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
     // 返回逆序（最新的在前）
-    let reversed: Vec<HistoryItem> = history.iter().rev().cloned().collect();
-    Json(reversed)
+    history.reverse();
+    // 记录由路径和扫描时间确定，只有备注会原地修改
+    let entries: Vec<_> = history
+        .iter()
+        .map(|item| (&item.path, item.scan_time.timestamp(), &item.note))
+        .collect();
+    let etag = format!("W/\"{:016x}\"", fingerprint(&entries));
+    Ok(json_with_etag(&headers, etag, &history))
 }
 
 // 历史记录详情处理器
+#[utoipa::path(
+    post,
+    path = "/api/history-item",
+    request_body = ScanRequest,
+    responses(
+        (status = 200, description = "该路径最新的历史结果", body = ScanResult),
+        (status = 404, description = "未找到历史记录", body = ErrorResponse)
+    )
+)]
 async fn history_item_handler(
     State(state): State<AppState>,
+    Extension(session): Extension<Session>,
     Json(payload): Json<ScanRequest>,
 ) -> Result<Json<ScanResult>, (StatusCode, Json<ErrorResponse>)> {
-    let path = &payload.path;
+    let unit = size_unit(&state, payload.unit).await;
 
-    let history = state.history.read().await;
+    // 查找最新的匹配历史记录（等价路径写法视为同一路径）
+    let item = state
+        .history(&session)
+        .latest(&payload.path)
+        .await
+        .map_err(|e| api_error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .ok_or_else(|| api_error(StatusCode::NOT_FOUND, "未找到该历史记录"))?;
 
-    // 查找最新的匹配历史记录
-    for item in history.iter().rev() {
-        if item.path == *path {
-            let result = ScanResult {
-                items: item.items.clone(),
-                total_size: item.total_size,
-                total_size_formatted: item.size_format.clone(),
-                scan_time: 0.0, // 历史记录没有扫描时间
-                path: item.path.clone(),
-            };
-            return Ok(Json(result));
-        }
+    let mut result = ScanResult {
+        root: RootSummary::from_items(&item.items),
+        items: item.items,
+        total_size: item.total_size,
+        total_size_formatted: item.size_format,
+        scan_time: 0.0, // 历史记录没有扫描时间
+        path: item.path,
+        treemap: None,
+        result_id: None,
+        stats: None,
+        audit: None,
+        estimate: None,
+    };
+    format_sizes(&mut result, unit, payload.format);
+    Ok(Json(result))
+}
+
+// 历史记录备注处理器
+#[utoipa::path(
+    post,
+    path = "/api/history/annotate",
+    request_body = AnnotateRequest,
+    responses(
+        (status = 200, description = "更新备注后的历史记录", body = HistoryItem),
+        (status = 404, description = "未找到历史记录", body = ErrorResponse),
+        (status = 500, description = "存储不可用", body = ErrorResponse)
+    )
+)]
+async fn annotate_history_handler(
+    State(state): State<AppState>,
+    Extension(session): Extension<Session>,
+    Json(payload): Json<AnnotateRequest>,
+) -> Result<Json<HistoryItem>, ApiError> {
+    state
+        .history(&session)
+        .annotate(payload.path.trim(), payload.timestamp, &payload.note)
+        .await
+        .map_err(|e| api_error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .map(Json)
+        .ok_or_else(|| api_error(StatusCode::NOT_FOUND, "未找到该历史记录"))
+}
+
+// 增长趋势处理器
+#[utoipa::path(
+    post,
+    path = "/api/trend",
+    request_body = ScanRequest,
+    responses(
+        (status = 200, description = "增长趋势", body = Trend),
+        (status = 500, description = "存储不可用", body = ErrorResponse)
+    )
+)]
+async fn trend_handler(
+    State(state): State<AppState>,
+    Extension(session): Extension<Session>,
+    Json(payload): Json<ScanRequest>,
+) -> Result<Json<Trend>, ApiError> {
+    let history = state
+        .history(&session)
+        .for_path(payload.path.trim())
+        .await
+        .map_err(|e| api_error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    Ok(Json(build_trend(&history, payload.path.trim())))
+}
+
+#[derive(Serialize, ToSchema)]
+struct ClearCacheResponse {
+    removed: usize,
+}
+
+// 缓存概要处理器
+#[utoipa::path(
+    get,
+    path = "/api/admin/cache",
+    responses(
+        (status = 200, description = "所有会话缓存的结果、估算大小和命中计数", body = CacheStats),
+        (status = 401, description = "管理令牌无效", body = ErrorResponse),
+        (status = 500, description = "存储不可用", body = ErrorResponse)
+    )
+)]
+async fn admin_cache_handler(State(state): State<AppState>) -> Result<Json<CacheStats>, ApiError> {
+    let mut stats = state
+        .storage
+        .cache_stats()
+        .await
+        .map_err(|e| api_error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    for entry in &mut stats.entries {
+        entry.session = session_label(&entry.session);
     }
+    Ok(Json(stats))
+}
 
-    Err((
-        StatusCode::NOT_FOUND,
-        Json(ErrorResponse {
-            error: "未找到该历史记录".to_string(),
-        }),
-    ))
+// 清空缓存处理器，已发出的结果 ID 随之失效
+#[utoipa::path(
+    delete,
+    path = "/api/admin/cache",
+    responses(
+        (status = 200, description = "移除的结果条数", body = ClearCacheResponse),
+        (status = 401, description = "管理令牌无效", body = ErrorResponse),
+        (status = 500, description = "存储不可用", body = ErrorResponse)
+    )
+)]
+async fn admin_clear_cache_handler(
+    State(state): State<AppState>,
+) -> Result<Json<ClearCacheResponse>, ApiError> {
+    let removed = state
+        .storage
+        .clear_cache()
+        .await
+        .map_err(|e| api_error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    tracing::info!("已清空缓存，移除 {} 条结果", removed);
+    Ok(Json(ClearCacheResponse { removed }))
+}
+
+// 进行中的扫描处理器
+#[utoipa::path(
+    get,
+    path = "/api/admin/jobs",
+    responses(
+        (status = 200, description = "进行中的扫描，最早开始的在前", body = [JobInfo]),
+        (status = 401, description = "管理令牌无效", body = ErrorResponse)
+    )
+)]
+async fn admin_jobs_handler(State(state): State<AppState>) -> Json<Vec<JobInfo>> {
+    Json(state.jobs.list())
 }
@@ -0,0 +1,141 @@
+use crate::scan::ScanResult;
+use serde::Serialize;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::sync::Mutex;
+
+// 扫描过程中的事件回调，传给 scan_directory_observed 即可接入扫描流程，不必修改遍历代码。
+// on_item 和 on_error 在遍历任务中同步调用，实现应尽快返回
+pub trait ScanObserver: Send + Sync {
+    // root 为规范化后的扫描根目录
+    fn on_start(&self, _root: &Path) {}
+
+    // 统计到一个文件，path 为绝对路径
+    fn on_item(&self, _root: &Path, _path: &Path, _size: i64) {}
+
+    // 无法读取的目录或条目、因目录循环、重复到达或深度上限跳过的目录；根目录不可读时扫描随之失败
+    fn on_error(&self, _path: &Path, _error: &str) {}
+
+    fn on_complete(&self, _result: &ScanResult) {}
+}
+
+// 通过 tracing 输出扫描日志，逐个文件的事件为 trace 级别
+#[derive(Debug, Default)]
+pub struct TracingObserver;
+
+impl ScanObserver for TracingObserver {
+    fn on_start(&self, root: &Path) {
+        tracing::info!("开始扫描 {}", root.display());
+    }
+
+    fn on_item(&self, _root: &Path, path: &Path, size: i64) {
+        tracing::trace!("{} ({} 字节)", path.display(), size);
+    }
+
+    fn on_error(&self, path: &Path, error: &str) {
+        tracing::warn!("{}: {}", path.display(), error);
+    }
+
+    fn on_complete(&self, result: &ScanResult) {
+        tracing::info!(
+            "扫描完成 {}: {} 个条目，共 {} 字节，用时 {:.2} 秒",
+            result.path,
+            result.items.len(),
+            result.total_size,
+            result.scan_time
+        );
+    }
+}
+
+#[derive(Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+enum AuditEvent<'a> {
+    Start {
+        time: i64,
+        root: &'a str,
+    },
+    Item {
+        time: i64,
+        path: &'a str,
+        size: i64,
+    },
+    Error {
+        time: i64,
+        path: &'a str,
+        error: &'a str,
+    },
+    Complete {
+        time: i64,
+        path: &'a str,
+        total_size: i64,
+        items: usize,
+        scan_time: f64,
+    },
+}
+
+// 把扫描事件逐行以 JSON 追加到文件，用于审计；写入失败时忽略，不影响扫描
+pub struct JsonlAuditObserver {
+    writer: Mutex<BufWriter<File>>,
+}
+
+impl JsonlAuditObserver {
+    // 文件不存在时创建
+    pub fn open(path: &Path) -> std::io::Result<Self> {
+        let file = std::fs::OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(JsonlAuditObserver {
+            writer: Mutex::new(BufWriter::new(file)),
+        })
+    }
+
+    fn write(&self, event: AuditEvent) {
+        let Ok(line) = serde_json::to_string(&event) else {
+            return;
+        };
+        let mut writer = self.writer.lock().unwrap();
+        let _ = writeln!(writer, "{}", line);
+        // 开始和结束事件立即落盘，便于跟踪进行中的扫描
+        if matches!(event, AuditEvent::Start { .. } | AuditEvent::Complete { .. }) {
+            let _ = writer.flush();
+        }
+    }
+}
+
+fn now() -> i64 {
+    chrono::Utc::now().timestamp()
+}
+
+impl ScanObserver for JsonlAuditObserver {
+    fn on_start(&self, root: &Path) {
+        self.write(AuditEvent::Start {
+            time: now(),
+            root: &root.to_string_lossy(),
+        });
+    }
+
+    fn on_item(&self, _root: &Path, path: &Path, size: i64) {
+        self.write(AuditEvent::Item {
+            time: now(),
+            path: &path.to_string_lossy(),
+            size,
+        });
+    }
+
+    fn on_error(&self, path: &Path, error: &str) {
+        self.write(AuditEvent::Error {
+            time: now(),
+            path: &path.to_string_lossy(),
+            error,
+        });
+    }
+
+    fn on_complete(&self, result: &ScanResult) {
+        self.write(AuditEvent::Complete {
+            time: now(),
+            path: &result.path,
+            total_size: result.total_size,
+            items: result.items.len(),
+            scan_time: result.scan_time,
+        });
+    }
+}
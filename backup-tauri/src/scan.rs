@@ -1,55 +1,1027 @@
+use crate::audit::{AuditCollector, AuditReport};
+use crate::estimate::{SizeEstimate, Sampling};
+use crate::observer::ScanObserver;
+use crate::paths::{self, native_separators, path_key};
+use crate::priority::BackgroundIo;
+use crate::stream::{EventSink, ScanEvent};
+use crate::treemap::TreemapNode;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::path::{Path, PathBuf};
 use tokio::fs;
 use tokio::sync::Mutex;
-use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, LazyLock};
 use tokio::sync::mpsc;
+use tokio_stream::StreamExt;
+use utoipa::ToSchema;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct Item {
+    // 相对于扫描根目录的路径
     pub path: String,
+    // 绝对路径，使用系统的路径分隔符；阶段性结果中为空
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub abs_path: String,
+    // 父目录相对于扫描根目录的路径，一级子项为空字符串
+    #[serde(default)]
+    pub parent: String,
+    // 路径含无效的 UTF-8，上面的路径字段经过有损转换，不能直接用于文件操作
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub encoding_lossy: bool,
     pub size: i64,
+    // format=none 时为空并从响应中省略
+    #[serde(default, skip_serializing_if = "String::is_empty")]
     pub size_formatted: String,
     pub is_dir: bool,
+    // 文件的修改时间（Unix 秒），目录为空
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub modified: Option<i64>,
+    // 文件的最后访问时间（Unix 秒），只在 record_access_time 时记录
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub accessed: Option<i64>,
+    // 命中的大小阈值规则名称
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub flags: Vec<String>,
+    // 快速估算时按抽样推算大小的目录及其置信区间，精确统计的条目为空
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub estimate: Option<SizeEstimate>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum FlagTarget {
+    #[default]
+    Any,
+    File,
+    Dir,
+}
+
+// 大小阈值规则，例如“单个文件超过 1 GB”或“目录超过 50 GB”
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct FlagRule {
+    pub name: String,
+    #[serde(default)]
+    pub target: FlagTarget,
+    // 大小超过该字节数时标记
+    pub threshold: i64,
+}
+
+impl FlagRule {
+    pub fn matches(&self, size: i64, is_dir: bool) -> bool {
+        let target = match self.target {
+            FlagTarget::Any => true,
+            FlagTarget::File => !is_dir,
+            FlagTarget::Dir => is_dir,
+        };
+        target && size > self.threshold
+    }
+}
+
+// 返回条目命中的规则名称
+pub fn item_flags(rules: &[FlagRule], size: i64, is_dir: bool) -> Vec<String> {
+    rules
+        .iter()
+        .filter(|rule| rule.matches(size, is_dir))
+        .map(|rule| rule.name.clone())
+        .collect()
+}
+
+// 解析 "1GB"、"1.5GiB"、"500M"、"4096" 等大小写法；KB 等为 1000 进制，K 和 KiB 等为 1024 进制
+pub fn parse_size(text: &str) -> Option<i64> {
+    let text = text.trim();
+    let split = text
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(text.len());
+    let (number, suffix) = text.split_at(split);
+    let number: f64 = number.parse().ok()?;
+    let multiplier: f64 = match suffix.trim().to_ascii_uppercase().as_str() {
+        "" | "B" => 1.0,
+        "K" | "KIB" => 1024.0,
+        "M" | "MIB" => 1024.0f64.powi(2),
+        "G" | "GIB" => 1024.0f64.powi(3),
+        "T" | "TIB" => 1024.0f64.powi(4),
+        "KB" => 1e3,
+        "MB" => 1e6,
+        "GB" => 1e9,
+        "TB" => 1e12,
+        _ => return None,
+    };
+    Some((number * multiplier) as i64)
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+#[serde(default)]
+pub struct ScanOptions {
+    // 只统计超过该天数未修改的文件
+    pub older_than_days: Option<u64>,
+    // 只统计该天数内修改过的文件
+    pub newer_than_days: Option<u64>,
+    // 后台模式：以后台 IO 优先级读取目录并限制读取速率，减少对其他服务的影响
+    pub low_priority: bool,
+    // 按名称排除的文件和目录
+    pub excludes: Option<Vec<String>>,
+    // 是否跟随符号链接，为空时跟随
+    pub follow_symlinks: Option<bool>,
+    // 聚合时标记超过阈值的条目
+    pub flag_rules: Option<Vec<FlagRule>>,
+    // 只统计这些扩展名的文件（不区分大小写，可带前导点）
+    pub include_ext: Option<Vec<String>>,
+    // 不统计这些扩展名的文件
+    pub exclude_ext: Option<Vec<String>>,
+    // 记录文件的最后访问时间，见 unused_since
+    pub record_access_time: bool,
+    // 检查权限过宽的文件和目录，结果见 ScanResult::audit
+    pub audit: bool,
+    // 快速估算：条目很多的目录只抽样统计，推算出的大小带置信区间
+    pub quick_estimate: bool,
+    // scan_stream 是否为每个文件产生 Item 事件；不影响扫描结果
+    #[serde(skip)]
+    pub stream_items: bool,
+}
+
+impl ScanOptions {
+    // 带过滤条件的扫描结果只反映部分文件，估算结果也不是精确值
+    pub fn is_unfiltered(&self) -> bool {
+        self.older_than_days.is_none()
+            && self.newer_than_days.is_none()
+            && self.extension_filter().is_empty()
+            && !self.quick_estimate
+    }
+
+    pub fn extension_filter(&self) -> ExtensionFilter {
+        ExtensionFilter::new(self.include_ext.as_deref(), self.exclude_ext.as_deref())
+    }
+
+    // 将天数换算为修改时间（Unix 秒）的下界和上界
+    fn modified_range(&self) -> (Option<i64>, Option<i64>) {
+        let now = chrono::Utc::now().timestamp();
+        let cutoff = |days: u64| now - days as i64 * 86400;
+        (
+            self.newer_than_days.map(cutoff),
+            self.older_than_days.map(cutoff),
+        )
+    }
+}
+
+fn in_modified_range(range: (Option<i64>, Option<i64>), modified: Option<i64>) -> bool {
+    match (range, modified) {
+        ((None, None), _) => true,
+        // 设置了时间过滤但无法获取修改时间时排除该文件
+        (_, None) => false,
+        ((min, max), Some(modified)) => {
+            min.is_none_or(|min| modified >= min) && max.is_none_or(|max| modified < max)
+        }
+    }
+}
+
+// 按扩展名过滤文件，没有扩展名的文件对应空字符串
+#[derive(Debug, Clone, Default)]
+pub struct ExtensionFilter {
+    include: Option<HashSet<String>>,
+    exclude: HashSet<String>,
+}
+
+impl ExtensionFilter {
+    pub fn new(include: Option<&[String]>, exclude: Option<&[String]>) -> Self {
+        let normalize = |ext: &String| ext.trim().trim_start_matches('.').to_lowercase();
+        ExtensionFilter {
+            include: include.map(|exts| exts.iter().map(normalize).collect()),
+            exclude: exclude.iter().copied().flatten().map(normalize).collect(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.include.is_none() && self.exclude.is_empty()
+    }
+
+    pub fn matches(&self, path: &Path) -> bool {
+        let extension = path
+            .extension()
+            .map(|e| e.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        self.include
+            .as_ref()
+            .is_none_or(|include| include.contains(&extension))
+            && !self.exclude.contains(&extension)
+    }
+}
+
+// 文件路径 -> (大小, 修改时间, 访问时间)
+type FileMap = HashMap<PathBuf, (i64, Option<i64>, Option<i64>)>;
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct UnusedReport {
+    pub path: String,
+    pub days: u64,
+    // 访问时间不可靠时的提示
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub warning: Option<String>,
+    pub total_size: i64,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub total_size_formatted: String,
+    pub items: Vec<Item>,
+}
+
+// 超过 days 天未被读取且不小于 min_size 的文件，按大小降序。
+// 访问时间只是尽力而为：noatime 挂载下从不更新，relatime（Linux 默认）下
+// 每天最多更新一次，Windows 也可能关闭了最后访问时间的更新
+pub fn unused_since(
+    result: &ScanResult,
+    days: u64,
+    min_size: i64,
+    unit: SizeUnit,
+) -> UnusedReport {
+    let cutoff = chrono::Utc::now().timestamp() - days as i64 * 86400;
+    let mut items: Vec<Item> = result
+        .items
+        .iter()
+        .filter(|item| {
+            !item.is_dir
+                && item.size >= min_size
+                && item.accessed.is_some_and(|accessed| accessed < cutoff)
+        })
+        .cloned()
+        .collect();
+    items.sort_by_key(|item| std::cmp::Reverse(item.size));
+
+    let total_size = items.iter().map(|item| item.size).sum();
+    UnusedReport {
+        path: result.path.clone(),
+        days,
+        warning: atime_warning(Path::new(&result.path)),
+        total_size,
+        total_size_formatted: format_size(total_size, unit),
+        items,
+    }
+}
+
+// 路径所在文件系统以 noatime 挂载时访问时间不会更新
+#[cfg(target_os = "linux")]
+pub fn atime_warning(path: &Path) -> Option<String> {
+    let path = std::fs::canonicalize(path).ok()?;
+    let mounts = std::fs::read_to_string("/proc/self/mounts").ok()?;
+    let (_, options) = mounts
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let mount_point = fields.nth(1)?.replace("\\040", " ");
+            let options = fields.nth(1)?;
+            Some((mount_point, options))
+        })
+        .filter(|(mount_point, _)| path.starts_with(mount_point))
+        .max_by_key(|(mount_point, _)| mount_point.len())?;
+    options
+        .split(',')
+        .any(|option| option == "noatime")
+        .then(|| "该路径所在的文件系统以 noatime 挂载，访问时间不会更新".to_string())
+}
+
+// NtfsDisableLastAccessUpdate 的最低位为 1 时 NTFS 不更新访问时间
+#[cfg(target_os = "windows")]
+pub fn atime_warning(_path: &Path) -> Option<String> {
+    use windows_sys::Win32::Foundation::ERROR_SUCCESS;
+    use windows_sys::Win32::System::Registry::{
+        RegGetValueW, HKEY_LOCAL_MACHINE, RRF_RT_REG_DWORD,
+    };
+
+    let wide = |s: &str| s.encode_utf16().chain(std::iter::once(0)).collect::<Vec<u16>>();
+    let subkey = wide(r"SYSTEM\CurrentControlSet\Control\FileSystem");
+    let name = wide("NtfsDisableLastAccessUpdate");
+    let mut value: u32 = 0;
+    let mut size = std::mem::size_of::<u32>() as u32;
+    // SAFETY: 字符串以 0 结尾，value 和 size 在调用期间有效
+    let code = unsafe {
+        RegGetValueW(
+            HKEY_LOCAL_MACHINE,
+            subkey.as_ptr(),
+            name.as_ptr(),
+            RRF_RT_REG_DWORD,
+            std::ptr::null_mut(),
+            &mut value as *mut u32 as *mut _,
+            &mut size,
+        )
+    };
+    (code == ERROR_SUCCESS && value & 1 == 1)
+        .then(|| "系统已关闭 NTFS 最后访问时间的更新，访问时间不可靠".to_string())
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "windows")))]
+pub fn atime_warning(_path: &Path) -> Option<String> {
+    None
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum SortKey {
+    #[default]
+    Size,
+    Name,
+    Path,
+}
+
+// 正在进行的扫描，键为规范路径加扫描选项
+type Flight = Arc<tokio::sync::OnceCell<Result<ScanResult, String>>>;
+
+static IN_FLIGHT: LazyLock<std::sync::Mutex<HashMap<String, Flight>>> =
+    LazyLock::new(Default::default);
+
+// 低优先级模式下每读取这么多目录暂停一次，给其他程序让出磁盘
+const LOW_PRIORITY_DIR_BATCH: usize = 64;
+const LOW_PRIORITY_PAUSE: std::time::Duration = std::time::Duration::from_millis(10);
+
+// 遍历的最大目录深度，兜底无法识别的循环
+const MAX_WALK_DEPTH: usize = 256;
+// 最多记录的跳过目录数
+const MAX_SKIPPED_DIRS: usize = 100;
+
+// 已进入的目录，用于发现绑定挂载或符号链接造成的目录循环和重复目录
+#[derive(Default)]
+struct VisitedDirs {
+    // 目录 ID -> 首次进入时的路径
+    #[cfg(unix)]
+    ids: HashMap<(u64, u64), PathBuf>,
+    #[cfg(not(unix))]
+    ids: HashSet<PathBuf>,
+}
+
+enum Visit {
+    First,
+    // 重新进入当前路径的祖先，继续遍历不会结束
+    Cycle,
+    // 从另一条路径（绑定挂载、链接）再次到达已统计的目录
+    Duplicate,
+}
+
+impl Visit {
+    fn skip_reason(&self) -> Option<&'static str> {
+        match self {
+            Visit::First => None,
+            Visit::Cycle => Some("目录循环"),
+            Visit::Duplicate => Some("重复目录"),
+        }
+    }
+}
+
+impl VisitedDirs {
+    // 祖先总是先于子孙进入，首次进入的路径是当前路径的前缀即为循环
+    #[cfg(unix)]
+    fn enter(&mut self, path: &Path, metadata: &std::fs::Metadata) -> Visit {
+        use std::os::unix::fs::MetadataExt;
+        let id = (metadata.dev(), metadata.ino());
+        match self.ids.get(&id) {
+            None => {
+                self.ids.insert(id, path.to_path_buf());
+                Visit::First
+            }
+            Some(first) if path.starts_with(first) => Visit::Cycle,
+            Some(_) => Visit::Duplicate,
+        }
+    }
+
+    // 没有稳定的文件 ID 接口：普通目录不会成环，只解析链接（含目录联接）的目标，
+    // 目标是链接所在目录的祖先时为循环，已经进入过时为重复
+    #[cfg(not(unix))]
+    fn enter(&mut self, path: &Path, _metadata: &std::fs::Metadata) -> Visit {
+        if !std::fs::symlink_metadata(path).is_ok_and(|m| m.file_type().is_symlink()) {
+            return Visit::First;
+        }
+        let Ok(target) = std::fs::canonicalize(path) else {
+            return Visit::First;
+        };
+        let parent = path.parent().and_then(|parent| std::fs::canonicalize(parent).ok());
+        if parent.is_some_and(|parent| parent.starts_with(&target)) {
+            return Visit::Cycle;
+        }
+        if self.ids.insert(target) {
+            Visit::First
+        } else {
+            Visit::Duplicate
+        }
+    }
+}
+
+struct WalkContext {
+    modified_range: (Option<i64>, Option<i64>),
+    low_priority: bool,
+    dirs_read: AtomicUsize,
+    // 无法读取的子目录或元数据数量
+    errors: AtomicUsize,
+    excludes: HashSet<String>,
+    follow_symlinks: bool,
+    extensions: ExtensionFilter,
+    record_access_time: bool,
+    audit: Option<AuditCollector>,
+    sampling: Option<Sampling>,
+    visited: std::sync::Mutex<VisitedDirs>,
+    skipped_dirs: std::sync::Mutex<Vec<String>>,
+    observers: Vec<Arc<dyn ScanObserver>>,
+    events: Option<Arc<EventSink>>,
+}
+
+impl WalkContext {
+    fn new(options: &ScanOptions, root: &Path) -> Self {
+        let mut visited = VisitedDirs::default();
+        if let Ok(metadata) = std::fs::metadata(root) {
+            visited.enter(root, &metadata);
+        }
+        WalkContext {
+            modified_range: options.modified_range(),
+            low_priority: options.low_priority,
+            dirs_read: AtomicUsize::new(0),
+            errors: AtomicUsize::new(0),
+            excludes: options.excludes.iter().flatten().cloned().collect(),
+            follow_symlinks: options.follow_symlinks.unwrap_or(true),
+            extensions: options.extension_filter(),
+            record_access_time: options.record_access_time,
+            audit: options.audit.then(AuditCollector::default),
+            sampling: options.quick_estimate.then(|| Sampling::new(root)),
+            visited: std::sync::Mutex::new(visited),
+            skipped_dirs: std::sync::Mutex::new(Vec::new()),
+            observers: Vec::new(),
+            events: None,
+        }
+    }
+
+    // 计入错误数并通知观察者
+    fn error(&self, path: &Path, error: &dyn std::fmt::Display) {
+        self.errors.fetch_add(1, Ordering::Relaxed);
+        if !self.observers.is_empty() || self.events.is_some() {
+            let error = error.to_string();
+            for observer in &self.observers {
+                observer.on_error(path, &error);
+            }
+            if let Some(events) = &self.events {
+                events.error(path, &error);
+            }
+        }
+    }
+
+    fn skip_dir(&self, path: &Path, reason: &str) {
+        self.error(path, &reason);
+        let mut skipped = self.skipped_dirs.lock().unwrap();
+        if skipped.len() < MAX_SKIPPED_DIRS {
+            skipped.push(format!("{}: {}", path.display(), reason));
+        }
+    }
+
+    fn is_excluded(&self, entry: &std::fs::DirEntry) -> bool {
+        entry
+            .file_name()
+            .to_str()
+            .is_some_and(|name| self.excludes.contains(name))
+    }
+
+    // 在阻塞线程上执行文件系统调用。tokio::fs 的每次调用可能落在任意线程上，
+    // 低优先级扫描无法为其设置 IO 优先级，因此在这里进入后台模式，与桌面版一致
+    async fn blocking<T: Send + 'static>(
+        &self,
+        f: impl FnOnce() -> T + Send + 'static,
+    ) -> Result<T, tokio::task::JoinError> {
+        let low_priority = self.low_priority;
+        tokio::task::spawn_blocking(move || {
+            let _background = low_priority.then(BackgroundIo::enter);
+            f()
+        })
+        .await
+    }
+
+    // 目录中未被排除的条目
+    async fn read_dir(
+        &self,
+        path: &Path,
+    ) -> Result<Vec<std::fs::DirEntry>, Box<dyn std::error::Error + Send + Sync>> {
+        let dir = path.to_path_buf();
+        let mut entries = self
+            .blocking(move || std::fs::read_dir(dir)?.collect::<std::io::Result<Vec<_>>>())
+            .await??;
+        entries.retain(|entry| !self.is_excluded(entry));
+        Ok(entries)
+    }
+
+    // 条目的路径和元数据，跟随链接时为目标的元数据；跳过的链接和无法读取的条目不返回
+    async fn metadata(
+        &self,
+        entries: Vec<std::fs::DirEntry>,
+    ) -> Vec<(std::fs::DirEntry, std::fs::Metadata)> {
+        let follow_symlinks = self.follow_symlinks;
+        let read = self.blocking(move || {
+            entries
+                .into_iter()
+                .filter_map(|entry| {
+                    let metadata = match entry.metadata() {
+                        Ok(metadata) if metadata.is_symlink() => {
+                            if !follow_symlinks {
+                                return None;
+                            }
+                            std::fs::metadata(entry.path())
+                        }
+                        metadata => metadata,
+                    };
+                    Some((entry, metadata))
+                })
+                .collect::<Vec<_>>()
+        });
+        let Ok(read) = read.await else {
+            return Vec::new();
+        };
+        read.into_iter()
+            .filter_map(|(entry, metadata)| match metadata {
+                Ok(metadata) => Some((entry, metadata)),
+                Err(e) => {
+                    self.error(&entry.path(), &e);
+                    None
+                }
+            })
+            .collect()
+    }
+
+    // 不满足时间或扩展名过滤条件的文件为空
+    fn walked_file(
+        &self,
+        path: &Path,
+        metadata: &std::fs::Metadata,
+        weight: f64,
+    ) -> Option<WalkedFile> {
+        let modified = metadata
+            .modified()
+            .ok()
+            .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+            .map(|d| d.as_secs() as i64);
+        if !in_modified_range(self.modified_range, modified) || !self.extensions.matches(path) {
+            return None;
+        }
+        let accessed = self
+            .record_access_time
+            .then(|| metadata.accessed().ok())
+            .flatten()
+            .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+            .map(|d| d.as_secs() as i64);
+        Some(WalkedFile {
+            path: path.to_path_buf(),
+            size: metadata.len() as i64,
+            modified,
+            accessed,
+            weight,
+        })
+    }
+}
+
+// 扫描进行中的阶段性结果：目前已统计到的顶层子项大小
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ScanSnapshot {
+    pub files: usize,
+    pub total_size: i64,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub total_size_formatted: String,
+    pub items: Vec<Item>,
+}
+
+// 调用方用于在扫描期间读取阶段性结果
+#[derive(Debug, Default)]
+pub struct ScanProgress {
+    files: AtomicUsize,
+    // 顶层子项名称 -> (已统计的大小, 是否为目录)
+    top_level: std::sync::Mutex<HashMap<String, (i64, bool)>>,
+}
+
+impl ScanProgress {
+    // 目前已统计的文件数
+    pub fn files(&self) -> usize {
+        self.files.load(Ordering::Relaxed)
+    }
+
+    pub fn snapshot(&self, unit: SizeUnit, format: SizeFormat) -> ScanSnapshot {
+        let formatted = |size| match format {
+            SizeFormat::Human => format_size(size, unit),
+            SizeFormat::None => String::new(),
+        };
+        let mut items: Vec<Item> = self
+            .top_level
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(name, &(size, is_dir))| Item {
+                path: name.clone(),
+                abs_path: String::new(),
+                parent: String::new(),
+                encoding_lossy: false,
+                size,
+                size_formatted: formatted(size),
+                is_dir,
+                modified: None,
+                accessed: None,
+                flags: Vec::new(),
+                estimate: None,
+            })
+            .collect();
+        items.sort_by_key(|item| std::cmp::Reverse(item.size));
+
+        let total_size = items.iter().map(|item| item.size).sum();
+        ScanSnapshot {
+            files: self.files.load(Ordering::Relaxed),
+            total_size,
+            total_size_formatted: formatted(total_size),
+            items,
+        }
+    }
+
+    fn record(&self, root: &Path, file: &Path, size: i64) {
+        let Ok(rel_path) = file.strip_prefix(root) else {
+            return;
+        };
+        let mut components = rel_path.components();
+        let Some(first) = components.next() else {
+            return;
+        };
+        let is_dir = components.next().is_some();
+        let name = first.as_os_str().to_string_lossy().to_string();
+        self.files.fetch_add(1, Ordering::Relaxed);
+        let mut top_level = self.top_level.lock().unwrap();
+        top_level.entry(name).or_insert((0, is_dir)).0 += size;
+    }
+}
+
+impl ScanObserver for ScanProgress {
+    fn on_item(&self, root: &Path, path: &Path, size: i64) {
+        self.record(root, path, size);
+    }
+}
+
+struct WalkedFile {
+    path: PathBuf,
+    size: i64,
+    modified: Option<i64>,
+    accessed: Option<i64>,
+    // 快速估算时该文件代表的文件数，精确统计时为 1
+    weight: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct ScanResult {
     pub items: Vec<Item>,
     pub total_size: i64,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
     pub total_size_formatted: String,
     pub scan_time: f64,
     pub path: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub treemap: Option<TreemapNode>,
+    // 结果存储中的 ID，后续操作可直接引用
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub result_id: Option<String>,
+    // 本次扫描的性能统计，来自历史的结果为空
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stats: Option<ScanStats>,
+    // 根目录本身的统计，区分直接位于根目录下的文件和子目录中的文件
+    #[serde(default)]
+    pub root: RootSummary,
+    // 权限审计结果，只在开启 audit 时提供
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub audit: Option<AuditReport>,
+    // 快速估算时总大小的置信区间
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub estimate: Option<SizeEstimate>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+pub struct RootSummary {
+    // 直接位于根目录下的文件
+    pub direct_files: usize,
+    pub direct_size: i64,
+    // 根目录下的直接子目录
+    pub direct_dirs: usize,
+    // 整个子树中的文件和（非空）目录
+    pub files: usize,
+    pub dirs: usize,
+    pub subtree_size: i64,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl RootSummary {
+    // items 需要是完整的扫描结果，按层级截断后的结果会少算
+    pub fn from_items(items: &[Item]) -> Self {
+        let mut summary = RootSummary::default();
+        for item in items {
+            let direct = Path::new(&item.path).components().count() == 1;
+            if item.is_dir {
+                summary.dirs += 1;
+                summary.direct_dirs += direct as usize;
+            } else {
+                summary.files += 1;
+                summary.subtree_size += item.size;
+                if direct {
+                    summary.direct_files += 1;
+                    summary.direct_size += item.size;
+                }
+            }
+        }
+        summary
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+pub struct ScanStats {
+    pub files: usize,
+    pub dirs_visited: usize,
+    // 无法读取的子目录或元数据数量
+    pub errors: usize,
+    pub files_per_sec: f64,
+    // 各阶段耗时（秒）；遍历与累加目录大小并发进行，计入 walk_time
+    pub walk_time: f64,
+    pub aggregate_time: f64,
+    pub sort_time: f64,
+    pub wall_time: f64,
+    // 进程峰值内存（字节），平台不支持时为空
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub peak_memory: Option<u64>,
+    // 因目录循环、重复到达或超过深度上限而跳过的目录，也计入 errors
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub skipped_dirs: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct HistoryItem {
     pub path: String,
     #[serde(with = "chrono::serde::ts_seconds")]
+    #[schema(value_type = i64)]
     pub scan_time: chrono::DateTime<chrono::Utc>,
     pub total_size: i64,
     pub size_format: String,
     pub items: Vec<Item>,
+    // 用户附加的备注，如“清理 node_modules 之前”
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
 }
 
-pub fn format_size(bytes: i64) -> String {
-    if bytes < 1024 {
+impl HistoryItem {
+    // 空白备注视为删除备注
+    pub fn set_note(&mut self, note: &str) {
+        let note = note.trim();
+        self.note = (!note.is_empty()).then(|| note.to_string());
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct TrendPoint {
+    #[serde(with = "chrono::serde::ts_seconds")]
+    #[schema(value_type = i64)]
+    pub scan_time: chrono::DateTime<chrono::Utc>,
+    pub total_size: i64,
+    // 顶层子项路径 -> 大小
+    pub children: BTreeMap<String, i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct Trend {
+    pub path: String,
+    pub points: Vec<TrendPoint>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ExtensionReport {
+    pub extension: String,
+    pub total_size: i64,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub total_size_formatted: String,
+    pub count: usize,
+    pub items: Vec<Item>,
+}
+
+// SEARCH_TOOL_THREADS 环境变量配置的工作线程数
+pub fn configured_threads() -> Option<usize> {
+    std::env::var("SEARCH_TOOL_THREADS")
+        .ok()
+        .and_then(|value| value.trim().parse().ok())
+        .filter(|&threads| threads > 0)
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum SizeUnit {
+    // 1024 进制（KiB/MiB）
+    #[default]
+    Binary,
+    // 1000 进制（KB/MB）
+    Decimal,
+}
+
+pub fn format_size(bytes: i64, unit: SizeUnit) -> String {
+    let (base, labels) = match unit {
+        SizeUnit::Binary => (1024.0, ["KiB", "MiB", "GiB", "TiB"]),
+        SizeUnit::Decimal => (1000.0, ["KB", "MB", "GB", "TB"]),
+    };
+    if (bytes.unsigned_abs() as f64) < base {
         return format!("{} B", bytes);
     }
-    let kb = bytes as f64 / 1024.0;
-    if kb < 1024.0 {
-        return format!("{:.1} KB", kb);
+
+    let mut value = bytes as f64 / base;
+    let mut label = labels[0];
+    for next in &labels[1..] {
+        if value.abs() < base {
+            break;
+        }
+        value /= base;
+        label = next;
+    }
+    format!("{:.1} {}", value, label)
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum SizeFormat {
+    #[default]
+    Human,
+    // 只返回字节数，由客户端自行格式化，可明显减小大结果的响应体
+    None,
+}
+
+// 扫描时统一按二进制单位格式化，响应前按请求的单位和格式重新生成
+pub fn format_sizes(result: &mut ScanResult, unit: SizeUnit, format: SizeFormat) {
+    match format {
+        SizeFormat::None => result.total_size_formatted.clear(),
+        SizeFormat::Human if unit != SizeUnit::Binary => {
+            result.total_size_formatted = format_size(result.total_size, unit);
+        }
+        SizeFormat::Human => {}
+    }
+    format_item_sizes(&mut result.items, unit, format);
+}
+
+pub fn format_item_sizes(items: &mut [Item], unit: SizeUnit, format: SizeFormat) {
+    match format {
+        SizeFormat::None => items.iter_mut().for_each(|item| item.size_formatted.clear()),
+        SizeFormat::Human if unit != SizeUnit::Binary => {
+            for item in items {
+                item.size_formatted = format_size(item.size, unit);
+            }
+        }
+        SizeFormat::Human => {}
     }
-    let mb = kb / 1024.0;
-    if mb < 1024.0 {
-        return format!("{:.1} MB", mb);
+}
+
+// 相对路径的父目录，一级子项为空字符串
+pub fn parent_of(rel_path: &str) -> String {
+    Path::new(&native_separators(rel_path))
+        .parent()
+        .map(|parent| parent.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+// 条目的绝对路径
+pub fn abs_path(root: &str, rel_path: &str) -> String {
+    Path::new(&paths::display(root))
+        .join(native_separators(rel_path))
+        .to_string_lossy()
+        .to_string()
+}
+
+// 根据扫描根目录填写条目的绝对路径和父目录
+pub fn fill_item_paths(root: &str, items: &mut [Item]) {
+    let root = PathBuf::from(paths::display(root));
+    for item in items {
+        let rel_path = native_separators(&item.path);
+        item.abs_path = root.join(&rel_path).to_string_lossy().to_string();
+        item.parent = parent_of(&rel_path);
+    }
+}
+
+// 从历史快照生成按时间升序排列的增长趋势
+pub fn build_trend(history: &[HistoryItem], path: &str) -> Trend {
+    let key = path_key(path);
+    let mut points: Vec<TrendPoint> = history
+        .iter()
+        .filter(|item| path_key(&item.path) == key)
+        .map(|item| {
+            let children = item
+                .items
+                .iter()
+                .filter(|child| Path::new(&child.path).components().count() == 1)
+                .map(|child| (child.path.clone(), child.size))
+                .collect();
+            TrendPoint {
+                scan_time: item.scan_time,
+                total_size: item.total_size,
+                children,
+                note: item.note.clone(),
+            }
+        })
+        .collect();
+
+    points.sort_by_key(|point| point.scan_time);
+
+    Trend {
+        path: path.to_string(),
+        points,
+    }
+}
+
+// 按请求裁剪结果：只保留不超过 depth 层的条目，并按指定字段排序（大小降序，名称和路径升序）
+pub fn shape_result(result: &mut ScanResult, depth: Option<usize>, sort: SortKey) {
+    if let Some(depth) = depth {
+        result
+            .items
+            .retain(|item| Path::new(&item.path).components().count() <= depth);
+    }
+
+    match sort {
+        SortKey::Size => result
+            .items
+            .sort_by_key(|item| std::cmp::Reverse(item.size)),
+        SortKey::Name => result.items.sort_by(|a, b| {
+            let name = |item: &Item| {
+                Path::new(&item.path)
+                    .file_name()
+                    .map(|n| n.to_string_lossy().to_lowercase())
+                    .unwrap_or_default()
+            };
+            name(a).cmp(&name(b))
+        }),
+        SortKey::Path => result.items.sort_by(|a, b| a.path.cmp(&b.path)),
+    }
+}
+
+// 统计指定扩展名（不区分大小写，可带前导点）的文件，返回最大的 n 个
+pub fn top_by_extension(
+    result: &ScanResult,
+    ext: &str,
+    n: usize,
+    unit: SizeUnit,
+) -> ExtensionReport {
+    let extension = ext.trim().trim_start_matches('.').to_lowercase();
+
+    // 结果中的条目已按大小降序排列
+    let matching: Vec<&Item> = result
+        .items
+        .iter()
+        .filter(|item| !item.is_dir)
+        .filter(|item| {
+            Path::new(&item.path)
+                .extension()
+                .map(|e| e.to_string_lossy().to_lowercase() == extension)
+                .unwrap_or(false)
+        })
+        .collect();
+
+    let total_size: i64 = matching.iter().map(|item| item.size).sum();
+    let count = matching.len();
+    let mut items: Vec<Item> = matching.into_iter().take(n).cloned().collect();
+    format_item_sizes(&mut items, unit, SizeFormat::Human);
+
+    ExtensionReport {
+        extension,
+        total_size,
+        total_size_formatted: format_size(total_size, unit),
+        count,
+        items,
+    }
+}
+
+pub async fn scan_directory(
+    path: &str,
+    options: &ScanOptions,
+) -> Result<ScanResult, Box<dyn std::error::Error + Send + Sync>> {
+    // 只关心结果时事件流中只有最后的完成或失败事件
+    let mut events = crate::stream::scan_result_stream(path, options);
+    while let Some(event) = events.next().await {
+        match event {
+            ScanEvent::Completed(result) => return Ok(*result),
+            ScanEvent::Failed(error) => return Err(error.into()),
+            _ => {}
+        }
     }
-    let gb = mb / 1024.0;
-    format!("{:.1} GB", gb)
+    Err("扫描意外结束".into())
+}
+
+pub async fn scan_directory_with_progress(
+    path: &str,
+    options: &ScanOptions,
+    progress: Option<Arc<ScanProgress>>,
+) -> Result<ScanResult, Box<dyn std::error::Error + Send + Sync>> {
+    let observers: Vec<Arc<dyn ScanObserver>> = match progress {
+        Some(progress) => vec![progress],
+        None => Vec::new(),
+    };
+    scan_directory_observed(path, options, &observers).await
+}
+
+// 有观察者的扫描不与其他请求共享，事件和阶段性结果只反映本次扫描
+pub async fn scan_directory_observed(
+    path: &str,
+    options: &ScanOptions,
+    observers: &[Arc<dyn ScanObserver>],
+) -> Result<ScanResult, Box<dyn std::error::Error + Send + Sync>> {
+    scan_directory_events(path, options, observers, None).await
 }
 
-pub async fn scan_directory(path: &str) -> Result<ScanResult, Box<dyn std::error::Error>> {
+// events 为 scan_stream 的发送端，与观察者一样使扫描不再与其他请求共享
+pub(crate) async fn scan_directory_events(
+    path: &str,
+    options: &ScanOptions,
+    observers: &[Arc<dyn ScanObserver>],
+    events: Option<Arc<EventSink>>,
+) -> Result<ScanResult, Box<dyn std::error::Error + Send + Sync>> {
     let start_time = std::time::Instant::now();
 
     if path.is_empty() {
@@ -63,32 +1035,248 @@ pub async fn scan_directory(path: &str) -> Result<ScanResult, Box<dyn std::error
     }
 
     let canonical_path = fs::canonicalize(&path_buf).await?;
+
+    if !observers.is_empty() || events.is_some() {
+        for observer in observers {
+            observer.on_start(&canonical_path);
+        }
+        if let Some(events) = &events {
+            events.start(&canonical_path).await;
+        }
+        let outcome = scan_uncached(
+            canonical_path.clone(),
+            options,
+            start_time,
+            observers.to_vec(),
+            events,
+        )
+        .await;
+        let mut result = match outcome {
+            Ok(result) => result,
+            Err(e) => {
+                let error = e.to_string();
+                for observer in observers {
+                    observer.on_error(&canonical_path, &error);
+                }
+                return Err(e);
+            }
+        };
+        result.path = path.to_string();
+        for observer in observers {
+            observer.on_complete(&result);
+        }
+        return Ok(result);
+    }
+
+    // 相同根目录和选项的并发请求共享同一次扫描，避免重复遍历
+    let flight_key = format!(
+        "{}|{}",
+        paths::key(&canonical_path.to_string_lossy()),
+        serde_json::to_string(options).unwrap_or_default()
+    );
+    let flight = IN_FLIGHT
+        .lock()
+        .unwrap()
+        .entry(flight_key.clone())
+        .or_default()
+        .clone();
+    let shared = flight
+        .get_or_init(|| async {
+            scan_uncached(canonical_path, options, start_time, Vec::new(), None)
+                .await
+                .map_err(|e| e.to_string())
+        })
+        .await
+        .clone();
+    {
+        let mut in_flight = IN_FLIGHT.lock().unwrap();
+        if in_flight
+            .get(&flight_key)
+            .is_some_and(|f| Arc::ptr_eq(f, &flight))
+        {
+            in_flight.remove(&flight_key);
+        }
+    }
+
+    let mut result = shared?;
+    result.path = path.to_string();
+    Ok(result)
+}
+
+// 按一级子项分批扫描：先发送根目录下的文件，之后每统计完一个一级子目录就发送该目录
+// 及其下的全部条目，路径相对于根目录。结果不包含审计报告。
+// 接收端关闭时提前结束，返回已统计部分的汇总
+pub async fn scan_by_top_level(
+    path: &str,
+    options: &ScanOptions,
+    tx: mpsc::Sender<Vec<Item>>,
+) -> Result<RootSummary, Box<dyn std::error::Error + Send + Sync>> {
+    if path.is_empty() {
+        return Err("路径不能为空".into());
+    }
+    if !fs::metadata(path).await?.is_dir() {
+        return Err("不是目录".into());
+    }
+    let root = fs::canonicalize(path).await?;
+    let options = ScanOptions {
+        audit: false,
+        ..options.clone()
+    };
+    let context = WalkContext::new(&options, &root);
+    let flag_rules = options.flag_rules.as_deref().unwrap_or_default();
+
+    let mut files = Vec::new();
+    let mut dirs = Vec::new();
+    let entries = context.read_dir(&root).await?;
+    for (entry, metadata) in context.metadata(entries).await {
+        let name = entry.file_name();
+        if metadata.is_dir() {
+            dirs.push(name);
+        } else if let Some(file) = context.walked_file(&entry.path(), &metadata, 1.0) {
+            files.push(Item {
+                path: name.to_string_lossy().to_string(),
+                abs_path: String::new(),
+                parent: String::new(),
+                encoding_lossy: name.to_str().is_none(),
+                size: file.size,
+                size_formatted: format_size(file.size, SizeUnit::Binary),
+                is_dir: false,
+                modified: file.modified,
+                accessed: file.accessed,
+                flags: item_flags(flag_rules, file.size, false),
+                estimate: None,
+            });
+        }
+    }
+
+    let root_dir = root.to_string_lossy().to_string();
+    fill_item_paths(&root_dir, &mut files);
+    let mut summary = RootSummary {
+        direct_files: files.len(),
+        direct_size: files.iter().map(|item| item.size).sum(),
+        ..RootSummary::default()
+    };
+    summary.files = summary.direct_files;
+    summary.subtree_size = summary.direct_size;
+    if !files.is_empty() {
+        files.sort_by_key(|item| std::cmp::Reverse(item.size));
+        if tx.send(files).await.is_err() {
+            return Ok(summary);
+        }
+    }
+
+    dirs.sort();
+    for name in dirs {
+        // 直接按 PathBuf 扫描，目录名不是有效的 UTF-8 时也能统计；子目录不可读时跳过，
+        // 与整体扫描一致
+        let Ok(dir_path) = fs::canonicalize(root.join(&name)).await else {
+            continue;
+        };
+        let scan =
+            scan_uncached(dir_path, &options, std::time::Instant::now(), Vec::new(), None);
+        let Ok(child) = scan.await else {
+            continue;
+        };
+        // 没有统计到文件的目录在整体扫描中也不会出现
+        if child.root.files == 0 {
+            continue;
+        }
+
+        let lossy = name.to_str().is_none();
+        let mut batch = Vec::with_capacity(child.items.len() + 1);
+        batch.push(Item {
+            path: name.to_string_lossy().to_string(),
+            abs_path: String::new(),
+            parent: String::new(),
+            encoding_lossy: lossy,
+            size: child.total_size,
+            size_formatted: format_size(child.total_size, SizeUnit::Binary),
+            is_dir: true,
+            modified: None,
+            accessed: None,
+            flags: item_flags(flag_rules, child.total_size, true),
+            estimate: child.estimate,
+        });
+        batch.extend(child.items.into_iter().map(|mut item| {
+            item.path = Path::new(&name).join(&item.path).to_string_lossy().to_string();
+            item.encoding_lossy |= lossy;
+            item
+        }));
+        fill_item_paths(&root_dir, &mut batch);
+
+        summary.direct_dirs += 1;
+        summary.dirs += child.root.dirs + 1;
+        summary.files += child.root.files;
+        summary.subtree_size += child.total_size;
+        if tx.send(batch).await.is_err() {
+            break;
+        }
+    }
+
+    Ok(summary)
+}
+
+async fn scan_uncached(
+    canonical_path: PathBuf,
+    options: &ScanOptions,
+    start_time: std::time::Instant,
+    observers: Vec<Arc<dyn ScanObserver>>,
+    events: Option<Arc<EventSink>>,
+) -> Result<ScanResult, Box<dyn std::error::Error + Send + Sync>> {
     let root_dir = canonical_path.to_string_lossy().to_string();
 
     let dir_sizes = Arc::new(Mutex::new(HashMap::new()));
-    let file_sizes = Arc::new(Mutex::new(HashMap::new()));
+    let file_sizes: Arc<Mutex<FileMap>> = Arc::new(Mutex::new(HashMap::new()));
 
     // 使用并发工作池模式
-    let (tx, mut rx) = mpsc::channel::<(String, i64)>(1024);
+    let (tx, mut rx) = mpsc::channel::<WalkedFile>(1024);
     let dir_sizes_worker = Arc::clone(&dir_sizes);
-    let root_dir_clone = root_dir.clone();
+    let file_sizes_worker = Arc::clone(&file_sizes);
+    let root_path = canonical_path.clone();
+    let item_observers = observers.clone();
+    let item_events = events.clone();
 
     // 启动工作协程处理任务队列
     let handle = tokio::spawn(async move {
-        while let Some((file_path, size)) = rx.recv().await {
-            let mut current_dir = Path::new(&file_path).parent();
+        while let Some(WalkedFile {
+            path: file_path,
+            size,
+            modified,
+            accessed,
+            weight,
+        }) = rx.recv().await
+        {
+            for observer in &item_observers {
+                observer.on_item(&root_path, &file_path, size);
+            }
+            // 事件流的通道满时在这里等待，遍历随之放慢
+            if let Some(events) = &item_events {
+                events.item(&file_path, size).await;
+            }
+
+            file_sizes_worker
+                .lock()
+                .await
+                .insert(file_path.clone(), (size, modified, accessed));
+            // 抽样得到的文件按其代表的文件数计入上层目录
+            let size = if weight == 1.0 {
+                size
+            } else {
+                (size as f64 * weight).round() as i64
+            };
+
+            let mut current_dir = file_path.parent();
             while let Some(dir) = current_dir {
-                let dir_path = dir.to_string_lossy().to_string();
-                if dir_path == root_dir_clone || dir_path.is_empty() {
+                if dir == root_path || dir.as_os_str().is_empty() {
                     // 添加到根目录
-                    dir_sizes_worker.lock().await.entry(root_dir_clone.clone()).and_modify(|s| *s += size).or_insert(size);
+                    dir_sizes_worker.lock().await.entry(root_path.clone()).and_modify(|s| *s += size).or_insert(size);
                     break;
                 }
 
                 dir_sizes_worker
                     .lock()
                     .await
-                    .entry(dir_path.clone())
+                    .entry(dir.to_path_buf())
                     .and_modify(|s| *s += size)
                     .or_insert(size);
 
@@ -97,82 +1285,192 @@ pub async fn scan_directory(path: &str) -> Result<ScanResult, Box<dyn std::error
         }
     });
 
-    scan_recursive(&canonical_path, &root_dir, &tx).await?;
+    let mut context = WalkContext::new(options, &canonical_path);
+    context.observers = observers;
+    context.events = events;
+    let walk_start = std::time::Instant::now();
+    scan_recursive(&canonical_path, 0, &context, &tx).await?;
     drop(tx);
     handle.await?;
+    if let Some(events) = &context.events {
+        events.flush().await;
+    }
+    let walk_time = walk_start.elapsed().as_secs_f64();
 
-    let dir_sizes = dir_sizes.lock().await;
+    let mut dir_sizes = dir_sizes.lock().await;
     let file_sizes = file_sizes.lock().await;
+    let aggregate_start = std::time::Instant::now();
+
+    // 累计值除以目录自身的权重，得到各目录的估算大小，再计算置信区间
+    let mut estimates = HashMap::new();
+    if let Some(sampling) = &context.sampling {
+        for (dir, size) in dir_sizes.iter_mut() {
+            *size = (*size as f64 / sampling.weight(dir)).round() as i64;
+        }
+        estimates = sampling.bounds(&canonical_path, |path| {
+            dir_sizes
+                .get(path)
+                .copied()
+                .or_else(|| file_sizes.get(path).map(|&(size, _, _)| size))
+        });
+    }
 
     let mut items = Vec::new();
-    let mut total_size = 0i64;
+    let flag_rules = options.flag_rules.as_deref().unwrap_or_default();
 
     for (dir, size) in dir_sizes.iter() {
-        if dir == &root_dir {
+        if dir == &canonical_path {
             continue;
         }
 
-        if let Ok(rel_path) = Path::new(dir).strip_prefix(&root_dir) {
+        if let Ok(rel_path) = dir.strip_prefix(&canonical_path) {
             let rel_path_str = rel_path.to_string_lossy().to_string();
             if !rel_path_str.is_empty() {
                 items.push(Item {
                     path: rel_path_str,
+                    abs_path: String::new(),
+                    parent: String::new(),
+                    encoding_lossy: rel_path.to_str().is_none(),
                     size: *size,
-                    size_formatted: format_size(*size),
+                    size_formatted: format_size(*size, SizeUnit::Binary),
                     is_dir: true,
+                    modified: None,
+                    accessed: None,
+                    flags: item_flags(flag_rules, *size, true),
+                    estimate: estimates.get(dir).copied(),
                 });
-                total_size += size;
             }
         }
     }
 
-    for (file, size) in file_sizes.iter() {
-        if let Ok(rel_path) = Path::new(file).strip_prefix(&root_dir) {
+    for (file, (size, modified, accessed)) in file_sizes.iter() {
+        if let Ok(rel_path) = file.strip_prefix(&canonical_path) {
             let rel_path_str = rel_path.to_string_lossy().to_string();
             if !rel_path_str.is_empty() {
                 items.push(Item {
                     path: rel_path_str,
+                    abs_path: String::new(),
+                    parent: String::new(),
+                    encoding_lossy: rel_path.to_str().is_none(),
                     size: *size,
-                    size_formatted: format_size(*size),
+                    size_formatted: format_size(*size, SizeUnit::Binary),
                     is_dir: false,
+                    modified: *modified,
+                    accessed: *accessed,
+                    flags: item_flags(flag_rules, *size, false),
+                    estimate: None,
                 });
-                total_size += size;
             }
         }
     }
 
-    items.sort_by(|a, b| b.size.cmp(&a.size));
+    // 总大小只累计文件，目录大小已包含其中的文件
+    fill_item_paths(&root_dir, &mut items);
+    let mut root = RootSummary::from_items(&items);
+    // 估算模式下条目只包含抽到的文件，总大小取按权重推算的值，文件数仍为实际统计的数量
+    if context.sampling.is_some() {
+        root.subtree_size = dir_sizes.get(&canonical_path).copied().unwrap_or(0);
+    }
+    let total_size = root.subtree_size;
+    let aggregate_time = aggregate_start.elapsed().as_secs_f64();
+
+    let sort_start = std::time::Instant::now();
+    items.sort_by_key(|item| std::cmp::Reverse(item.size));
+    let sort_time = sort_start.elapsed().as_secs_f64();
 
     let scan_time = start_time.elapsed().as_secs_f64();
+    let files = file_sizes.len();
+    let stats = ScanStats {
+        files,
+        dirs_visited: context.dirs_read.load(Ordering::Relaxed),
+        errors: context.errors.load(Ordering::Relaxed),
+        files_per_sec: if scan_time > 0.0 { files as f64 / scan_time } else { 0.0 },
+        walk_time,
+        aggregate_time,
+        sort_time,
+        wall_time: scan_time,
+        peak_memory: peak_memory(),
+        skipped_dirs: std::mem::take(&mut *context.skipped_dirs.lock().unwrap()),
+    };
 
     Ok(ScanResult {
         items,
         total_size,
-        total_size_formatted: format_size(total_size),
+        total_size_formatted: format_size(total_size, SizeUnit::Binary),
         scan_time,
-        path: path.to_string(),
+        path: root_dir.clone(),
+        treemap: None,
+        result_id: None,
+        stats: Some(stats),
+        root,
+        audit: context
+            .audit
+            .as_ref()
+            .map(|audit| audit.report(&canonical_path)),
+        estimate: estimates.get(&canonical_path).copied(),
     })
 }
 
 async fn scan_recursive(
     path: &Path,
-    root_dir: &str,
-    tx: &mpsc::Sender<(String, i64)>,
-) -> Result<(), Box<dyn std::error::Error>> {
-    let mut entries = fs::read_dir(path).await?;
+    depth: usize,
+    context: &WalkContext,
+    tx: &mpsc::Sender<WalkedFile>,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let dirs_read = context.dirs_read.fetch_add(1, Ordering::Relaxed) + 1;
+    if context.low_priority && dirs_read.is_multiple_of(LOW_PRIORITY_DIR_BATCH) {
+        tokio::time::sleep(LOW_PRIORITY_PAUSE).await;
+    }
 
-    while let Some(entry) = entries.next_entry().await? {
+    let mut entries = context.read_dir(path).await?;
+
+    // 快速估算时条目过多的目录只处理抽到的部分
+    let weight = match &context.sampling {
+        Some(sampling) => sampling.sample(path, &mut entries, |entry| entry.path()),
+        None => 1.0,
+    };
+
+    for (entry, metadata) in context.metadata(entries).await {
         let path = entry.path();
-        let metadata = entry.metadata().await?;
+        if let Some(audit) = &context.audit {
+            audit.check(&path, &metadata);
+        }
 
         if metadata.is_dir() {
-            scan_recursive(&path, root_dir, tx).await?;
-        } else {
-            let size = metadata.len() as i64;
-            let file_path = path.to_string_lossy().to_string();
-            let _ = tx.send((file_path, size)).await;
+            if depth + 1 > MAX_WALK_DEPTH {
+                context.skip_dir(&path, "超过最大目录深度");
+                continue;
+            }
+            let visit = context.visited.lock().unwrap().enter(&path, &metadata);
+            if let Some(reason) = visit.skip_reason() {
+                context.skip_dir(&path, reason);
+                continue;
+            }
+            if let Some(sampling) = &context.sampling {
+                sampling.enter(&path, weight);
+            }
+            // 子目录不可读时记录错误并继续，只有根目录不可读才使扫描失败
+            if let Err(e) = Box::pin(scan_recursive(&path, depth + 1, context, tx)).await {
+                context.error(&path, &e);
+            }
+        } else if let Some(file) = context.walked_file(&path, &metadata, weight) {
+            let _ = tx.send(file).await;
         }
     }
 
     Ok(())
 }
+
+// 进程的峰值常驻内存（字节）
+#[cfg(target_os = "linux")]
+fn peak_memory() -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find(|l| l.starts_with("VmHWM:"))?;
+    let kb: u64 = line.split_whitespace().nth(1)?.parse().ok()?;
+    Some(kb * 1024)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn peak_memory() -> Option<u64> {
+    None
+}
@@ -0,0 +1,1059 @@
+use crate::paths::path_key;
+use crate::scan::{HistoryItem, ScanResult};
+use crate::store::{estimated_bytes, ResultStore};
+use async_trait::async_trait;
+use redis::aio::ConnectionManager;
+use redis::AsyncCommands;
+use rusqlite::OptionalExtension;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use tokio::sync::RwLock;
+use utoipa::ToSchema;
+
+pub type StoreResult<T> = Result<T, Box<dyn std::error::Error + Send + Sync>>;
+
+// 历史记录和结果存储的位置；修改后需要重启服务才会生效
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(tag = "backend", rename_all = "lowercase")]
+pub enum StorageSettings {
+    // 保存在进程内，重启后丢失
+    #[default]
+    Memory,
+    // 本地 SQLite 数据库文件
+    Sqlite { path: String },
+    // 多个服务实例共享同一 Redis，实例本身不保存状态，可部署在负载均衡之后
+    Redis {
+        url: String,
+        #[serde(default = "default_redis_prefix")]
+        prefix: String,
+    },
+}
+
+fn default_redis_prefix() -> String {
+    "search-tool:".to_string()
+}
+
+// 缓存中的一条扫描结果，bytes 为估算的占用字节数（数据库后端为序列化后的大小）；
+// 管理接口返回时 session 换成会话令牌摘要的前几位
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CacheEntry {
+    pub session: String,
+    pub id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    pub bytes: u64,
+}
+
+// 命中与未命中次数只统计当前进程的读取
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CacheStats {
+    pub backend: String,
+    pub entries: Vec<CacheEntry>,
+    pub total_bytes: u64,
+    pub hits: u64,
+    pub misses: u64,
+}
+
+#[derive(Default)]
+struct CacheCounters {
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl CacheCounters {
+    fn record<T>(&self, found: &Option<T>) {
+        let counter = if found.is_some() { &self.hits } else { &self.misses };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn stats(&self, backend: &str, entries: Vec<CacheEntry>) -> CacheStats {
+        CacheStats {
+            backend: backend.to_string(),
+            total_bytes: entries.iter().map(|entry| entry.bytes).sum(),
+            entries,
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+        }
+    }
+}
+
+// 扫描历史，按写入顺序保存（最旧的在前）
+#[async_trait]
+pub trait HistoryStore: Send + Sync {
+    // 追加一条记录，并只保留最新的 limit 条
+    async fn push(&self, item: HistoryItem, limit: usize) -> StoreResult<()>;
+
+    async fn list(&self) -> StoreResult<Vec<HistoryItem>>;
+
+    async fn truncate(&self, limit: usize) -> StoreResult<()>;
+
+    // 设置该路径在该时间（Unix 秒）的记录的备注，返回更新后的记录；找不到时为空
+    async fn annotate(
+        &self,
+        path: &str,
+        timestamp: i64,
+        note: &str,
+    ) -> StoreResult<Option<HistoryItem>>;
+
+    // 该路径的记录（最旧的在前），等价路径写法视为同一路径。
+    // 按写入时的规范路径键在存储中筛选，不读取其他路径的记录
+    async fn for_path(&self, path: &str) -> StoreResult<Vec<HistoryItem>>;
+
+    // 该路径最新的记录
+    async fn latest(&self, path: &str) -> StoreResult<Option<HistoryItem>> {
+        Ok(self.for_path(path).await?.pop())
+    }
+}
+
+// 按 ID 保存最近的扫描结果，超出容量时移除最旧的结果
+#[async_trait]
+pub trait CacheStore: Send + Sync {
+    async fn insert(&self, result: ScanResult) -> StoreResult<String>;
+
+    async fn get(&self, id: &str) -> StoreResult<Option<Arc<ScanResult>>>;
+
+    async fn set_max_entries(&self, max_entries: usize) -> StoreResult<()>;
+}
+
+// 按会话划分命名空间的存储，会话为空字符串时是所有客户端共享的空间
+#[async_trait]
+pub trait Storage: Send + Sync {
+    fn history(&self, session: &str) -> Arc<dyn HistoryStore>;
+
+    fn results(&self, session: &str) -> Arc<dyn CacheStore>;
+
+    // 把新的条数限制应用到所有会话
+    async fn apply_limits(
+        &self,
+        history_limit: usize,
+        cache_max_entries: usize,
+    ) -> StoreResult<()>;
+
+    // 所有会话缓存的结果及读取计数
+    async fn cache_stats(&self) -> StoreResult<CacheStats>;
+
+    // 清空所有会话缓存的结果，返回移除的条数；历史记录不受影响
+    async fn clear_cache(&self) -> StoreResult<usize>;
+
+    // 服务关闭前调用，确保已完成的写入落盘
+    async fn flush(&self) -> StoreResult<()> {
+        Ok(())
+    }
+}
+
+// 按设置打开存储
+pub async fn open(
+    settings: &StorageSettings,
+    cache_max_entries: usize,
+) -> StoreResult<Arc<dyn Storage>> {
+    Ok(match settings {
+        StorageSettings::Memory => Arc::new(MemoryStorage::new(cache_max_entries)),
+        StorageSettings::Sqlite { path } => Arc::new(SqliteStorage::open(path, cache_max_entries)?),
+        StorageSettings::Redis { url, prefix } => {
+            Arc::new(RedisStorage::open(url, prefix, cache_max_entries).await?)
+        }
+    })
+}
+
+// 内存中最多保留的会话数，超出时移除最久未使用的会话（共享空间除外）
+const MAX_MEMORY_SESSIONS: usize = 256;
+
+pub struct MemoryStorage {
+    // 会话 -> (存储, 最近使用时间)
+    sessions: Mutex<HashMap<String, (Arc<MemoryStore>, Instant)>>,
+    max_entries: AtomicUsize,
+    counters: Arc<CacheCounters>,
+}
+
+impl MemoryStorage {
+    pub fn new(cache_max_entries: usize) -> Self {
+        MemoryStorage {
+            sessions: Mutex::new(HashMap::new()),
+            max_entries: AtomicUsize::new(cache_max_entries),
+            counters: Arc::default(),
+        }
+    }
+
+    fn scope(&self, session: &str) -> Arc<MemoryStore> {
+        let mut sessions = self.sessions.lock().unwrap();
+        if let Some((store, used)) = sessions.get_mut(session) {
+            *used = Instant::now();
+            return store.clone();
+        }
+
+        if sessions.len() >= MAX_MEMORY_SESSIONS {
+            let oldest = sessions
+                .iter()
+                .filter(|(name, _)| !name.is_empty())
+                .min_by_key(|(_, (_, used))| *used)
+                .map(|(name, _)| name.clone());
+            if let Some(oldest) = oldest {
+                sessions.remove(&oldest);
+            }
+        }
+        let store = Arc::new(MemoryStore::new(
+            self.max_entries.load(Ordering::Relaxed),
+            Arc::clone(&self.counters),
+        ));
+        sessions.insert(session.to_string(), (store.clone(), Instant::now()));
+        store
+    }
+
+    // 复制出当前的会话列表，避免跨 await 持有锁
+    fn stores(&self) -> Vec<(String, Arc<MemoryStore>)> {
+        self.sessions
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(session, (store, _))| (session.clone(), store.clone()))
+            .collect()
+    }
+}
+
+#[async_trait]
+impl Storage for MemoryStorage {
+    fn history(&self, session: &str) -> Arc<dyn HistoryStore> {
+        self.scope(session)
+    }
+
+    fn results(&self, session: &str) -> Arc<dyn CacheStore> {
+        self.scope(session)
+    }
+
+    async fn apply_limits(
+        &self,
+        history_limit: usize,
+        cache_max_entries: usize,
+    ) -> StoreResult<()> {
+        self.max_entries.store(cache_max_entries, Ordering::Relaxed);
+        for (_, store) in self.stores() {
+            store.truncate(history_limit).await?;
+            store.set_max_entries(cache_max_entries).await?;
+        }
+        Ok(())
+    }
+
+    async fn cache_stats(&self) -> StoreResult<CacheStats> {
+        let mut entries = Vec::new();
+        for (session, store) in self.stores() {
+            let results = store.results.read().await;
+            entries.extend(results.entries().map(|(id, result)| CacheEntry {
+                session: session.clone(),
+                id: id.clone(),
+                path: Some(result.path.clone()),
+                bytes: estimated_bytes(result),
+            }));
+        }
+        Ok(self.counters.stats("memory", entries))
+    }
+
+    async fn clear_cache(&self) -> StoreResult<usize> {
+        let mut removed = 0;
+        for (_, store) in self.stores() {
+            removed += store.results.write().await.clear();
+        }
+        Ok(removed)
+    }
+}
+
+pub struct MemoryStore {
+    // (规范路径键, 记录)
+    history: RwLock<Vec<(String, HistoryItem)>>,
+    results: RwLock<ResultStore>,
+    counters: Arc<CacheCounters>,
+}
+
+impl MemoryStore {
+    fn new(cache_max_entries: usize, counters: Arc<CacheCounters>) -> Self {
+        MemoryStore {
+            history: RwLock::new(Vec::new()),
+            results: RwLock::new(ResultStore::new(cache_max_entries)),
+            counters,
+        }
+    }
+}
+
+fn keep_latest<T>(history: &mut Vec<T>, limit: usize) {
+    if history.len() > limit {
+        let excess = history.len() - limit;
+        history.drain(..excess);
+    }
+}
+
+#[async_trait]
+impl HistoryStore for MemoryStore {
+    async fn push(&self, item: HistoryItem, limit: usize) -> StoreResult<()> {
+        let key = path_key(&item.path);
+        let mut history = self.history.write().await;
+        history.push((key, item));
+        keep_latest(&mut history, limit);
+        Ok(())
+    }
+
+    async fn list(&self) -> StoreResult<Vec<HistoryItem>> {
+        Ok(self.history.read().await.iter().map(|(_, item)| item.clone()).collect())
+    }
+
+    async fn for_path(&self, path: &str) -> StoreResult<Vec<HistoryItem>> {
+        let key = path_key(path);
+        let history = self.history.read().await;
+        Ok(history
+            .iter()
+            .filter(|(item_key, _)| *item_key == key)
+            .map(|(_, item)| item.clone())
+            .collect())
+    }
+
+    async fn truncate(&self, limit: usize) -> StoreResult<()> {
+        keep_latest(&mut *self.history.write().await, limit);
+        Ok(())
+    }
+
+    async fn annotate(
+        &self,
+        path: &str,
+        timestamp: i64,
+        note: &str,
+    ) -> StoreResult<Option<HistoryItem>> {
+        let key = path_key(path);
+        let mut history = self.history.write().await;
+        let item = history
+            .iter_mut()
+            .rev()
+            .find(|(item_key, item)| *item_key == key && item.scan_time.timestamp() == timestamp);
+        Ok(item.map(|(_, item)| {
+            item.set_note(note);
+            item.clone()
+        }))
+    }
+}
+
+#[async_trait]
+impl CacheStore for MemoryStore {
+    async fn insert(&self, result: ScanResult) -> StoreResult<String> {
+        Ok(self.results.write().await.insert(result))
+    }
+
+    async fn get(&self, id: &str) -> StoreResult<Option<Arc<ScanResult>>> {
+        let result = self.results.read().await.get(id);
+        self.counters.record(&result);
+        Ok(result)
+    }
+
+    async fn set_max_entries(&self, max_entries: usize) -> StoreResult<()> {
+        self.results.write().await.set_max_entries(max_entries);
+        Ok(())
+    }
+}
+
+// 记录以 JSON 保存，按自增序号保持写入顺序，session 列区分会话
+pub struct SqliteStorage {
+    connection: Arc<Mutex<rusqlite::Connection>>,
+    max_entries: Arc<AtomicUsize>,
+    counters: Arc<CacheCounters>,
+}
+
+impl SqliteStorage {
+    pub fn open(path: &str, cache_max_entries: usize) -> StoreResult<Self> {
+        let connection = rusqlite::Connection::open(path)?;
+        connection.execute_batch(
+            "CREATE TABLE IF NOT EXISTS history (
+                seq INTEGER PRIMARY KEY AUTOINCREMENT,
+                session TEXT NOT NULL,
+                data TEXT NOT NULL,
+                path_key TEXT
+            );
+            CREATE TABLE IF NOT EXISTS results (
+                seq INTEGER PRIMARY KEY AUTOINCREMENT,
+                session TEXT NOT NULL,
+                id TEXT NOT NULL UNIQUE,
+                data TEXT NOT NULL
+            );",
+        )?;
+        index_history_paths(&connection)?;
+        Ok(SqliteStorage {
+            connection: Arc::new(Mutex::new(connection)),
+            max_entries: Arc::new(AtomicUsize::new(cache_max_entries.max(1))),
+            counters: Arc::default(),
+        })
+    }
+
+    fn scope(&self, session: &str) -> Arc<SqliteStore> {
+        Arc::new(SqliteStore {
+            connection: Arc::clone(&self.connection),
+            session: session.to_string(),
+            max_entries: Arc::clone(&self.max_entries),
+            counters: Arc::clone(&self.counters),
+        })
+    }
+}
+
+#[async_trait]
+impl Storage for SqliteStorage {
+    fn history(&self, session: &str) -> Arc<dyn HistoryStore> {
+        self.scope(session)
+    }
+
+    fn results(&self, session: &str) -> Arc<dyn CacheStore> {
+        self.scope(session)
+    }
+
+    async fn apply_limits(
+        &self,
+        history_limit: usize,
+        cache_max_entries: usize,
+    ) -> StoreResult<()> {
+        let cache_max_entries = cache_max_entries.max(1);
+        self.max_entries.store(cache_max_entries, Ordering::Relaxed);
+        let connection = Arc::clone(&self.connection);
+        tokio::task::spawn_blocking(move || -> StoreResult<()> {
+            let connection = connection.lock().unwrap();
+            for (table, limit) in [("history", history_limit), ("results", cache_max_entries)] {
+                let mut statement =
+                    connection.prepare(&format!("SELECT DISTINCT session FROM {table}"))?;
+                let sessions = statement
+                    .query_map([], |row| row.get::<_, String>(0))?
+                    .collect::<Result<Vec<_>, _>>()?;
+                for session in sessions {
+                    keep_latest_rows(&connection, table, &session, limit)?;
+                }
+            }
+            Ok(())
+        })
+        .await?
+    }
+
+    async fn cache_stats(&self) -> StoreResult<CacheStats> {
+        let connection = Arc::clone(&self.connection);
+        let entries = tokio::task::spawn_blocking(move || -> StoreResult<Vec<CacheEntry>> {
+            let connection = connection.lock().unwrap();
+            let mut statement = connection.prepare(
+                "SELECT session, id, json_extract(data, '$.path'), LENGTH(CAST(data AS BLOB)) \
+                 FROM results ORDER BY seq",
+            )?;
+            let entries = statement
+                .query_map([], |row| {
+                    Ok(CacheEntry {
+                        session: row.get(0)?,
+                        id: row.get(1)?,
+                        path: row.get(2)?,
+                        bytes: row.get::<_, i64>(3)? as u64,
+                    })
+                })?
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(entries)
+        })
+        .await??;
+        Ok(self.counters.stats("sqlite", entries))
+    }
+
+    async fn clear_cache(&self) -> StoreResult<usize> {
+        let connection = Arc::clone(&self.connection);
+        tokio::task::spawn_blocking(move || -> StoreResult<usize> {
+            Ok(connection.lock().unwrap().execute("DELETE FROM results", [])?)
+        })
+        .await?
+    }
+
+    // 获取连接锁即等待进行中的写入完成；WAL 模式下再把日志写回数据库文件
+    async fn flush(&self) -> StoreResult<()> {
+        let connection = Arc::clone(&self.connection);
+        tokio::task::spawn_blocking(move || -> StoreResult<()> {
+            connection
+                .lock()
+                .unwrap()
+                .query_row("PRAGMA wal_checkpoint(TRUNCATE)", [], |_| Ok(()))?;
+            Ok(())
+        })
+        .await?
+    }
+}
+
+pub struct SqliteStore {
+    connection: Arc<Mutex<rusqlite::Connection>>,
+    session: String,
+    max_entries: Arc<AtomicUsize>,
+    counters: Arc<CacheCounters>,
+}
+
+impl SqliteStore {
+    // rusqlite 是同步接口，放到阻塞线程池中执行
+    async fn with_connection<T, F>(&self, f: F) -> StoreResult<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&rusqlite::Connection, &str) -> StoreResult<T> + Send + 'static,
+    {
+        let connection = Arc::clone(&self.connection);
+        let session = self.session.clone();
+        tokio::task::spawn_blocking(move || f(&connection.lock().unwrap(), &session)).await?
+    }
+}
+
+// 按路径查询历史时使用 path_key 列；旧版本创建的表没有该列，补上并为已有记录填写
+fn index_history_paths(connection: &rusqlite::Connection) -> StoreResult<()> {
+    let has_column = connection
+        .prepare("SELECT 1 FROM pragma_table_info('history') WHERE name = 'path_key'")?
+        .exists([])?;
+    if !has_column {
+        connection.execute("ALTER TABLE history ADD COLUMN path_key TEXT", [])?;
+    }
+    let mut statement = connection.prepare("SELECT seq, data FROM history WHERE path_key IS NULL")?;
+    let rows = statement
+        .query_map([], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?)))?
+        .collect::<Result<Vec<_>, _>>()?;
+    for (seq, data) in rows {
+        let item: HistoryItem = serde_json::from_str(&data)?;
+        connection.execute(
+            "UPDATE history SET path_key = ?1 WHERE seq = ?2",
+            rusqlite::params![path_key(&item.path), seq],
+        )?;
+    }
+    connection.execute(
+        "CREATE INDEX IF NOT EXISTS history_path ON history (session, path_key, seq)",
+        [],
+    )?;
+    Ok(())
+}
+
+// 表中该会话只保留序号最大的 limit 行
+fn keep_latest_rows(
+    connection: &rusqlite::Connection,
+    table: &str,
+    session: &str,
+    limit: usize,
+) -> StoreResult<()> {
+    connection.execute(
+        &format!(
+            "DELETE FROM {table} WHERE session = ?1 AND seq NOT IN \
+             (SELECT seq FROM {table} WHERE session = ?1 ORDER BY seq DESC LIMIT ?2)"
+        ),
+        rusqlite::params![session, limit as i64],
+    )?;
+    Ok(())
+}
+
+#[async_trait]
+impl HistoryStore for SqliteStore {
+    async fn push(&self, item: HistoryItem, limit: usize) -> StoreResult<()> {
+        let data = serde_json::to_string(&item)?;
+        let key = path_key(&item.path);
+        self.with_connection(move |connection, session| {
+            connection.execute(
+                "INSERT INTO history (session, data, path_key) VALUES (?1, ?2, ?3)",
+                [session, &data, &key],
+            )?;
+            keep_latest_rows(connection, "history", session, limit)
+        })
+        .await
+    }
+
+    async fn list(&self) -> StoreResult<Vec<HistoryItem>> {
+        self.with_connection(|connection, session| {
+            let mut statement =
+                connection.prepare("SELECT data FROM history WHERE session = ?1 ORDER BY seq")?;
+            let rows = statement.query_map([session], |row| row.get::<_, String>(0))?;
+            let mut history = Vec::new();
+            for data in rows {
+                history.push(serde_json::from_str(&data?)?);
+            }
+            Ok(history)
+        })
+        .await
+    }
+
+    async fn truncate(&self, limit: usize) -> StoreResult<()> {
+        self.with_connection(move |connection, session| {
+            keep_latest_rows(connection, "history", session, limit)
+        })
+        .await
+    }
+
+    async fn for_path(&self, path: &str) -> StoreResult<Vec<HistoryItem>> {
+        let key = path_key(path);
+        self.with_connection(move |connection, session| {
+            let mut statement = connection.prepare(
+                "SELECT data FROM history WHERE session = ?1 AND path_key = ?2 ORDER BY seq",
+            )?;
+            let rows = statement.query_map([session, &key], |row| row.get::<_, String>(0))?;
+            let mut history = Vec::new();
+            for data in rows {
+                history.push(serde_json::from_str(&data?)?);
+            }
+            Ok(history)
+        })
+        .await
+    }
+
+    async fn latest(&self, path: &str) -> StoreResult<Option<HistoryItem>> {
+        let key = path_key(path);
+        self.with_connection(move |connection, session| {
+            let data = connection
+                .query_row(
+                    "SELECT data FROM history WHERE session = ?1 AND path_key = ?2 \
+                     ORDER BY seq DESC LIMIT 1",
+                    [session, &key],
+                    |row| row.get::<_, String>(0),
+                )
+                .optional()?;
+            Ok(data.map(|data| serde_json::from_str(&data)).transpose()?)
+        })
+        .await
+    }
+
+    async fn annotate(
+        &self,
+        path: &str,
+        timestamp: i64,
+        note: &str,
+    ) -> StoreResult<Option<HistoryItem>> {
+        // 规范化路径会访问磁盘，在持有连接锁之前进行，锁内按 path_key 列筛选
+        let key = path_key(path);
+        loop {
+            let key = key.clone();
+            let found = self
+                .with_connection(move |connection, session| {
+                    let mut statement = connection.prepare(
+                        "SELECT seq, data FROM history WHERE session = ?1 AND path_key = ?2 \
+                         ORDER BY seq DESC",
+                    )?;
+                    let rows = statement.query_map([session, &key], |row| {
+                        Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
+                    })?;
+                    for row in rows {
+                        let (seq, data) = row?;
+                        let item: HistoryItem = serde_json::from_str(&data)?;
+                        if item.scan_time.timestamp() == timestamp {
+                            return Ok(Some((seq, data, item)));
+                        }
+                    }
+                    Ok(None)
+                })
+                .await?;
+            let Some((seq, data, mut item)) = found else {
+                return Ok(None);
+            };
+            item.set_note(note);
+            let updated = serde_json::to_string(&item)?;
+            // 只在记录未被改动时写入，期间被并发修改或移除则重新查找
+            let changed = self
+                .with_connection(move |connection, _| {
+                    Ok(connection.execute(
+                        "UPDATE history SET data = ?1 WHERE seq = ?2 AND data = ?3",
+                        rusqlite::params![updated, seq, data],
+                    )?)
+                })
+                .await?;
+            if changed > 0 {
+                return Ok(Some(item));
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl CacheStore for SqliteStore {
+    async fn insert(&self, result: ScanResult) -> StoreResult<String> {
+        let id = uuid::Uuid::new_v4().to_string();
+        let data = serde_json::to_string(&result)?;
+        let max_entries = self.max_entries.load(Ordering::Relaxed);
+        let inserted = id.clone();
+        self.with_connection(move |connection, session| {
+            connection.execute(
+                "INSERT INTO results (session, id, data) VALUES (?1, ?2, ?3)",
+                [session, &inserted, &data],
+            )?;
+            keep_latest_rows(connection, "results", session, max_entries)
+        })
+        .await?;
+        Ok(id)
+    }
+
+    // 其他会话的结果视为不存在
+    async fn get(&self, id: &str) -> StoreResult<Option<Arc<ScanResult>>> {
+        let id = id.to_string();
+        let result = self.with_connection(move |connection, session| {
+            let data: Option<String> = connection
+                .query_row(
+                    "SELECT data FROM results WHERE id = ?1 AND session = ?2",
+                    [&id, session],
+                    |row| row.get(0),
+                )
+                .map(Some)
+                .or_else(|e| match e {
+                    rusqlite::Error::QueryReturnedNoRows => Ok(None),
+                    e => Err(e),
+                })?;
+            match data {
+                Some(data) => Ok(Some(Arc::new(serde_json::from_str(&data)?))),
+                None => Ok(None),
+            }
+        })
+        .await?;
+        self.counters.record(&result);
+        Ok(result)
+    }
+
+    async fn set_max_entries(&self, max_entries: usize) -> StoreResult<()> {
+        let max_entries = max_entries.max(1);
+        self.with_connection(move |connection, session| {
+            keep_latest_rows(connection, "results", session, max_entries)
+        })
+        .await
+    }
+}
+
+// 共享空间的键以 <prefix> 开头，会话的键以 <prefix>session:<会话>: 开头；
+// <prefix>sessions 集合记录出现过的会话，用于批量应用条数限制
+pub struct RedisStorage {
+    connection: ConnectionManager,
+    prefix: String,
+    max_entries: Arc<AtomicUsize>,
+    counters: Arc<CacheCounters>,
+}
+
+impl RedisStorage {
+    pub async fn open(url: &str, prefix: &str, cache_max_entries: usize) -> StoreResult<Self> {
+        let client = redis::Client::open(url)?;
+        let storage = RedisStorage {
+            connection: ConnectionManager::new(client).await?,
+            prefix: prefix.to_string(),
+            max_entries: Arc::new(AtomicUsize::new(cache_max_entries.max(1))),
+            counters: Arc::default(),
+        };
+        for session in storage.sessions().await? {
+            storage.scope(&session).index_history_paths().await?;
+        }
+        Ok(storage)
+    }
+
+    // 所有出现过的会话，包括共享空间
+    async fn sessions(&self) -> StoreResult<Vec<String>> {
+        let mut connection = self.connection.clone();
+        let mut sessions: Vec<String> =
+            connection.smembers(format!("{}sessions", self.prefix)).await?;
+        sessions.push(String::new());
+        Ok(sessions)
+    }
+
+    fn scope(&self, session: &str) -> Arc<RedisStore> {
+        let prefix = if session.is_empty() {
+            self.prefix.clone()
+        } else {
+            format!("{}session:{}:", self.prefix, session)
+        };
+        Arc::new(RedisStore {
+            connection: self.connection.clone(),
+            prefix,
+            sessions_key: format!("{}sessions", self.prefix),
+            session: session.to_string(),
+            max_entries: Arc::clone(&self.max_entries),
+            counters: Arc::clone(&self.counters),
+        })
+    }
+}
+
+#[async_trait]
+impl Storage for RedisStorage {
+    fn history(&self, session: &str) -> Arc<dyn HistoryStore> {
+        self.scope(session)
+    }
+
+    fn results(&self, session: &str) -> Arc<dyn CacheStore> {
+        self.scope(session)
+    }
+
+    async fn apply_limits(
+        &self,
+        history_limit: usize,
+        cache_max_entries: usize,
+    ) -> StoreResult<()> {
+        self.max_entries.store(cache_max_entries.max(1), Ordering::Relaxed);
+        for session in self.sessions().await? {
+            let store = self.scope(&session);
+            store.trim_history(history_limit).await?;
+            store.trim_results().await?;
+        }
+        Ok(())
+    }
+
+    // 结果只按键长度估算大小，不读取内容，因此不提供路径
+    async fn cache_stats(&self) -> StoreResult<CacheStats> {
+        let mut connection = self.connection.clone();
+        let mut entries = Vec::new();
+        for session in self.sessions().await? {
+            let store = self.scope(&session);
+            let ids: Vec<String> = connection.lrange(store.key("results"), 0, -1).await?;
+            for id in ids {
+                let bytes: u64 = connection.strlen(store.result_key(&id)).await?;
+                entries.push(CacheEntry {
+                    session: session.clone(),
+                    id,
+                    path: None,
+                    bytes,
+                });
+            }
+        }
+        Ok(self.counters.stats("redis", entries))
+    }
+
+    async fn clear_cache(&self) -> StoreResult<usize> {
+        let mut connection = self.connection.clone();
+        let mut removed = 0;
+        for session in self.sessions().await? {
+            let store = self.scope(&session);
+            let ids: Vec<String> = connection.lrange(store.key("results"), 0, -1).await?;
+            for id in &ids {
+                connection.del::<_, ()>(store.result_key(id)).await?;
+            }
+            connection.del::<_, ()>(store.key("results")).await?;
+            removed += ids.len();
+        }
+        Ok(removed)
+    }
+}
+
+// 历史记录保存在 <前缀>history 列表中，同一记录也写入按路径划分的
+// <前缀>history:path:<规范路径键> 列表，<前缀>history:keys 按相同顺序记录每条记录的路径键；
+// 结果保存在 <前缀>result:<id>，<前缀>results 列表记录结果 ID 的写入顺序
+pub struct RedisStore {
+    connection: ConnectionManager,
+    prefix: String,
+    sessions_key: String,
+    session: String,
+    max_entries: Arc<AtomicUsize>,
+    counters: Arc<CacheCounters>,
+}
+
+// 在每个列表中从末尾查找内容等于 ARGV[1] 的元素并替换为 ARGV[2]，返回替换的个数；
+// 在服务器上原子执行
+const REPLACE_ENTRY_SCRIPT: &str = r#"
+local replaced = 0
+for k = 1, #KEYS do
+    local entries = redis.call('LRANGE', KEYS[k], 0, -1)
+    for i = #entries, 1, -1 do
+        if entries[i] == ARGV[1] then
+            redis.call('LSET', KEYS[k], i - 1, ARGV[2])
+            replaced = replaced + 1
+            break
+        end
+    end
+end
+return replaced
+"#;
+
+// 只保留 KEYS[1]（history）最新的 ARGV[1] 条记录，KEYS[2]（history:keys）随之裁剪，
+// 移除的记录同时从 ARGV[2] .. 路径键 的按路径列表中删除
+const TRIM_HISTORY_SCRIPT: &str = r#"
+local excess = redis.call('LLEN', KEYS[1]) - tonumber(ARGV[1])
+if excess <= 0 then
+    return 0
+end
+local entries = redis.call('LRANGE', KEYS[1], 0, excess - 1)
+local keys = redis.call('LRANGE', KEYS[2], 0, excess - 1)
+for i = 1, #entries do
+    if keys[i] then
+        redis.call('LREM', ARGV[2] .. keys[i], 1, entries[i])
+    end
+end
+redis.call('LTRIM', KEYS[1], excess, -1)
+redis.call('LTRIM', KEYS[2], excess, -1)
+return excess
+"#;
+
+impl RedisStore {
+    fn key(&self, name: &str) -> String {
+        format!("{}{}", self.prefix, name)
+    }
+
+    fn result_key(&self, id: &str) -> String {
+        self.key(&format!("result:{}", id))
+    }
+
+    async fn register_session(&self) -> StoreResult<()> {
+        if !self.session.is_empty() {
+            let mut connection = self.connection.clone();
+            connection.sadd::<_, _, ()>(&self.sessions_key, &self.session).await?;
+        }
+        Ok(())
+    }
+
+    fn path_history_key(&self, key: &str) -> String {
+        self.key(&format!("history:path:{}", key))
+    }
+
+    async fn trim_history(&self, limit: usize) -> StoreResult<()> {
+        let mut connection = self.connection.clone();
+        redis::cmd("EVAL")
+            .arg(TRIM_HISTORY_SCRIPT)
+            .arg(2)
+            .arg(self.key("history"))
+            .arg(self.key("history:keys"))
+            .arg(limit)
+            .arg(self.path_history_key(""))
+            .query_async::<()>(&mut connection)
+            .await?;
+        Ok(())
+    }
+
+    // 旧版本只写入 history 列表，按其内容补建路径键和按路径的列表
+    async fn index_history_paths(&self) -> StoreResult<()> {
+        let mut connection = self.connection.clone();
+        let entries: Vec<String> = connection.lrange(self.key("history"), 0, -1).await?;
+        let indexed: usize = connection.llen(self.key("history:keys")).await?;
+        if indexed == entries.len() {
+            return Ok(());
+        }
+        let stale: Vec<String> = connection.lrange(self.key("history:keys"), 0, -1).await?;
+        let mut pipe = redis::pipe();
+        pipe.atomic().del(self.key("history:keys"));
+        for key in &stale {
+            pipe.del(self.path_history_key(key));
+        }
+        for data in &entries {
+            let item: HistoryItem = serde_json::from_str(data)?;
+            let key = path_key(&item.path);
+            pipe.rpush(self.key("history:keys"), &key)
+                .rpush(self.path_history_key(&key), data);
+        }
+        pipe.query_async::<()>(&mut connection).await?;
+        Ok(())
+    }
+
+    // 移除超出容量的最旧结果
+    async fn trim_results(&self) -> StoreResult<()> {
+        let mut connection = self.connection.clone();
+        let order = self.key("results");
+        let max_entries = self.max_entries.load(Ordering::Relaxed);
+        let len: usize = connection.llen(&order).await?;
+        for _ in max_entries..len {
+            let oldest: Option<String> = connection.lpop(&order, None).await?;
+            if let Some(oldest) = oldest {
+                connection.del::<_, ()>(self.result_key(&oldest)).await?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl HistoryStore for RedisStore {
+    async fn push(&self, item: HistoryItem, limit: usize) -> StoreResult<()> {
+        let data = serde_json::to_string(&item)?;
+        let key = path_key(&item.path);
+        let mut connection = self.connection.clone();
+        redis::pipe()
+            .atomic()
+            .rpush(self.key("history"), &data)
+            .rpush(self.key("history:keys"), &key)
+            .rpush(self.path_history_key(&key), &data)
+            .query_async::<()>(&mut connection)
+            .await?;
+        self.register_session().await?;
+        self.trim_history(limit).await
+    }
+
+    async fn list(&self) -> StoreResult<Vec<HistoryItem>> {
+        let mut connection = self.connection.clone();
+        let entries: Vec<String> = connection.lrange(self.key("history"), 0, -1).await?;
+        let mut history = Vec::with_capacity(entries.len());
+        for data in entries {
+            history.push(serde_json::from_str(&data)?);
+        }
+        Ok(history)
+    }
+
+    async fn truncate(&self, limit: usize) -> StoreResult<()> {
+        self.trim_history(limit).await
+    }
+
+    async fn for_path(&self, path: &str) -> StoreResult<Vec<HistoryItem>> {
+        let mut connection = self.connection.clone();
+        let entries: Vec<String> = connection
+            .lrange(self.path_history_key(&path_key(path)), 0, -1)
+            .await?;
+        let mut history = Vec::with_capacity(entries.len());
+        for data in entries {
+            history.push(serde_json::from_str(&data)?);
+        }
+        Ok(history)
+    }
+
+    async fn latest(&self, path: &str) -> StoreResult<Option<HistoryItem>> {
+        let mut connection = self.connection.clone();
+        let data: Option<String> = connection
+            .lindex(self.path_history_key(&path_key(path)), -1)
+            .await?;
+        Ok(data.map(|data| serde_json::from_str(&data)).transpose()?)
+    }
+
+    async fn annotate(
+        &self,
+        path: &str,
+        timestamp: i64,
+        note: &str,
+    ) -> StoreResult<Option<HistoryItem>> {
+        let path_history = self.path_history_key(&path_key(path));
+        let mut connection = self.connection.clone();
+        loop {
+            let entries: Vec<String> = connection.lrange(&path_history, 0, -1).await?;
+            let mut found = None;
+            for data in entries.into_iter().rev() {
+                let item: HistoryItem = serde_json::from_str(&data)?;
+                if item.scan_time.timestamp() == timestamp {
+                    found = Some((data, item));
+                    break;
+                }
+            }
+            let Some((data, mut item)) = found else {
+                return Ok(None);
+            };
+            item.set_note(note);
+            // 其他客户端可能同时写入或裁剪列表，按内容而非下标替换；未找到原记录时重新查找
+            let replaced: usize = redis::cmd("EVAL")
+                .arg(REPLACE_ENTRY_SCRIPT)
+                .arg(2)
+                .arg(&path_history)
+                .arg(self.key("history"))
+                .arg(data)
+                .arg(serde_json::to_string(&item)?)
+                .query_async(&mut connection)
+                .await?;
+            if replaced > 0 {
+                return Ok(Some(item));
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl CacheStore for RedisStore {
+    async fn insert(&self, result: ScanResult) -> StoreResult<String> {
+        let id = uuid::Uuid::new_v4().to_string();
+        let data = serde_json::to_string(&result)?;
+        let mut connection = self.connection.clone();
+        connection.set::<_, _, ()>(self.result_key(&id), data).await?;
+        connection.rpush::<_, _, ()>(self.key("results"), &id).await?;
+        self.register_session().await?;
+        self.trim_results().await?;
+        Ok(id)
+    }
+
+    async fn get(&self, id: &str) -> StoreResult<Option<Arc<ScanResult>>> {
+        let mut connection = self.connection.clone();
+        let data: Option<String> = connection.get(self.result_key(id)).await?;
+        let result = match data {
+            Some(data) => Some(Arc::new(serde_json::from_str(&data)?)),
+            None => None,
+        };
+        self.counters.record(&result);
+        Ok(result)
+    }
+
+    async fn set_max_entries(&self, max_entries: usize) -> StoreResult<()> {
+        self.max_entries.store(max_entries.max(1), Ordering::Relaxed);
+        self.trim_results().await
+    }
+}
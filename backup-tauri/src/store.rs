@@ -0,0 +1,109 @@
+use crate::scan::{Item, ScanResult};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+use utoipa::ToSchema;
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ResultPage {
+    pub result_id: String,
+    pub offset: usize,
+    pub total: usize,
+    pub items: Vec<Item>,
+}
+
+// 按 ID 保存最近的扫描结果，后续的分页、搜索、树图、比较等操作只需传递 ID
+pub struct ResultStore {
+    results: HashMap<String, Arc<ScanResult>>,
+    order: VecDeque<String>,
+    max_entries: usize,
+}
+
+impl ResultStore {
+    pub fn new(max_entries: usize) -> Self {
+        ResultStore {
+            results: HashMap::new(),
+            order: VecDeque::new(),
+            max_entries,
+        }
+    }
+
+    // 调整容量，超出的最旧结果立即移除
+    pub fn set_max_entries(&mut self, max_entries: usize) {
+        self.max_entries = max_entries.max(1);
+        while self.order.len() > self.max_entries {
+            if let Some(oldest) = self.order.pop_front() {
+                self.results.remove(&oldest);
+            }
+        }
+    }
+
+    pub fn insert(&mut self, result: ScanResult) -> String {
+        let id = uuid::Uuid::new_v4().to_string();
+
+        while self.order.len() >= self.max_entries {
+            match self.order.pop_front() {
+                Some(oldest) => {
+                    self.results.remove(&oldest);
+                }
+                None => break,
+            }
+        }
+
+        self.order.push_back(id.clone());
+        self.results.insert(id.clone(), Arc::new(result));
+        id
+    }
+
+    pub fn get(&self, id: &str) -> Option<Arc<ScanResult>> {
+        self.results.get(id).cloned()
+    }
+
+    // 按写入顺序列出保存的结果
+    pub fn entries(&self) -> impl Iterator<Item = (&String, &Arc<ScanResult>)> {
+        self.order
+            .iter()
+            .filter_map(|id| self.results.get_key_value(id))
+    }
+
+    // 移除全部结果，返回移除的条数
+    pub fn clear(&mut self) -> usize {
+        let count = self.order.len();
+        self.order.clear();
+        self.results.clear();
+        count
+    }
+}
+
+// 结果在内存中占用的估算字节数（结构体本身加各字符串的容量）
+pub fn estimated_bytes(result: &ScanResult) -> u64 {
+    let strings = |values: &[String]| {
+        values
+            .iter()
+            .map(|value| std::mem::size_of::<String>() + value.capacity())
+            .sum::<usize>()
+    };
+    let items: usize = result
+        .items
+        .iter()
+        .map(|item| {
+            std::mem::size_of::<Item>()
+                + item.path.capacity()
+                + item.size_formatted.capacity()
+                + strings(&item.flags)
+        })
+        .sum();
+    (std::mem::size_of::<ScanResult>()
+        + result.path.capacity()
+        + result.total_size_formatted.capacity()
+        + items) as u64
+}
+
+pub fn page(result_id: &str, result: &ScanResult, offset: usize, limit: usize) -> ResultPage {
+    ResultPage {
+        result_id: result_id.to_string(),
+        offset,
+        total: result.items.len(),
+        items: result.items.iter().skip(offset).take(limit).cloned().collect(),
+    }
+}
@@ -0,0 +1,152 @@
+use crate::scan::{scan_directory_events, ScanOptions, ScanResult};
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::sync::atomic::{AtomicI64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use tokio::sync::mpsc;
+use tokio_stream::Stream;
+
+// 每统计这么多个文件发送一次进度事件
+const PROGRESS_INTERVAL: usize = 1000;
+// 尚未被消费的事件上限
+const EVENT_BUFFER: usize = 256;
+
+#[derive(Debug, Clone)]
+pub enum ScanEvent {
+    // 扫描开始，root 为规范化后的根目录
+    Started { root: PathBuf },
+    // 统计到一个文件，path 为绝对路径；只在 ScanOptions::stream_items 为 true 时产生
+    Item { path: PathBuf, size: i64 },
+    // 目前已统计的文件数和大小
+    Progress { files: usize, size: i64 },
+    // 无法读取的目录或条目，扫描继续进行
+    Error { path: PathBuf, error: String },
+    // 最后一个事件：扫描完成或失败
+    Completed(Box<ScanResult>),
+    Failed(String),
+}
+
+type ScanOutcome = Result<ScanResult, Box<dyn std::error::Error + Send + Sync>>;
+type ScanFuture = Pin<Box<dyn Future<Output = ScanOutcome> + Send>>;
+
+// 扫描向事件流发送事件的一端。通道满时发送会等待，消费者跟不上时扫描随之放慢
+pub(crate) struct EventSink {
+    tx: mpsc::Sender<ScanEvent>,
+    // 为 false 时不发送 Item 事件，见 ScanOptions::stream_items
+    items: bool,
+    files: AtomicUsize,
+    size: AtomicI64,
+    // 遍历中同步产生的错误先暂存，随下一个文件或扫描结束时发送
+    errors: Mutex<Vec<ScanEvent>>,
+}
+
+impl EventSink {
+    pub(crate) async fn start(&self, root: &Path) {
+        let _ = self
+            .tx
+            .send(ScanEvent::Started {
+                root: root.to_path_buf(),
+            })
+            .await;
+    }
+
+    pub(crate) async fn item(&self, path: &Path, size: i64) {
+        self.flush().await;
+        if self.items {
+            let _ = self
+                .tx
+                .send(ScanEvent::Item {
+                    path: path.to_path_buf(),
+                    size,
+                })
+                .await;
+        }
+        let files = self.files.fetch_add(1, Ordering::Relaxed) + 1;
+        let total = self.size.fetch_add(size, Ordering::Relaxed) + size;
+        if files.is_multiple_of(PROGRESS_INTERVAL) {
+            let _ = self.tx.send(ScanEvent::Progress { files, size: total }).await;
+        }
+    }
+
+    pub(crate) fn error(&self, path: &Path, error: &str) {
+        self.errors.lock().unwrap().push(ScanEvent::Error {
+            path: path.to_path_buf(),
+            error: error.to_string(),
+        });
+    }
+
+    pub(crate) async fn flush(&self) {
+        let errors = std::mem::take(&mut *self.errors.lock().unwrap());
+        for error in errors {
+            let _ = self.tx.send(error).await;
+        }
+    }
+}
+
+// 扫描事件流。扫描在轮询流时推进，丢弃流即取消扫描
+pub struct ScanStream {
+    scan: Option<ScanFuture>,
+    events: mpsc::Receiver<ScanEvent>,
+    last: Option<ScanEvent>,
+}
+
+impl ScanStream {
+    // progress 为 false 时只产生最后的 Completed 或 Failed，扫描可与并发的相同请求共享
+    fn new(path: &str, options: &ScanOptions, progress: bool) -> Self {
+        let (tx, events) = mpsc::channel(EVENT_BUFFER);
+        let sink = progress.then(|| {
+            Arc::new(EventSink {
+                tx,
+                items: options.stream_items,
+                files: AtomicUsize::new(0),
+                size: AtomicI64::new(0),
+                errors: Mutex::new(Vec::new()),
+            })
+        });
+        let path = path.to_string();
+        let options = options.clone();
+        ScanStream {
+            scan: Some(Box::pin(async move {
+                scan_directory_events(&path, &options, &[], sink).await
+            })),
+            events,
+            last: None,
+        }
+    }
+}
+
+impl Stream for ScanStream {
+    type Item = ScanEvent;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<ScanEvent>> {
+        loop {
+            if let Poll::Ready(Some(event)) = self.events.poll_recv(cx) {
+                return Poll::Ready(Some(event));
+            }
+            let Some(scan) = self.scan.as_mut() else {
+                // 扫描结束后先发完已排队的事件，最后是结果
+                return Poll::Ready(self.last.take());
+            };
+            let outcome = std::task::ready!(scan.as_mut().poll(cx));
+            // 扫描结束时发送端随之释放，通道中只剩已发送的事件
+            self.scan = None;
+            self.last = Some(match outcome {
+                Ok(result) => ScanEvent::Completed(Box::new(result)),
+                Err(e) => ScanEvent::Failed(e.to_string()),
+            });
+        }
+    }
+}
+
+// 以事件流的形式扫描目录，便于其他程序嵌入扫描器并逐项处理进度、文件和错误。
+// 逐个文件的 Item 事件需设置 options.stream_items
+pub fn scan_stream(path: &str, options: &ScanOptions) -> impl Stream<Item = ScanEvent> {
+    ScanStream::new(path, options, true)
+}
+
+// 只关心最终结果的扫描，见 scan_directory
+pub(crate) fn scan_result_stream(path: &str, options: &ScanOptions) -> ScanStream {
+    ScanStream::new(path, options, false)
+}
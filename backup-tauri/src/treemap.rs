@@ -0,0 +1,125 @@
+use crate::scan::{Item, ScanResult};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+use utoipa::{IntoParams, ToSchema};
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, IntoParams)]
+#[serde(default)]
+#[into_params(parameter_in = Query)]
+pub struct TreemapOptions {
+    // 最大层级，超过该深度的节点不再展开
+    pub max_depth: usize,
+    // 小于父节点该比例的子项合并到"其他"桶
+    pub min_fraction: f64,
+    // 每个节点最多保留的子项数量
+    pub max_children: usize,
+}
+
+impl Default for TreemapOptions {
+    fn default() -> Self {
+        TreemapOptions {
+            max_depth: 3,
+            min_fraction: 0.01,
+            max_children: 50,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct TreemapNode {
+    pub name: String,
+    pub path: String,
+    pub value: i64,
+    pub is_dir: bool,
+    pub children: Vec<TreemapNode>,
+    // "其他"桶节点合并的子项数量，普通节点为 0
+    pub truncated_count: usize,
+}
+
+pub fn build_treemap(result: &ScanResult, options: &TreemapOptions) -> TreemapNode {
+    // 按父目录建立子项索引，顶层子项的父路径为空字符串
+    let mut children_of: HashMap<String, Vec<&Item>> = HashMap::new();
+    for item in &result.items {
+        let parent = Path::new(&item.path)
+            .parent()
+            .map(|p| p.to_string_lossy().to_string())
+            .unwrap_or_default();
+        children_of.entry(parent).or_default().push(item);
+    }
+
+    let name = Path::new(&result.path)
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| result.path.clone());
+
+    TreemapNode {
+        name,
+        path: String::new(),
+        value: result.total_size,
+        is_dir: true,
+        children: build_children("", result.total_size, 1, &children_of, options),
+        truncated_count: 0,
+    }
+}
+
+fn build_children(
+    parent: &str,
+    parent_value: i64,
+    depth: usize,
+    children_of: &HashMap<String, Vec<&Item>>,
+    options: &TreemapOptions,
+) -> Vec<TreemapNode> {
+    let Some(items) = children_of.get(parent) else {
+        return Vec::new();
+    };
+    if depth > options.max_depth {
+        return Vec::new();
+    }
+
+    let mut items = items.clone();
+    items.sort_by_key(|item| std::cmp::Reverse(item.size));
+
+    let threshold = (parent_value as f64 * options.min_fraction) as i64;
+    let mut nodes = Vec::new();
+    let mut other_value = 0i64;
+    let mut other_count = 0usize;
+
+    for item in items {
+        if nodes.len() >= options.max_children || item.size < threshold {
+            other_value += item.size;
+            other_count += 1;
+            continue;
+        }
+
+        let children = if item.is_dir {
+            build_children(&item.path, item.size, depth + 1, children_of, options)
+        } else {
+            Vec::new()
+        };
+        nodes.push(TreemapNode {
+            name: Path::new(&item.path)
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_else(|| item.path.clone()),
+            path: item.path.clone(),
+            value: item.size,
+            is_dir: item.is_dir,
+            children,
+            truncated_count: 0,
+        });
+    }
+
+    if other_count > 0 {
+        nodes.push(TreemapNode {
+            name: format!("其他 {} 项", other_count),
+            path: String::new(),
+            value: other_value,
+            is_dir: false,
+            children: Vec::new(),
+            truncated_count: other_count,
+        });
+    }
+
+    nodes
+}
@@ -0,0 +1,109 @@
+use crate::paths::path_key;
+use crate::scan::HistoryItem;
+use crate::settings::Settings;
+use chrono::{DateTime, Utc};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::io::{Read, Write};
+use std::path::Path;
+
+// 归档格式版本，结构不兼容时递增
+const ARCHIVE_VERSION: u32 = 1;
+
+// 导出的应用状态：设置（含预设）、扫描历史和基线，gzip 压缩的 JSON
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StateArchive {
+    pub version: u32,
+    #[serde(with = "chrono::serde::ts_seconds")]
+    pub exported_at: DateTime<Utc>,
+    pub settings: Settings,
+    pub history: Vec<HistoryItem>,
+    // 规范路径键 -> 基线快照
+    pub baselines: HashMap<String, HistoryItem>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportSummary {
+    #[serde(with = "chrono::serde::ts_seconds")]
+    pub exported_at: DateTime<Utc>,
+    pub presets: usize,
+    // 新加入的历史记录数，已存在的记录不重复导入
+    pub history: usize,
+    pub baselines: usize,
+}
+
+impl StateArchive {
+    pub fn new(
+        settings: Settings,
+        history: Vec<HistoryItem>,
+        baselines: HashMap<String, HistoryItem>,
+    ) -> Self {
+        StateArchive {
+            version: ARCHIVE_VERSION,
+            exported_at: Utc::now(),
+            settings,
+            history,
+            baselines,
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+        }
+        let json = serde_json::to_vec(self).map_err(|e| e.to_string())?;
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&json).map_err(|e| e.to_string())?;
+        let data = encoder.finish().map_err(|e| e.to_string())?;
+        std::fs::write(path, data).map_err(|e| e.to_string())
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let data = std::fs::read(path).map_err(|e| e.to_string())?;
+        let mut json = Vec::new();
+        GzDecoder::new(data.as_slice())
+            .read_to_end(&mut json)
+            .map_err(|_| "不是有效的状态归档文件".to_string())?;
+
+        let value: serde_json::Value =
+            serde_json::from_slice(&json).map_err(|e| format!("无法解析状态归档: {}", e))?;
+        let version = value.get("version").and_then(|v| v.as_u64()).unwrap_or(0);
+        if version != ARCHIVE_VERSION as u64 {
+            return Err(format!("不支持的归档版本: {}", version));
+        }
+        serde_json::from_value(value).map_err(|e| format!("无法解析状态归档: {}", e))
+    }
+}
+
+// 把归档中的历史合并进现有历史：按扫描时间排序，同一路径同一时间的记录只保留一份，
+// 超出 limit 时丢弃最旧的。返回新加入的条数
+pub fn merge_history(
+    history: &mut Vec<HistoryItem>,
+    imported: Vec<HistoryItem>,
+    limit: usize,
+) -> usize {
+    let before = history.len();
+    // 按规范路径键比较，大小写或分隔符写法不同的同一目录视为同一路径
+    let mut seen: HashSet<_> = history
+        .iter()
+        .map(|existing| (path_key(&existing.path), existing.scan_time))
+        .collect();
+    for item in imported {
+        if seen.insert((path_key(&item.path), item.scan_time)) {
+            history.push(item);
+        }
+    }
+    let added = history.len() - before;
+
+    history.sort_by_key(|item| item.scan_time);
+    if history.len() > limit {
+        let excess = history.len() - limit;
+        history.drain(..excess);
+    }
+    added
+}
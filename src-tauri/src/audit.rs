@@ -0,0 +1,204 @@
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+// 最多返回的审计发现，超出的部分只计数
+const MAX_FINDINGS: usize = 10_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum AuditIssue {
+    // 所有用户可写；设置了粘滞位的目录（如 /tmp）除外
+    WorldWritable,
+    // 设置了 setuid 的文件
+    Setuid,
+    // 设置了 setgid 的文件
+    Setgid,
+    // Windows 上 Everyone 拥有完全控制权限，或没有 DACL
+    EveryoneFullControl,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditFinding {
+    pub path: String,
+    pub is_dir: bool,
+    pub issues: Vec<AuditIssue>,
+    // Unix 权限位（八进制）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mode: Option<String>,
+}
+
+// 扫描范围内权限过宽的文件和目录
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditReport {
+    // 检查过的文件和目录数
+    pub checked: usize,
+    // 有问题的条目总数，超过上限时多于 findings 的长度
+    pub flagged: usize,
+    pub findings: Vec<AuditFinding>,
+}
+
+// 遍历时逐个检查条目，可在多个任务间共享
+#[derive(Default)]
+pub struct AuditCollector {
+    checked: AtomicUsize,
+    flagged: AtomicUsize,
+    findings: Mutex<Vec<AuditFinding>>,
+}
+
+impl AuditCollector {
+    // metadata 为跟随符号链接后的元数据
+    pub fn check(&self, path: &Path, metadata: &std::fs::Metadata) {
+        self.checked.fetch_add(1, Ordering::Relaxed);
+        let (issues, mode) = inspect(path, metadata);
+        if issues.is_empty() {
+            return;
+        }
+        self.flagged.fetch_add(1, Ordering::Relaxed);
+        let mut findings = self.findings.lock().unwrap();
+        if findings.len() < MAX_FINDINGS {
+            findings.push(AuditFinding {
+                path: path.to_string_lossy().to_string(),
+                is_dir: metadata.is_dir(),
+                issues,
+                mode,
+            });
+        }
+    }
+
+    // 路径改为相对于根目录，并按路径排序
+    pub fn report(&self, root: &Path) -> AuditReport {
+        let mut findings = std::mem::take(&mut *self.findings.lock().unwrap());
+        for finding in &mut findings {
+            if let Ok(rel_path) = Path::new(&finding.path).strip_prefix(root) {
+                finding.path = rel_path.to_string_lossy().to_string();
+            }
+        }
+        findings.sort_by(|a, b| a.path.cmp(&b.path));
+        AuditReport {
+            checked: self.checked.load(Ordering::Relaxed),
+            flagged: self.flagged.load(Ordering::Relaxed),
+            findings,
+        }
+    }
+}
+
+#[cfg(unix)]
+fn inspect(_path: &Path, metadata: &std::fs::Metadata) -> (Vec<AuditIssue>, Option<String>) {
+    use std::os::unix::fs::PermissionsExt;
+
+    let mode = metadata.permissions().mode();
+    let mut issues = Vec::new();
+    let sticky_dir = metadata.is_dir() && mode & 0o1000 != 0;
+    if mode & 0o002 != 0 && !sticky_dir {
+        issues.push(AuditIssue::WorldWritable);
+    }
+    // 目录的 setgid 只影响新建文件的属组，不作为问题
+    if metadata.is_file() {
+        if mode & 0o4000 != 0 {
+            issues.push(AuditIssue::Setuid);
+        }
+        if mode & 0o2000 != 0 {
+            issues.push(AuditIssue::Setgid);
+        }
+    }
+    (issues, Some(format!("{:04o}", mode & 0o7777)))
+}
+
+#[cfg(windows)]
+fn inspect(path: &Path, _metadata: &std::fs::Metadata) -> (Vec<AuditIssue>, Option<String>) {
+    let issues = if everyone_full_control(path) {
+        vec![AuditIssue::EveryoneFullControl]
+    } else {
+        Vec::new()
+    };
+    (issues, None)
+}
+
+#[cfg(not(any(unix, windows)))]
+fn inspect(_path: &Path, _metadata: &std::fs::Metadata) -> (Vec<AuditIssue>, Option<String>) {
+    (Vec::new(), None)
+}
+
+// DACL 中有授予 Everyone 完全控制的允许项，或者根本没有 DACL
+#[cfg(windows)]
+fn everyone_full_control(path: &Path) -> bool {
+    use std::os::windows::ffi::OsStrExt;
+    use windows_sys::Win32::Foundation::{LocalFree, ERROR_SUCCESS, GENERIC_ALL};
+    use windows_sys::Win32::Security::Authorization::{GetNamedSecurityInfoW, SE_FILE_OBJECT};
+    use windows_sys::Win32::Security::{
+        AclSizeInformation, CreateWellKnownSid, EqualSid, GetAce, GetAclInformation, WinWorldSid,
+        ACCESS_ALLOWED_ACE, ACL, ACL_SIZE_INFORMATION, DACL_SECURITY_INFORMATION,
+        PSECURITY_DESCRIPTOR, SECURITY_MAX_SID_SIZE,
+    };
+    use windows_sys::Win32::Storage::FileSystem::FILE_ALL_ACCESS;
+
+    const ACCESS_ALLOWED_ACE_TYPE: u8 = 0;
+
+    let wide: Vec<u16> = path.as_os_str().encode_wide().chain(Some(0)).collect();
+    let mut everyone = [0u8; SECURITY_MAX_SID_SIZE as usize];
+    let mut everyone_len = everyone.len() as u32;
+    let mut dacl: *mut ACL = std::ptr::null_mut();
+    let mut descriptor: PSECURITY_DESCRIPTOR = std::ptr::null_mut();
+
+    // SAFETY: 路径以 0 结尾；安全描述符由系统分配，用完后通过 LocalFree 释放，
+    // dacl 指向其内部，只在释放前访问
+    unsafe {
+        if CreateWellKnownSid(
+            WinWorldSid,
+            std::ptr::null_mut(),
+            everyone.as_mut_ptr() as *mut _,
+            &mut everyone_len,
+        ) == 0
+        {
+            return false;
+        }
+        if GetNamedSecurityInfoW(
+            wide.as_ptr(),
+            SE_FILE_OBJECT,
+            DACL_SECURITY_INFORMATION,
+            std::ptr::null_mut(),
+            std::ptr::null_mut(),
+            &mut dacl,
+            std::ptr::null_mut(),
+            &mut descriptor,
+        ) != ERROR_SUCCESS
+        {
+            return false;
+        }
+
+        let mut found = dacl.is_null();
+        let mut info: ACL_SIZE_INFORMATION = std::mem::zeroed();
+        if !found
+            && GetAclInformation(
+                dacl,
+                &mut info as *mut ACL_SIZE_INFORMATION as *mut _,
+                std::mem::size_of::<ACL_SIZE_INFORMATION>() as u32,
+                AclSizeInformation,
+            ) != 0
+        {
+            for index in 0..info.AceCount {
+                let mut ace = std::ptr::null_mut();
+                if GetAce(dacl, index, &mut ace) == 0 {
+                    continue;
+                }
+                let ace = &*(ace as *const ACCESS_ALLOWED_ACE);
+                if ace.Header.AceType != ACCESS_ALLOWED_ACE_TYPE {
+                    continue;
+                }
+                let full = ace.Mask & FILE_ALL_ACCESS == FILE_ALL_ACCESS
+                    || ace.Mask & GENERIC_ALL != 0;
+                let sid = &ace.SidStart as *const u32 as *mut _;
+                if full && EqualSid(sid, everyone.as_mut_ptr() as *mut _) != 0 {
+                    found = true;
+                    break;
+                }
+            }
+        }
+        LocalFree(descriptor as _);
+        found
+    }
+}
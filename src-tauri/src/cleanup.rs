@@ -0,0 +1,105 @@
+use crate::deletions::{self, TrashReport};
+use crate::fileops::MoveFailure;
+use crate::paths;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EmptyReport {
+    pub root: String,
+    // 零字节文件的绝对路径
+    pub files: Vec<String>,
+    // 不包含任何文件的目录（只列出最外层，其下的空目录随之删除）
+    pub dirs: Vec<String>,
+}
+
+pub fn find_empty(root: &Path) -> EmptyReport {
+    let mut files = Vec::new();
+    let mut dirs = Vec::new();
+    walk_empty(root, true, &mut files, &mut dirs);
+
+    files.sort();
+    dirs.sort();
+
+    EmptyReport {
+        root: paths::slash(root),
+        files,
+        dirs,
+    }
+}
+
+// 返回目录是否不包含任何文件；非空目录（以及根目录）负责上报其下的空子目录
+fn walk_empty(dir: &Path, is_root: bool, files: &mut Vec<String>, dirs: &mut Vec<String>) -> bool {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        // 无法读取的目录按非空处理，避免误删
+        Err(_) => return false,
+    };
+
+    let mut is_empty = true;
+    let mut empty_children: Vec<PathBuf> = Vec::new();
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let Ok(metadata) = std::fs::symlink_metadata(&path) else {
+            is_empty = false;
+            continue;
+        };
+
+        if metadata.is_dir() {
+            if walk_empty(&path, false, files, dirs) {
+                empty_children.push(path);
+            } else {
+                is_empty = false;
+            }
+        } else {
+            // 符号链接等特殊条目也视为目录中的内容
+            is_empty = false;
+            if metadata.is_file() && metadata.len() == 0 {
+                files.push(paths::slash(&path));
+            }
+        }
+    }
+
+    if !is_empty || is_root {
+        dirs.extend(empty_children.iter().map(|p| paths::slash(p)));
+    }
+
+    is_empty
+}
+
+fn contains_files(dir: &Path) -> bool {
+    !walk_empty(dir, false, &mut Vec::new(), &mut Vec::new())
+}
+
+// 删除前重新确认条目仍为空，防止扫描后新增内容被一并删除；
+// 与 delete_paths 一样移到回收站或暂存目录，记入删除日志后可以撤销
+pub fn delete_empty(paths: &[String], staging_dir: &Path) -> TrashReport {
+    let mut deleted = Vec::new();
+    let mut failed = Vec::new();
+
+    for path_str in paths {
+        let path = Path::new(path_str);
+        let result = match std::fs::symlink_metadata(path) {
+            Ok(metadata) if metadata.is_file() && metadata.len() == 0 => {
+                deletions::delete_path(path_str, staging_dir)
+            }
+            Ok(metadata) if metadata.is_dir() && !contains_files(path) => {
+                deletions::delete_path(path_str, staging_dir)
+            }
+            Ok(_) => Err("条目已不为空，跳过删除".to_string()),
+            Err(e) => Err(e.to_string()),
+        };
+
+        match result {
+            Ok(entry) => deleted.push(entry),
+            Err(error) => failed.push(MoveFailure {
+                path: path_str.clone(),
+                error,
+            }),
+        }
+    }
+
+    TrashReport { deleted, failed }
+}
@@ -1,12 +1,56 @@
-use crate::scan::{self, HistoryItem, ScanResult};
+use crate::archive::{self, ImportSummary, StateArchive};
+use crate::cleanup::{self, EmptyReport};
+use crate::diff::{self, BaselineComparison, SnapshotDiff};
+use crate::deletions::{self, DeletionEntry, DeletionJournal, RestoreReport, TrashReport};
+use crate::filter::{self, Filter};
+use crate::fileops::{self, ConflictPolicy, MoveFailure, MoveReport, MovedPath};
+use crate::history::SavedHistory;
+use crate::jobs::{Job, JobPriority, JobSnapshot, StartedJob};
+use crate::locations::{self, LocationUsage, LocationsOverview};
+use crate::locks::{self, LockingProcess};
+use crate::paths;
+use crate::scan::{
+    self, ExtensionReport, HistoryItem, Item, RootSummary, ScanControl, ScanOptions, ScanResult,
+    ScanSnapshot, SizeFormat, SizeUnit, Trend, UnusedReport,
+};
+use crate::session::{self, RestoredSession, Session};
+use crate::settings::{ScanPreset, Settings};
+use crate::recycle::{self, TrashUsage};
+use crate::report;
+use crate::shell;
+use crate::system::{self, SystemOverview, VolumeOverview};
+use crate::store::{self, ResultPage};
+use crate::tray;
+use crate::treemap::{self, TreemapNode, TreemapOptions};
+use crate::volumes::{self, Volume};
 use crate::AppState;
 use chrono::Utc;
-use tauri::{command, State};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::{command, AppHandle, Manager, State};
 
+// 任务的排队、开始、进度和结束都通过 job-updated 事件推送，前端按 status 字段区分；
+// 拖放、托盘和 queue_scan 加入的任务相同。job-snapshot 另外携带运行中的阶段性结果
+const JOB_UPDATED: &str = "job-updated";
+// 运行中任务的进度推送间隔
+const JOB_PROGRESS_INTERVAL: Duration = Duration::from_millis(500);
+// 每推送这么多次进度附带一次阶段性结果
+const JOB_SNAPSHOT_TICKS: u32 = 4;
+
+// 前端以具名参数调用，参数较多时不便合并为结构体
+#[allow(clippy::too_many_arguments)]
 #[command]
 pub async fn scan_directory(
     path: String,
     force_refresh: bool,
+    options: Option<ScanOptions>,
+    treemap: Option<TreemapOptions>,
+    unit: Option<SizeUnit>,
+    format: Option<SizeFormat>,
+    snapshot_interval: Option<u64>,
+    app: AppHandle,
     state: State<'_, AppState>,
 ) -> Result<ScanResult, String> {
     let path = path.trim();
@@ -15,38 +59,720 @@ pub async fn scan_directory(
         return Err("请提供有效的目录路径".to_string());
     }
 
-    match scan::scan_directory(path, force_refresh).await {
-        Ok(mut result) => {
-            // 只在非缓存命中时添加到历史记录
-            if result.scan_time > 0.0 {
-                // 添加到历史记录
-                let history_item = HistoryItem {
+    let options = scan_options(options, &state);
+
+    // 指定间隔（秒）时扫描期间通过 scan-snapshot 事件推送阶段性结果
+    let scanned = match snapshot_interval {
+        Some(secs) => {
+            let control = Arc::new(ScanControl::default());
+            let unit = size_unit(unit, &state);
+            let scan = scan::scan_directory_controlled(
+                path,
+                force_refresh,
+                &options,
+                Some(control.clone()),
+            );
+            drive_scan(scan, Duration::from_secs(secs.max(1)), || {
+                let snapshot = PathSnapshot {
                     path: path.to_string(),
-                    scan_time: Utc::now(),
-                    total_size: result.total_size,
-                    size_format: result.total_size_formatted.clone(),
-                    items: result.items.clone(),
+                    snapshot: control.snapshot(unit, format.unwrap_or_default()),
                 };
+                let _ = app.emit_all("scan-snapshot", &snapshot);
+            })
+            .await
+        }
+        None => scan::scan_directory(path, force_refresh, &options).await,
+    };
 
-                // 保存到历史记录
-                let mut history = state.history.lock().unwrap();
-                history.push(history_item);
+    match scanned {
+        Ok(mut result) => {
+            record_scan(path, &options, &mut result, &state);
+            scan::format_sizes(&mut result, size_unit(unit, &state), format.unwrap_or_default());
 
-                // 保持历史记录在合理范围内（最多保存20条，减少内存占用）
-                if history.len() > 20 {
-                    history.remove(0);
-                }
+            // 按需生成树图结构，避免前端从扁平路径重建层级
+            if let Some(options) = treemap {
+                result.treemap = Some(treemap::build_treemap(&result, &options));
             }
 
-            // 更新结果中的路径为规范路径
-            result.path = path.to_string();
-
             Ok(result)
         }
         Err(e) => Err(e.to_string()),
     }
 }
 
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PathSnapshot {
+    path: String,
+    #[serde(flatten)]
+    snapshot: ScanSnapshot,
+}
+
+// 未指定单位时使用设置中的单位
+fn size_unit(unit: Option<SizeUnit>, state: &AppState) -> SizeUnit {
+    unit.unwrap_or_else(|| state.settings.lock().unwrap().size_unit)
+}
+
+// 未指定的扫描选项使用用户设置中的默认值
+fn scan_options(options: Option<ScanOptions>, state: &AppState) -> ScanOptions {
+    let mut options = options.unwrap_or_default();
+    state.settings.lock().unwrap().apply_scan_defaults(&mut options);
+    options
+}
+
+// 记录历史并保存到结果存储
+fn record_scan(path: &str, options: &ScanOptions, result: &mut ScanResult, state: &AppState) {
+    // 只在非缓存命中且未过滤时添加到历史记录
+    if result.scan_time > 0.0 && options.is_unfiltered() {
+        // 添加到历史记录
+        let history_item = HistoryItem {
+            path: path.to_string(),
+            scan_time: Utc::now(),
+            total_size: result.total_size,
+            size_format: result.total_size_formatted.clone(),
+            items: result.items.clone(),
+            note: None,
+        };
+
+        // 保持历史记录在设置的条数以内，减少内存占用
+        let limit = state.settings.lock().unwrap().history_limit;
+
+        // 保存到历史记录
+        let mut history = state.history.lock().unwrap();
+        history.push(history_item);
+        if history.len() > limit {
+            let excess = history.len() - limit;
+            history.drain(..excess);
+        }
+        drop(history);
+        save_history(state);
+    }
+
+    // 更新结果中的路径为规范路径
+    result.path = path.to_string();
+
+    // 保存到结果存储，后续操作通过 ID 引用
+    result.result_id = Some(state.results.lock().unwrap().insert(result.clone()));
+
+    remember_last_scan(result, state);
+}
+
+// 历史记录或基线变化后写入 history.json
+fn save_history(state: &AppState) {
+    if let Some(file) = &state.history_file {
+        file.save(SavedHistory {
+            history: state.history.lock().unwrap().clone(),
+            baselines: state.baselines.lock().unwrap().clone(),
+        });
+    }
+}
+
+// 记住最后扫描的路径和结果，下次启动时恢复
+fn remember_last_scan(result: &ScanResult, state: &AppState) {
+    let mut session = state.session.lock().unwrap();
+    session.last_path = Some(result.path.clone());
+    let Some(settings_path) = state.settings_path.clone() else {
+        return;
+    };
+
+    // 持有界面状态的锁时提交，并行的扫描按记录路径的先后写入，
+    // 上次路径和上次结果总是来自同一次扫描
+    let saved = session.clone();
+    let result = result.clone();
+    state.session_writer.spawn(move || {
+        let _ = saved.save(&settings_path);
+        let _ = session::save_last_result(&session::last_result_path(&settings_path), &result);
+    });
+}
+
+// 拖放到窗口上的文件夹加入扫描任务队列，进度和结果通过任务事件通知前端
+#[command]
+pub fn scan_dropped_paths(paths: Vec<String>, app: AppHandle) -> Vec<String> {
+    queue_scan_paths(&app, paths.into_iter().map(PathBuf::from).collect())
+}
+
+// 返回实际加入队列的目录，非目录项被忽略
+pub fn queue_scan_paths(app: &AppHandle, paths: Vec<PathBuf>) -> Vec<String> {
+    let dirs: Vec<String> = paths
+        .into_iter()
+        .filter(|p| p.is_dir())
+        .map(|p| p.to_string_lossy().to_string())
+        .collect();
+    if dirs.is_empty() {
+        return dirs;
+    }
+
+    let state = app.state::<AppState>();
+    let options = scan_options(None, &state);
+    {
+        let mut jobs = state.jobs.lock().unwrap();
+        for dir in &dirs {
+            let job = jobs.enqueue(dir.clone(), JobPriority::Normal, options.clone());
+            let _ = app.emit_all(JOB_UPDATED, &job);
+        }
+    }
+    schedule_jobs(app);
+
+    dirs
+}
+
+#[command]
+pub fn queue_scan(
+    path: String,
+    priority: Option<JobPriority>,
+    options: Option<ScanOptions>,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<Job, String> {
+    let path = path.trim();
+
+    if path.is_empty() {
+        return Err("请提供有效的目录路径".to_string());
+    }
+
+    let options = scan_options(options, &state);
+    let job = state
+        .jobs
+        .lock()
+        .unwrap()
+        .enqueue(path.to_string(), priority.unwrap_or_default(), options);
+    let _ = app.emit_all(JOB_UPDATED, &job);
+    schedule_jobs(&app);
+
+    Ok(job)
+}
+
+#[command]
+pub fn list_jobs(state: State<'_, AppState>) -> Vec<Job> {
+    state.jobs.lock().unwrap().list()
+}
+
+#[command]
+pub fn cancel_job(id: String, app: AppHandle, state: State<'_, AppState>) -> Result<Job, String> {
+    let job = state.jobs.lock().unwrap().cancel(&id)?;
+    let _ = app.emit_all(JOB_UPDATED, &job);
+    Ok(job)
+}
+
+// 在并行上限内启动排队的任务
+fn schedule_jobs(app: &AppHandle) {
+    let started = app.state::<AppState>().jobs.lock().unwrap().start_next();
+    for started in started {
+        let _ = app.emit_all(JOB_UPDATED, &started.job);
+        tauri::async_runtime::spawn(run_job(app.clone(), started));
+    }
+}
+
+async fn run_job(app: AppHandle, started: StartedJob) {
+    let StartedJob {
+        job,
+        options,
+        control,
+    } = started;
+    let state = app.state::<AppState>();
+    let unit = size_unit(None, &state);
+    let mut ticks = 0u32;
+
+    // 扫描期间定时推送进度，每隔若干次附带一份阶段性结果
+    let scan = scan::scan_directory_controlled(&job.path, false, &options, Some(control.clone()));
+    let outcome = drive_scan(scan, JOB_PROGRESS_INTERVAL, || {
+        let progress = state.jobs.lock().unwrap().update_progress(&job.id);
+        if let Some(progress) = progress {
+            let _ = app.emit_all(JOB_UPDATED, &progress);
+        }
+
+        ticks += 1;
+        if ticks.is_multiple_of(JOB_SNAPSHOT_TICKS) {
+            let snapshot = JobSnapshot {
+                job_id: job.id.clone(),
+                snapshot: control.snapshot(unit, SizeFormat::Human),
+            };
+            let _ = app.emit_all("job-snapshot", &snapshot);
+        }
+    })
+    .await;
+
+    let outcome = outcome
+        .map(|mut result| {
+            record_scan(&job.path, &options, &mut result, &state);
+            result.result_id.unwrap_or_default()
+        })
+        .map_err(|e| e.to_string());
+
+    let finished = state.jobs.lock().unwrap().finish(&job.id, outcome);
+    if let Some(finished) = finished {
+        let _ = app.emit_all(JOB_UPDATED, &finished);
+    }
+    schedule_jobs(&app);
+}
+
+#[command]
+pub fn scan_last_path(app: AppHandle) -> Result<String, String> {
+    tray::scan_last_path(&app).ok_or_else(|| "没有可重新扫描的历史记录".to_string())
+}
+
+#[command]
+pub fn get_volumes(state: State<'_, AppState>) -> Vec<Volume> {
+    state.volumes.lock().unwrap().clone()
+}
+
+// 并发扫描所有本机卷，返回每个卷前两层的占用概览
+#[command]
+pub async fn scan_system(state: State<'_, AppState>) -> Result<SystemOverview, String> {
+    let start_time = std::time::Instant::now();
+    let unit = size_unit(None, &state);
+    let base = scan_options(None, &state);
+    let volumes = tokio::task::spawn_blocking(move || volumes::list_volumes(unit))
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let handles: Vec<_> = volumes
+        .iter()
+        .map(|volume| {
+            let path = volume.mount_point.clone();
+            let options = system::volume_options(volume, &volumes, &base);
+            tauri::async_runtime::spawn(async move {
+                scan::scan_directory(&path, false, &options).await
+            })
+        })
+        .collect();
+
+    let mut overviews = Vec::with_capacity(volumes.len());
+    for (volume, handle) in volumes.into_iter().zip(handles) {
+        let scanned = match handle.await {
+            Ok(scanned) => scanned.map_err(|e| e.to_string()),
+            Err(e) => Err(e.to_string()),
+        };
+        let overview = match scanned {
+            Ok(mut result) => {
+                // 保存完整结果，便于从概览进入某个目录
+                result.result_id = Some(state.results.lock().unwrap().insert(result.clone()));
+                scan::limit_depth(&mut result, system::OVERVIEW_DEPTH);
+                scan::format_sizes(&mut result, unit, SizeFormat::Human);
+                VolumeOverview {
+                    volume,
+                    result: Some(result),
+                    error: None,
+                }
+            }
+            Err(error) => VolumeOverview {
+                volume,
+                result: None,
+                error: Some(error),
+            },
+        };
+        overviews.push(overview);
+    }
+
+    Ok(SystemOverview::new(overviews, start_time.elapsed().as_secs_f64()))
+}
+
+// 并发统计临时文件、缓存、下载等已知位置的占用
+#[command]
+pub async fn scan_known_locations(
+    state: State<'_, AppState>,
+) -> Result<LocationsOverview, String> {
+    let start_time = std::time::Instant::now();
+    let unit = size_unit(None, &state);
+    let options = scan_options(None, &state);
+    let known = tokio::task::spawn_blocking(locations::known_locations)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let handles: Vec<_> = known
+        .iter()
+        .map(|location| {
+            let path = location.path.clone();
+            let options = options.clone();
+            tauri::async_runtime::spawn(async move {
+                scan::scan_directory(&path, false, &options).await
+            })
+        })
+        .collect();
+
+    let mut usages = Vec::with_capacity(known.len());
+    for (location, handle) in known.into_iter().zip(handles) {
+        let scanned = match handle.await {
+            Ok(scanned) => scanned.map_err(|e| e.to_string()),
+            Err(e) => Err(e.to_string()),
+        };
+        let usage = match scanned {
+            Ok(result) => {
+                let mut usage =
+                    LocationUsage::new(location, result.total_size, result.root.files, unit);
+                usage.result_id = Some(state.results.lock().unwrap().insert(result));
+                usage
+            }
+            Err(error) => LocationUsage::failed(location, error),
+        };
+        usages.push(usage);
+    }
+
+    Ok(LocationsOverview::new(usages, unit, start_time.elapsed().as_secs_f64()))
+}
+
+#[command]
+pub fn toggle_window(app: AppHandle) {
+    tray::toggle_window(&app);
+}
+
+// 运行扫描，期间按固定间隔回调
+async fn drive_scan<T>(
+    scan: impl std::future::Future<Output = T>,
+    interval: Duration,
+    mut on_tick: impl FnMut(),
+) -> T {
+    tokio::pin!(scan);
+    let mut ticker = tokio::time::interval(interval);
+    // 第一次 tick 立即完成，跳过
+    ticker.tick().await;
+
+    loop {
+        tokio::select! {
+            result = &mut scan => return result,
+            _ = ticker.tick() => on_tick(),
+        }
+    }
+}
+
+fn stored_result(result_id: &str, state: &State<'_, AppState>) -> Result<Arc<ScanResult>, String> {
+    state
+        .results
+        .lock()
+        .unwrap()
+        .get(result_id)
+        .ok_or_else(|| "扫描结果已过期，请重新扫描".to_string())
+}
+
+#[command]
+pub fn get_result(
+    result_id: String,
+    format: Option<SizeFormat>,
+    state: State<'_, AppState>,
+) -> Result<ScanResult, String> {
+    let mut result = (*stored_result(&result_id, &state)?).clone();
+    result.result_id = Some(result_id);
+    scan::format_sizes(&mut result, size_unit(None, &state), format.unwrap_or_default());
+    Ok(result)
+}
+
+#[command]
+pub fn get_result_page(
+    result_id: String,
+    offset: usize,
+    limit: usize,
+    format: Option<SizeFormat>,
+    state: State<'_, AppState>,
+) -> Result<ResultPage, String> {
+    let result = stored_result(&result_id, &state)?;
+    let mut page = store::page(&result_id, &result, offset, limit);
+    let unit = size_unit(None, &state);
+    scan::format_item_sizes(&mut page.items, unit, format.unwrap_or_default());
+    Ok(page)
+}
+
+#[command]
+pub fn search_result(
+    result_id: String,
+    query: String,
+    format: Option<SizeFormat>,
+    include_ext: Option<Vec<String>>,
+    exclude_ext: Option<Vec<String>>,
+    state: State<'_, AppState>,
+) -> Result<Vec<Item>, String> {
+    let result = stored_result(&result_id, &state)?;
+    let mut filters = vec![Filter::NameMatches(query)];
+    filters.extend(include_ext.map(Filter::ExtIn));
+    filters.extend(exclude_ext.map(Filter::ExtNotIn));
+    let mut items = filter::filter_items(&result.items, &filters);
+    let unit = size_unit(None, &state);
+    scan::format_item_sizes(&mut items, unit, format.unwrap_or_default());
+    Ok(items)
+}
+
+// 按过滤条件筛选结果，条件全部满足的条目才保留
+#[command]
+pub fn filter_result(
+    result_id: String,
+    filters: Vec<Filter>,
+    format: Option<SizeFormat>,
+    state: State<'_, AppState>,
+) -> Result<ScanResult, String> {
+    let stored = stored_result(&result_id, &state)?;
+    let mut result = filter::apply(&stored, &filters);
+    result.result_id = Some(result_id);
+    scan::format_sizes(&mut result, size_unit(None, &state), format.unwrap_or_default());
+    Ok(result)
+}
+
+// 把结果导出为独立的 HTML 报告，返回写入的文件路径
+#[command]
+pub fn export_report(
+    result_id: String,
+    path: String,
+    state: State<'_, AppState>,
+) -> Result<String, String> {
+    let result = stored_result(&result_id, &state)?;
+    let html = report::render_html(&result, size_unit(None, &state));
+    std::fs::write(&path, html).map_err(|e| format!("无法写入报告: {}", e))?;
+    Ok(path)
+}
+
+#[command]
+pub fn get_result_treemap(
+    result_id: String,
+    options: Option<TreemapOptions>,
+    state: State<'_, AppState>,
+) -> Result<TreemapNode, String> {
+    let result = stored_result(&result_id, &state)?;
+    Ok(treemap::build_treemap(&result, &options.unwrap_or_default()))
+}
+
+#[command]
+pub fn diff_results(
+    old_id: String,
+    new_id: String,
+    state: State<'_, AppState>,
+) -> Result<SnapshotDiff, String> {
+    let old = stored_result(&old_id, &state)?;
+    let new = stored_result(&new_id, &state)?;
+    Ok(diff::diff_items(&old.items, &new.items))
+}
+
+#[command]
+pub async fn top_by_extension(
+    path: String,
+    ext: String,
+    n: usize,
+    state: State<'_, AppState>,
+) -> Result<ExtensionReport, String> {
+    let options = scan_options(None, &state);
+    let result = scan::scan_directory(path.trim(), false, &options)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(scan::top_by_extension(&result, &ext, n, size_unit(None, &state)))
+}
+
+#[command]
+pub async fn stale_files(
+    path: String,
+    days: u64,
+    min_size: i64,
+    state: State<'_, AppState>,
+) -> Result<Vec<Item>, String> {
+    let options = ScanOptions {
+        older_than_days: Some(days),
+        ..ScanOptions::default()
+    };
+    let options = scan_options(Some(options), &state);
+    let result = scan::scan_directory(path.trim(), false, &options)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let mut items: Vec<Item> = result
+        .items
+        .into_iter()
+        .filter(|item| !item.is_dir && item.size >= min_size)
+        .collect();
+    scan::format_item_sizes(&mut items, size_unit(None, &state), SizeFormat::Human);
+    Ok(items)
+}
+
+// 依赖文件系统的访问时间，结果只是尽力而为，不可靠时报告中带有 warning
+#[command]
+pub async fn unused_files(
+    path: String,
+    days: u64,
+    min_size: i64,
+    state: State<'_, AppState>,
+) -> Result<UnusedReport, String> {
+    let options = ScanOptions {
+        record_access_time: true,
+        ..ScanOptions::default()
+    };
+    let options = scan_options(Some(options), &state);
+    let result = scan::scan_directory(path.trim(), false, &options)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(scan::unused_since(&result, days, min_size, size_unit(None, &state)))
+}
+
+#[command]
+pub async fn find_empty(path: String) -> Result<EmptyReport, String> {
+    let root = std::fs::canonicalize(path.trim()).map_err(|e| format!("无法访问路径: {}", e))?;
+    if !root.is_dir() {
+        return Err("不是目录".to_string());
+    }
+    tokio::task::spawn_blocking(move || cleanup::find_empty(&root))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+// 与 delete_paths 一样记入删除日志
+#[command]
+pub async fn delete_empty(
+    paths: Vec<String>,
+    state: State<'_, AppState>,
+) -> Result<TrashReport, String> {
+    let staging_dir = deletions::staging_dir(state.settings_path.as_deref());
+    let report = tokio::task::spawn_blocking(move || cleanup::delete_empty(&paths, &staging_dir))
+        .await
+        .map_err(|e| e.to_string())?;
+
+    for entry in &report.deleted {
+        scan::invalidate_cache(&entry.original_path);
+    }
+    record_deletions(report.deleted.clone(), &state)?;
+
+    Ok(report)
+}
+
+// 按 Overwrite 被替换的目标记入删除日志
+#[command]
+pub async fn rename_path(
+    old: String,
+    new: String,
+    on_conflict: Option<ConflictPolicy>,
+    state: State<'_, AppState>,
+) -> Result<MovedPath, String> {
+    let staging_dir = deletions::staging_dir(state.settings_path.as_deref());
+    let moved = tokio::task::spawn_blocking(move || {
+        fileops::rename_path(&old, &new, on_conflict.unwrap_or_default(), &staging_dir)
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+
+    scan::invalidate_cache(&moved.from);
+    scan::invalidate_cache(&moved.to);
+    record_deletions(moved.replaced.iter().cloned().collect(), &state)?;
+    Ok(moved)
+}
+
+#[command]
+pub async fn move_paths(
+    paths: Vec<String>,
+    dest: String,
+    on_conflict: Option<ConflictPolicy>,
+    state: State<'_, AppState>,
+) -> Result<MoveReport, String> {
+    let staging_dir = deletions::staging_dir(state.settings_path.as_deref());
+    let report = tokio::task::spawn_blocking(move || {
+        fileops::move_paths(&paths, &dest, on_conflict.unwrap_or_default(), &staging_dir)
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+
+    // 源和目标所在的子树都已变化
+    for moved in &report.moved {
+        scan::invalidate_cache(&moved.from);
+        scan::invalidate_cache(&moved.to);
+    }
+    let replaced = report.moved.iter().filter_map(|moved| moved.replaced.clone());
+    record_deletions(replaced.collect(), &state)?;
+
+    Ok(report)
+}
+
+// 移到回收站并记入删除日志，之后可通过 restore_deleted 撤销
+#[command]
+pub async fn delete_paths(
+    paths: Vec<String>,
+    state: State<'_, AppState>,
+) -> Result<TrashReport, String> {
+    let staging_dir = deletions::staging_dir(state.settings_path.as_deref());
+    let report = tokio::task::spawn_blocking(move || deletions::delete_paths(&paths, &staging_dir))
+        .await
+        .map_err(|e| e.to_string())?;
+
+    for entry in &report.deleted {
+        scan::invalidate_cache(&entry.original_path);
+    }
+    record_deletions(report.deleted.clone(), &state)?;
+
+    Ok(report)
+}
+
+#[command]
+pub fn list_deleted(state: State<'_, AppState>) -> Vec<DeletionEntry> {
+    state.deletions.lock().unwrap().list()
+}
+
+#[command]
+pub async fn restore_deleted(
+    ids: Vec<String>,
+    state: State<'_, AppState>,
+) -> Result<RestoreReport, String> {
+    let entries = state.deletions.lock().unwrap().take(&ids);
+    let (entries, outcomes) = tokio::task::spawn_blocking(move || {
+        let outcomes = deletions::restore(&entries);
+        (entries, outcomes)
+    })
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let mut restored = Vec::new();
+    let mut failed = Vec::new();
+    let mut kept = Vec::new();
+    for (entry, outcome) in entries.into_iter().zip(outcomes) {
+        match outcome {
+            Ok(()) => {
+                scan::invalidate_cache(&entry.original_path);
+                restored.push(entry.original_path);
+            }
+            Err(error) => {
+                failed.push(MoveFailure {
+                    path: entry.id.clone(),
+                    error,
+                });
+                kept.push(entry);
+            }
+        }
+    }
+
+    // 恢复失败的记录放回日志，可以稍后重试
+    let mut journal = state.deletions.lock().unwrap();
+    journal.record(kept);
+    save_journal(&journal, &state)?;
+
+    Ok(RestoreReport { restored, failed })
+}
+
+#[command]
+pub async fn get_trash_usage(state: State<'_, AppState>) -> Result<Vec<TrashUsage>, String> {
+    let unit = size_unit(None, &state);
+    tokio::task::spawn_blocking(move || recycle::trash_usage(unit))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+// 清空系统回收站，删除日志中移到回收站的记录随之失效
+#[command]
+pub async fn empty_trash(state: State<'_, AppState>) -> Result<(), String> {
+    tokio::task::spawn_blocking(recycle::empty_trash)
+        .await
+        .map_err(|e| e.to_string())??;
+
+    let mut journal = state.deletions.lock().unwrap();
+    journal.forget_trashed();
+    save_journal(&journal, &state)
+}
+
+fn record_deletions(entries: Vec<DeletionEntry>, state: &AppState) -> Result<(), String> {
+    if entries.is_empty() {
+        return Ok(());
+    }
+    let mut journal = state.deletions.lock().unwrap();
+    journal.record(entries);
+    save_journal(&journal, state)
+}
+
+fn save_journal(journal: &DeletionJournal, state: &AppState) -> Result<(), String> {
+    match &state.settings_path {
+        Some(path) => journal.save(&deletions::journal_path(path)),
+        None => Ok(()),
+    }
+}
+
 #[command]
 pub fn get_history(state: State<'_, AppState>) -> Vec<HistoryItem> {
     let history = state.history.lock().unwrap();
@@ -56,33 +782,276 @@ pub fn get_history(state: State<'_, AppState>) -> Vec<HistoryItem> {
 
 #[command]
 pub fn get_history_item(path: String, state: State<'_, AppState>) -> Option<ScanResult> {
+    let unit = size_unit(None, &state);
     let history = state.history.lock().unwrap();
+    let key = paths::path_key(&path);
 
-    // 查找最新的匹配历史记录
+    // 查找最新的匹配历史记录（等价路径写法视为同一路径）
     for item in history.iter().rev() {
-        if item.path == path {
-            return Some(ScanResult {
+        if paths::path_key(&item.path) == key {
+            let mut result = ScanResult {
                 items: item.items.clone(),
                 total_size: item.total_size,
                 total_size_formatted: item.size_format.clone(),
                 scan_time: 0.0,
                 path: item.path.clone(),
-            });
+                treemap: None,
+                result_id: None,
+                stats: None,
+                root: RootSummary::from_items(&item.items),
+                audit: None,
+                estimate: None,
+            };
+            scan::format_sizes(&mut result, unit, SizeFormat::Human);
+            return Some(result);
         }
     }
 
     None
 }
 
+// 给某次扫描（路径 + 扫描时间的 Unix 秒）附加备注，备注为空时删除
 #[command]
-pub fn clear_history(state: State<'_, AppState>) -> Result<(), String> {
+pub fn annotate_history(
+    path: String,
+    timestamp: i64,
+    note: String,
+    state: State<'_, AppState>,
+) -> Result<HistoryItem, String> {
+    let key = paths::path_key(&path);
     let mut history = state.history.lock().unwrap();
-    history.clear();
+    let item = history
+        .iter_mut()
+        .rev()
+        .find(|item| item.is_snapshot(&key, timestamp))
+        .ok_or_else(|| "未找到该历史记录".to_string())?;
+    item.set_note(&note);
+    let item = item.clone();
+    drop(history);
+    save_history(&state);
+    Ok(item)
+}
+
+#[command]
+pub fn get_trend(path: String, state: State<'_, AppState>) -> Trend {
+    let history = state.history.lock().unwrap();
+    scan::build_trend(&history, path.trim())
+}
+
+#[command]
+pub fn clear_history(state: State<'_, AppState>) -> Result<(), String> {
+    state.history.lock().unwrap().clear();
+    save_history(&state);
+    Ok(())
+}
+
+#[command]
+pub fn set_baseline(path: String, state: State<'_, AppState>) -> Result<(), String> {
+    let key = paths::path_key(&path);
+    let history = state.history.lock().unwrap();
+
+    // 以该路径最新的一次扫描作为基线
+    let snapshot = history
+        .iter()
+        .rev()
+        .find(|item| paths::path_key(&item.path) == key)
+        .cloned()
+        .ok_or_else(|| "未找到该路径的扫描记录，请先扫描".to_string())?;
+    drop(history);
+
+    state.baselines.lock().unwrap().insert(key, snapshot);
+    save_history(&state);
     Ok(())
 }
 
 #[command]
-pub fn open_in_explorer(path: String) -> Result<(), String> {
+pub async fn compare_to_baseline(
+    path: String,
+    state: State<'_, AppState>,
+) -> Result<BaselineComparison, String> {
+    let path = path.trim();
+    let baseline = state
+        .baselines
+        .lock()
+        .unwrap()
+        .get(&paths::path_key(path))
+        .cloned()
+        .ok_or_else(|| "该路径尚未设置基线".to_string())?;
+
+    // 强制重新扫描，确保与当前磁盘状态比较
+    let options = scan_options(None, &state);
+    let current = scan::scan_directory(path, true, &options)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    Ok(BaselineComparison {
+        path: path.to_string(),
+        baseline_time: baseline.scan_time,
+        diff: diff::diff_items(&baseline.items, &current.items),
+    })
+}
+
+#[command]
+pub fn list_presets(state: State<'_, AppState>) -> Vec<ScanPreset> {
+    state.settings.lock().unwrap().presets.clone()
+}
+
+#[command]
+pub async fn run_preset(name: String, state: State<'_, AppState>) -> Result<ScanResult, String> {
+    let preset = state
+        .settings
+        .lock()
+        .unwrap()
+        .preset(&name)
+        .cloned()
+        .ok_or_else(|| "未找到该扫描预设".to_string())?;
+
+    let path = preset.path.trim();
+    let options = scan_options(Some(preset.options), &state);
+    let mut result = scan::scan_directory(path, false, &options)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    record_scan(path, &options, &mut result, &state);
+    if let Some(depth) = preset.depth {
+        scan::limit_depth(&mut result, depth);
+    }
+    scan::format_sizes(&mut result, size_unit(None, &state), SizeFormat::Human);
+    Ok(result)
+}
+
+#[command]
+pub fn get_settings(state: State<'_, AppState>) -> Settings {
+    state.settings.lock().unwrap().clone()
+}
+
+// 保存界面状态（展开的目录、排序方式），最后扫描的路径由扫描时记录
+#[command]
+pub fn save_session(session: Session, state: State<'_, AppState>) -> Result<(), String> {
+    let mut current = state.session.lock().unwrap();
+    *current = Session {
+        last_path: current.last_path.clone(),
+        ..session
+    };
+    match &state.settings_path {
+        Some(path) => state.session_writer.write(|| current.save(path)),
+        None => Ok(()),
+    }
+}
+
+// 启动时恢复上次的路径、结果和界面状态，没有上次扫描时返回空
+#[command]
+pub fn restore_session(state: State<'_, AppState>) -> Option<RestoredSession> {
+    let session = state.session.lock().unwrap().clone();
+    let unit = state.settings.lock().unwrap().size_unit;
+    let last_path = session.last_path.clone()?;
+
+    let result = state
+        .settings_path
+        .as_deref()
+        .map(session::last_result_path)
+        .and_then(|path| session::load_last_result(&path))
+        .filter(|result| result.path == last_path)
+        .map(|mut result| {
+            result.result_id = Some(state.results.lock().unwrap().insert(result.clone()));
+            scan::format_sizes(&mut result, unit, SizeFormat::Human);
+            result
+        });
+
+    Some(RestoredSession { session, result })
+}
+
+// 合并部分设置并写入磁盘，缓存和历史的限制立即生效
+#[command]
+pub fn update_settings(
+    patch: serde_json::Value,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<Settings, String> {
+    let mut settings = state.settings.lock().unwrap();
+    let updated = settings.merge(patch)?;
+    if let Some(path) = &state.settings_path {
+        updated.save(path)?;
+    }
+
+    apply_settings(&updated, &state);
+    *settings = updated.clone();
+    drop(settings);
+    schedule_jobs(&app);
+
+    Ok(updated)
+}
+
+fn apply_settings(settings: &Settings, state: &AppState) {
+    scan::set_cache_limits(settings.cache_max_entries, settings.cache_max_size_mb);
+    scan::set_prewarm(settings.prewarm_subdirectories);
+    let mut history = state.history.lock().unwrap();
+    let excess = history.len().saturating_sub(settings.history_limit);
+    history.drain(..excess);
+    drop(history);
+    if excess > 0 {
+        save_history(state);
+    }
+
+    // 并行上限提高后立即启动排队的任务
+    state.jobs.lock().unwrap().set_max_parallel(settings.max_parallel_scans);
+}
+
+// 把设置、预设、历史和基线打包成一个归档文件，用于备份或迁移到其他机器
+#[command]
+pub async fn export_state(path: String, state: State<'_, AppState>) -> Result<(), String> {
+    let archive = StateArchive::new(
+        state.settings.lock().unwrap().clone(),
+        state.history.lock().unwrap().clone(),
+        state.baselines.lock().unwrap().clone(),
+    );
+    tokio::task::spawn_blocking(move || archive.save(std::path::Path::new(path.trim())))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+// 导入归档：设置整体替换，历史与现有记录合并，同一路径的基线被覆盖；界面状态不在归档中，保持不变
+#[command]
+pub async fn import_state(
+    path: String,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<ImportSummary, String> {
+    let archive =
+        tokio::task::spawn_blocking(move || StateArchive::load(std::path::Path::new(path.trim())))
+            .await
+            .map_err(|e| e.to_string())??;
+
+    let mut settings = state.settings.lock().unwrap();
+    if let Some(path) = &state.settings_path {
+        archive.settings.save(path)?;
+    }
+    let limit = archive.settings.history_limit;
+    let history = archive::merge_history(&mut state.history.lock().unwrap(), archive.history, limit);
+    apply_settings(&archive.settings, &state);
+    let summary = ImportSummary {
+        exported_at: archive.exported_at,
+        presets: archive.settings.presets.len(),
+        history,
+        baselines: archive.baselines.len(),
+    };
+    state.baselines.lock().unwrap().extend(archive.baselines);
+    *settings = archive.settings;
+    drop(settings);
+    save_history(&state);
+    schedule_jobs(&app);
+
+    Ok(summary)
+}
+
+// path 可以是绝对路径，也可以是相对于 root 的条目路径
+#[command]
+pub fn open_in_explorer(path: String, root: Option<String>) -> Result<(), String> {
+    let path = match root {
+        Some(root) if Path::new(path.trim()).is_relative() => scan::abs_path(&root, &path),
+        _ => paths::display(&path),
+    };
+
     #[cfg(target_os = "windows")]
     {
         use std::process::Command;
@@ -113,3 +1082,20 @@ pub fn open_in_explorer(path: String) -> Result<(), String> {
         Ok(())
     }
 }
+
+#[command]
+pub fn register_shell_integration() -> Result<(), String> {
+    shell::register()
+}
+
+#[command]
+pub fn unregister_shell_integration() -> Result<(), String> {
+    shell::unregister()
+}
+
+#[command]
+pub async fn who_locks(path: String) -> Result<Vec<LockingProcess>, String> {
+    tokio::task::spawn_blocking(move || locks::who_locks(path.trim()))
+        .await
+        .map_err(|e| e.to_string())?
+}
@@ -0,0 +1,300 @@
+use crate::fileops::{self, MoveFailure};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+// 日志中最多保留的删除记录数
+const MAX_JOURNAL_ENTRIES: usize = 500;
+
+// 被删除条目的去向
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", tag = "kind")]
+pub enum DeletedLocation {
+    // 系统回收站
+    Trash,
+    // 回收站不可用时移动到应用的暂存目录
+    Staging { path: String },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeletionEntry {
+    pub id: String,
+    pub original_path: String,
+    #[serde(with = "chrono::serde::ts_seconds")]
+    pub deleted_at: DateTime<Utc>,
+    pub location: DeletedLocation,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrashReport {
+    pub deleted: Vec<DeletionEntry>,
+    pub failed: Vec<MoveFailure>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RestoreReport {
+    // 已恢复条目的原路径
+    pub restored: Vec<String>,
+    // 失败项的 path 为记录 ID
+    pub failed: Vec<MoveFailure>,
+}
+
+// 删除日志与设置文件放在同一目录
+pub fn journal_path(settings_path: &Path) -> PathBuf {
+    settings_path.with_file_name("deletions.json")
+}
+
+// 无法确定配置目录时暂存到临时目录
+pub fn staging_dir(settings_path: Option<&Path>) -> PathBuf {
+    match settings_path {
+        Some(path) => path.with_file_name("staging"),
+        None => std::env::temp_dir().join("search-tool-staging"),
+    }
+}
+
+// 应用内发起的删除记录，持久化后可在重启后撤销
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DeletionJournal {
+    entries: Vec<DeletionEntry>,
+}
+
+impl DeletionJournal {
+    // 文件不存在或无法解析时从空日志开始
+    pub fn load(path: &Path) -> Self {
+        std::fs::read_to_string(path)
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+        }
+        let json = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        // 先写临时文件再替换，写入中途崩溃不会损坏已有的日志
+        let temp = path.with_extension("json.tmp");
+        std::fs::write(&temp, json).map_err(|e| e.to_string())?;
+        std::fs::rename(&temp, path).map_err(|e| e.to_string())
+    }
+
+    // 最新的在前
+    pub fn list(&self) -> Vec<DeletionEntry> {
+        self.entries.iter().rev().cloned().collect()
+    }
+
+    // 超出上限时丢弃最早的记录，暂存目录中的数据随之删除
+    pub fn record(&mut self, entries: Vec<DeletionEntry>) {
+        self.entries.extend(entries);
+        if self.entries.len() > MAX_JOURNAL_ENTRIES {
+            let excess = self.entries.len() - MAX_JOURNAL_ENTRIES;
+            for entry in self.entries.drain(..excess) {
+                purge_staged(&entry);
+            }
+        }
+    }
+
+    pub fn take(&mut self, ids: &[String]) -> Vec<DeletionEntry> {
+        let (taken, kept) = std::mem::take(&mut self.entries)
+            .into_iter()
+            .partition(|entry| ids.contains(&entry.id));
+        self.entries = kept;
+        taken
+    }
+
+    // 清空回收站后，回收站中的记录已无法恢复
+    pub fn forget_trashed(&mut self) {
+        self.entries.retain(|entry| !matches!(entry.location, DeletedLocation::Trash));
+    }
+}
+
+// 逐项移到回收站，回收站不可用时移到暂存目录
+pub fn delete_paths(paths: &[String], staging_dir: &Path) -> TrashReport {
+    let mut deleted = Vec::new();
+    let mut failed = Vec::new();
+
+    for path_str in paths {
+        match delete_path(path_str, staging_dir) {
+            Ok(entry) => deleted.push(entry),
+            Err(error) => failed.push(MoveFailure {
+                path: path_str.clone(),
+                error,
+            }),
+        }
+    }
+
+    TrashReport { deleted, failed }
+}
+
+// 应用内的删除都经过这里或 stage，返回的记录由调用方写入日志
+pub fn delete_path(path_str: &str, staging_dir: &Path) -> Result<DeletionEntry, String> {
+    let path = Path::new(path_str);
+    std::fs::symlink_metadata(path).map_err(|e| format!("无法访问路径: {}", e))?;
+
+    let id = uuid::Uuid::new_v4().to_string();
+    let location = if supports_restore_from_trash() && trash::delete(path).is_ok() {
+        DeletedLocation::Trash
+    } else {
+        let name = path.file_name().ok_or_else(|| "无效的路径".to_string())?;
+        move_to_staging(path, Path::new(name), &id, staging_dir)?
+    };
+    Ok(DeletionEntry {
+        id,
+        original_path: path_str.to_string(),
+        deleted_at: Utc::now(),
+        location,
+    })
+}
+
+// 把 path 移到暂存目录，记为 original 被删除；用于覆盖时被替换的目标，
+// 此时 path 是目标改名后的备份，恢复时回到 original
+pub fn stage(path: &Path, original: &Path, staging_dir: &Path) -> Result<DeletionEntry, String> {
+    let name = original.file_name().ok_or_else(|| "无效的路径".to_string())?;
+    let id = uuid::Uuid::new_v4().to_string();
+    let location = move_to_staging(path, Path::new(name), &id, staging_dir)?;
+    Ok(DeletionEntry {
+        id,
+        original_path: original.to_string_lossy().to_string(),
+        deleted_at: Utc::now(),
+        location,
+    })
+}
+
+fn move_to_staging(
+    path: &Path,
+    name: &Path,
+    id: &str,
+    staging_dir: &Path,
+) -> Result<DeletedLocation, String> {
+    let dir = staging_dir.join(id);
+    std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    let staged = dir.join(name);
+    if let Err(e) = fileops::move_entry(path, &staged) {
+        let _ = std::fs::remove_dir(&dir);
+        return Err(e.to_string());
+    }
+    Ok(DeletedLocation::Staging {
+        path: staged.to_string_lossy().to_string(),
+    })
+}
+
+// 删除暂存的数据及其所在的 <id> 目录
+fn purge_staged(entry: &DeletionEntry) {
+    let DeletedLocation::Staging { path } = &entry.location else {
+        return;
+    };
+    // 只删除确实位于本条记录的 <id> 目录下的数据
+    let dir = Path::new(path).parent();
+    if let Some(dir) = dir.filter(|dir| dir.file_name().is_some_and(|name| *name == *entry.id)) {
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
+
+// 只有能列出并恢复回收站内容的平台才使用系统回收站
+fn supports_restore_from_trash() -> bool {
+    cfg!(any(
+        target_os = "windows",
+        all(unix, not(target_os = "macos"), not(target_os = "ios"), not(target_os = "android"))
+    ))
+}
+
+// 恢复到原位置，原位置已有同名条目时失败；失败的记录由调用方放回日志
+pub fn restore(entries: &[DeletionEntry]) -> Vec<Result<(), String>> {
+    entries.iter().map(restore_one).collect()
+}
+
+fn restore_one(entry: &DeletionEntry) -> Result<(), String> {
+    let original = PathBuf::from(&entry.original_path);
+    if std::fs::symlink_metadata(&original).is_ok() {
+        return Err("原位置已存在同名条目".to_string());
+    }
+
+    match &entry.location {
+        DeletedLocation::Trash => restore_from_trash(&original, entry.deleted_at),
+        DeletedLocation::Staging { path } => {
+            let staged = Path::new(path);
+            fileops::move_entry(staged, &original).map_err(|e| e.to_string())?;
+            if let Some(dir) = staged.parent() {
+                let _ = std::fs::remove_dir(dir);
+            }
+            Ok(())
+        }
+    }
+}
+
+#[cfg(any(
+    target_os = "windows",
+    all(unix, not(target_os = "macos"), not(target_os = "ios"), not(target_os = "android"))
+))]
+fn restore_from_trash(original: &Path, deleted_at: DateTime<Utc>) -> Result<(), String> {
+    // 同一路径可能被删除过多次，取删除时间最接近记录的一项
+    let item = trash::os_limited::list()
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .filter(|item| item.original_path() == original)
+        .min_by_key(|item| (item.time_deleted - deleted_at.timestamp()).abs())
+        .ok_or_else(|| "回收站中已找不到该条目".to_string())?;
+    trash::os_limited::restore_all([item]).map_err(|e| e.to_string())
+}
+
+#[cfg(not(any(
+    target_os = "windows",
+    all(unix, not(target_os = "macos"), not(target_os = "ios"), not(target_os = "android"))
+)))]
+fn restore_from_trash(_original: &Path, _deleted_at: DateTime<Utc>) -> Result<(), String> {
+    Err("当前平台不支持从回收站恢复".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn staged_entry(staging: &Path, n: usize) -> DeletionEntry {
+        let id = format!("entry-{}", n);
+        let dir = staging.join(&id);
+        std::fs::create_dir_all(&dir).unwrap();
+        let staged = dir.join("file.txt");
+        std::fs::write(&staged, "x").unwrap();
+        DeletionEntry {
+            id,
+            original_path: format!("/original/{}", n),
+            deleted_at: Utc::now(),
+            location: DeletedLocation::Staging {
+                path: staged.to_string_lossy().to_string(),
+            },
+        }
+    }
+
+    #[test]
+    fn evicted_entries_release_staged_data() {
+        let staging = std::env::temp_dir().join(format!("deletions-{}", uuid::Uuid::new_v4()));
+        let mut journal = DeletionJournal::default();
+        let entries = (0..MAX_JOURNAL_ENTRIES + 2)
+            .map(|n| staged_entry(&staging, n))
+            .collect();
+        journal.record(entries);
+
+        assert_eq!(journal.list().len(), MAX_JOURNAL_ENTRIES);
+        assert!(!staging.join("entry-0").exists());
+        assert!(!staging.join("entry-1").exists());
+        assert!(staging.join("entry-2").exists());
+        std::fs::remove_dir_all(&staging).unwrap();
+    }
+
+    #[test]
+    fn save_replaces_journal_file() {
+        let dir = std::env::temp_dir().join(format!("deletions-{}", uuid::Uuid::new_v4()));
+        let path = journal_path(&dir.join("settings.json"));
+        let mut journal = DeletionJournal::default();
+        journal.save(&path).unwrap();
+        journal.record(vec![staged_entry(&dir.join("staging"), 0)]);
+        journal.save(&path).unwrap();
+
+        assert_eq!(DeletionJournal::load(&path).list().len(), 1);
+        assert!(!path.with_extension("json.tmp").exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
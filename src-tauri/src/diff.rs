@@ -0,0 +1,87 @@
+use crate::scan::Item;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResizedItem {
+    pub path: String,
+    pub old_size: i64,
+    pub new_size: i64,
+    pub delta: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SnapshotDiff {
+    pub added: Vec<Item>,
+    pub removed: Vec<Item>,
+    pub resized: Vec<ResizedItem>,
+    pub size_delta: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BaselineComparison {
+    pub path: String,
+    #[serde(with = "chrono::serde::ts_seconds")]
+    pub baseline_time: chrono::DateTime<chrono::Utc>,
+    #[serde(flatten)]
+    pub diff: SnapshotDiff,
+}
+
+// 比较两次快照中的文件（目录大小由文件派生，不单独比较）
+pub fn diff_items(old: &[Item], new: &[Item]) -> SnapshotDiff {
+    let old_files: HashMap<&str, &Item> = old
+        .iter()
+        .filter(|item| !item.is_dir)
+        .map(|item| (item.path.as_str(), item))
+        .collect();
+    let new_files: HashMap<&str, &Item> = new
+        .iter()
+        .filter(|item| !item.is_dir)
+        .map(|item| (item.path.as_str(), item))
+        .collect();
+
+    let mut added = Vec::new();
+    let mut resized = Vec::new();
+    let mut size_delta = 0i64;
+
+    for (path, item) in new_files.iter() {
+        match old_files.get(path) {
+            None => {
+                size_delta += item.size;
+                added.push((*item).clone());
+            }
+            Some(old_item) if old_item.size != item.size => {
+                let delta = item.size - old_item.size;
+                size_delta += delta;
+                resized.push(ResizedItem {
+                    path: path.to_string(),
+                    old_size: old_item.size,
+                    new_size: item.size,
+                    delta,
+                });
+            }
+            Some(_) => {}
+        }
+    }
+
+    let mut removed: Vec<Item> = old_files
+        .iter()
+        .filter(|(path, _)| !new_files.contains_key(*path))
+        .map(|(_, item)| (*item).clone())
+        .collect();
+    size_delta -= removed.iter().map(|item| item.size).sum::<i64>();
+
+    added.sort_by_key(|item| std::cmp::Reverse(item.size));
+    removed.sort_by_key(|item| std::cmp::Reverse(item.size));
+    resized.sort_by_key(|item| std::cmp::Reverse(item.delta.abs()));
+
+    SnapshotDiff {
+        added,
+        removed,
+        resized,
+        size_delta,
+    }
+}
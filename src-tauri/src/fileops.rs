@@ -0,0 +1,429 @@
+use crate::deletions::{self, DeletionEntry};
+use crate::paths;
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::path::{Path, PathBuf};
+
+// 目标已存在时的处理方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConflictPolicy {
+    #[default]
+    Fail,
+    Skip,
+    // 在名称后追加 " (1)"、" (2)" 等
+    Rename,
+    Overwrite,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MovedPath {
+    pub from: String,
+    pub to: String,
+    // 按 Overwrite 被替换的目标，已记入删除日志，可以撤销
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub replaced: Option<DeletionEntry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MoveFailure {
+    pub path: String,
+    pub error: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MoveReport {
+    pub moved: Vec<MovedPath>,
+    // 因目标已存在而跳过的源路径
+    pub skipped: Vec<String>,
+    pub failed: Vec<MoveFailure>,
+}
+
+// new 不含路径分隔符时视为同一目录下的新名称，否则为完整的目标路径
+pub fn rename_path(
+    old: &str,
+    new: &str,
+    policy: ConflictPolicy,
+    staging_dir: &Path,
+) -> Result<MovedPath, String> {
+    let from = Path::new(old);
+    std::fs::symlink_metadata(from).map_err(|e| format!("无法访问路径: {}", e))?;
+
+    let new = new.trim();
+    if new.is_empty() {
+        return Err("新名称不能为空".to_string());
+    }
+    let to = if new.contains(['/', '\\']) {
+        PathBuf::from(new)
+    } else {
+        from.with_file_name(new)
+    };
+
+    place(from, to, policy, staging_dir)?.ok_or_else(|| "目标已存在，已跳过".to_string())
+}
+
+// 把多个条目移动到目标目录下，逐项处理，单项失败不影响其余条目
+pub fn move_paths(
+    paths: &[String],
+    dest: &str,
+    policy: ConflictPolicy,
+    staging_dir: &Path,
+) -> Result<MoveReport, String> {
+    let dest = Path::new(dest);
+    if !dest.is_dir() {
+        return Err("目标不是目录".to_string());
+    }
+
+    let mut moved = Vec::new();
+    let mut skipped = Vec::new();
+    let mut failed = Vec::new();
+
+    for path_str in paths {
+        let from = Path::new(path_str);
+        let result = from
+            .file_name()
+            .ok_or_else(|| "无效的路径".to_string())
+            .and_then(|name| {
+                // 不能把目录移动到它自身或其子目录中
+                if dest.starts_with(from) {
+                    return Err("不能移动到自身的子目录".to_string());
+                }
+                place(from, dest.join(name), policy, staging_dir)
+            });
+
+        match result {
+            Ok(Some(entry)) => moved.push(entry),
+            Ok(None) => skipped.push(path_str.clone()),
+            Err(error) => failed.push(MoveFailure {
+                path: path_str.clone(),
+                error,
+            }),
+        }
+    }
+
+    Ok(MoveReport {
+        moved,
+        skipped,
+        failed,
+    })
+}
+
+// 按冲突策略移动到目标，返回 None 表示跳过
+fn place(
+    from: &Path,
+    target: PathBuf,
+    policy: ConflictPolicy,
+    staging_dir: &Path,
+) -> Result<Option<MovedPath>, String> {
+    let moved = |to: &Path, replaced| MovedPath {
+        from: paths::slash(from),
+        to: paths::slash(to),
+        replaced,
+    };
+
+    if std::fs::symlink_metadata(&target).is_err() {
+        move_entry(from, &target).map_err(|e| e.to_string())?;
+        return Ok(Some(moved(&target, None)));
+    }
+
+    // 目标就是源本身（同名、仅大小写不同或移动到原目录）时不能按冲突处理，否则会删掉源
+    if is_same_entry(from, &target) {
+        if from.file_name() != target.file_name() {
+            std::fs::rename(from, &target).map_err(|e| e.to_string())?;
+        }
+        return Ok(Some(moved(&target, None)));
+    }
+
+    match policy {
+        ConflictPolicy::Fail => Err(format!("目标已存在: {}", paths::slash(&target))),
+        ConflictPolicy::Skip => Ok(None),
+        ConflictPolicy::Rename => {
+            let target = unique_path(&target);
+            move_entry(from, &target).map_err(|e| e.to_string())?;
+            Ok(Some(moved(&target, None)))
+        }
+        ConflictPolicy::Overwrite => {
+            let backup = move_over(from, &target)?;
+            // 移动已成功；暂存失败时备份留在原目录下，不会丢失数据
+            let replaced = deletions::stage(&backup, &target, staging_dir).ok();
+            Ok(Some(moved(&target, replaced)))
+        }
+    }
+}
+
+// 先把已有的目标改名为同目录下的备份再移动，移动失败时还原，返回备份位置
+fn move_over(from: &Path, target: &Path) -> Result<PathBuf, String> {
+    let name = target
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let backup = target.with_file_name(format!(".{}.{}.bak", name, uuid::Uuid::new_v4()));
+    std::fs::rename(target, &backup).map_err(|e| format!("无法覆盖目标: {}", e))?;
+
+    if let Err(e) = move_entry(from, target) {
+        let _ = std::fs::rename(&backup, target);
+        return Err(e.to_string());
+    }
+    Ok(backup)
+}
+
+// 两个路径是否指向同一个条目；符号链接本身不跟随
+fn is_same_entry(a: &Path, b: &Path) -> bool {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        match (std::fs::symlink_metadata(a), std::fs::symlink_metadata(b)) {
+            (Ok(a), Ok(b)) => a.dev() == b.dev() && a.ino() == b.ino(),
+            _ => false,
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        match (std::fs::canonicalize(a), std::fs::canonicalize(b)) {
+            (Ok(a), Ok(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+fn remove_entry(path: &Path) -> io::Result<()> {
+    if std::fs::symlink_metadata(path)?.is_dir() {
+        std::fs::remove_dir_all(path)
+    } else {
+        std::fs::remove_file(path)
+    }
+}
+
+fn unique_path(target: &Path) -> PathBuf {
+    let stem = target
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let extension = target
+        .extension()
+        .map(|e| format!(".{}", e.to_string_lossy()))
+        .unwrap_or_default();
+
+    (1..)
+        .map(|n| target.with_file_name(format!("{} ({}){}", stem, n, extension)))
+        .find(|candidate| std::fs::symlink_metadata(candidate).is_err())
+        .unwrap_or_else(|| target.to_path_buf())
+}
+
+// 跨卷移动无法直接重命名，改为复制后删除源
+pub fn move_entry(from: &Path, to: &Path) -> io::Result<()> {
+    match std::fs::rename(from, to) {
+        Err(e) if e.kind() == io::ErrorKind::CrossesDevices => {
+            if let Err(e) = copy_recursive(from, to) {
+                // 清理复制了一半的目标，源保持不变
+                let _ = remove_entry(to);
+                return Err(e);
+            }
+            remove_entry(from)
+        }
+        result => result,
+    }
+}
+
+// 符号链接按链接本身复制，不复制其指向的内容
+fn copy_recursive(from: &Path, to: &Path) -> io::Result<()> {
+    let metadata = std::fs::symlink_metadata(from)?;
+    if metadata.file_type().is_symlink() {
+        return copy_symlink(from, to);
+    }
+    if !metadata.is_dir() {
+        return std::fs::copy(from, to).map(|_| ());
+    }
+
+    std::fs::create_dir(to)?;
+    for entry in std::fs::read_dir(from)? {
+        let entry = entry?;
+        copy_recursive(&entry.path(), &to.join(entry.file_name()))?;
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn copy_symlink(from: &Path, to: &Path) -> io::Result<()> {
+    std::os::unix::fs::symlink(std::fs::read_link(from)?, to)
+}
+
+#[cfg(windows)]
+fn copy_symlink(from: &Path, to: &Path) -> io::Result<()> {
+    use std::os::windows::fs::FileTypeExt;
+    let link = std::fs::read_link(from)?;
+    if std::fs::symlink_metadata(from)?.file_type().is_symlink_dir() {
+        std::os::windows::fs::symlink_dir(link, to)
+    } else {
+        std::os::windows::fs::symlink_file(link, to)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new() -> Self {
+            let dir = std::env::temp_dir().join(format!("fileops-{}", uuid::Uuid::new_v4()));
+            std::fs::create_dir_all(&dir).unwrap();
+            TempDir(dir)
+        }
+
+        fn file(&self, name: &str, content: &str) -> PathBuf {
+            let path = self.0.join(name);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(&path, content).unwrap();
+            path
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn read(path: &Path) -> String {
+        std::fs::read_to_string(path).unwrap()
+    }
+
+    fn names(dir: &Path) -> Vec<String> {
+        let mut names: Vec<String> = std::fs::read_dir(dir)
+            .unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().to_string())
+            .collect();
+        names.sort();
+        names
+    }
+
+    fn rename(from: &Path, to: &str, policy: ConflictPolicy) -> Result<MovedPath, String> {
+        let staging = TempDir::new();
+        rename_path(&from.to_string_lossy(), to, policy, &staging.0)
+    }
+
+    fn move_into(from: &Path, dest: &Path, policy: ConflictPolicy, staging: &Path) -> MoveReport {
+        let paths = [from.to_string_lossy().to_string()];
+        move_paths(&paths, &dest.to_string_lossy(), policy, staging).unwrap()
+    }
+
+    #[test]
+    fn fail_keeps_both() {
+        let dir = TempDir::new();
+        let a = dir.file("a.txt", "a");
+        let b = dir.file("b.txt", "b");
+        assert!(rename(&a, "b.txt", ConflictPolicy::Fail).is_err());
+        assert_eq!(read(&a), "a");
+        assert_eq!(read(&b), "b");
+    }
+
+    #[test]
+    fn skip_keeps_both() {
+        let dir = TempDir::new();
+        let a = dir.file("a.txt", "a");
+        let dest = dir.0.join("dest");
+        let existing = dir.file("dest/a.txt", "old");
+        let report = move_into(&a, &dest, ConflictPolicy::Skip, &dir.0);
+        assert_eq!(report.skipped.len(), 1);
+        assert_eq!(read(&a), "a");
+        assert_eq!(read(&existing), "old");
+    }
+
+    #[test]
+    fn rename_picks_free_name() {
+        let dir = TempDir::new();
+        let a = dir.file("a.txt", "a");
+        dir.file("b.txt", "b");
+        let moved = rename(&a, "b.txt", ConflictPolicy::Rename).unwrap();
+        assert!(moved.to.ends_with("b (1).txt"));
+        assert_eq!(read(&dir.0.join("b (1).txt")), "a");
+        assert_eq!(read(&dir.0.join("b.txt")), "b");
+    }
+
+    #[test]
+    fn overwrite_stages_replaced_target() {
+        let dir = TempDir::new();
+        let staging = TempDir::new();
+        let a = dir.file("a.txt", "a");
+        dir.file("b/inner.txt", "old");
+        let moved =
+            rename_path(&a.to_string_lossy(), "b", ConflictPolicy::Overwrite, &staging.0).unwrap();
+        assert_eq!(read(&dir.0.join("b")), "a");
+        assert_eq!(names(&dir.0), vec!["b"]);
+
+        let replaced = moved.replaced.unwrap();
+        assert_eq!(Path::new(&replaced.original_path), dir.0.join("b"));
+        let deletions::DeletedLocation::Staging { path } = replaced.location else {
+            panic!("replaced target was not staged");
+        };
+        assert_eq!(read(&Path::new(&path).join("inner.txt")), "old");
+    }
+
+    #[test]
+    fn overwrite_in_move_stages_replaced_target() {
+        let dir = TempDir::new();
+        let staging = TempDir::new();
+        let a = dir.file("a.txt", "new");
+        let dest = dir.0.join("dest");
+        let existing = dir.file("dest/a.txt", "old");
+        let report = move_into(&a, &dest, ConflictPolicy::Overwrite, &staging.0);
+        assert_eq!(read(&existing), "new");
+        assert!(report.moved[0].replaced.is_some());
+        assert_eq!(names(&staging.0).len(), 1);
+    }
+
+    #[test]
+    fn overwrite_restores_target_when_move_fails() {
+        let dir = TempDir::new();
+        let staging = TempDir::new();
+        let b = dir.file("b.txt", "b");
+        let missing = dir.0.join("missing.txt");
+        assert!(place(&missing, b.clone(), ConflictPolicy::Overwrite, &staging.0).is_err());
+        assert_eq!(read(&b), "b");
+        assert_eq!(names(&dir.0), vec!["b.txt"]);
+    }
+
+    #[test]
+    fn same_path_is_noop_for_every_policy() {
+        for policy in [
+            ConflictPolicy::Fail,
+            ConflictPolicy::Skip,
+            ConflictPolicy::Rename,
+            ConflictPolicy::Overwrite,
+        ] {
+            let dir = TempDir::new();
+            let staging = TempDir::new();
+            let a = dir.file("a.txt", "a");
+            let moved = rename(&a, "a.txt", policy).unwrap();
+            assert_eq!(moved.from, moved.to);
+            assert_eq!(read(&a), "a");
+
+            let report = move_into(&a, &dir.0, policy, &staging.0);
+            assert_eq!(report.moved.len(), 1);
+            assert!(report.failed.is_empty());
+            assert_eq!(read(&a), "a");
+            assert_eq!(names(&dir.0), vec!["a.txt"]);
+            assert!(names(&staging.0).is_empty());
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn copy_keeps_symlinks_as_links() {
+        let dir = TempDir::new();
+        let src = dir.0.join("src");
+        dir.file("src/file.txt", "x");
+        std::os::unix::fs::symlink("/", src.join("root")).unwrap();
+        let copy = dir.0.join("copy");
+        copy_recursive(&src, &copy).unwrap();
+        let link = copy.join("root");
+        assert!(std::fs::symlink_metadata(&link).unwrap().file_type().is_symlink());
+        assert_eq!(std::fs::read_link(&link).unwrap(), Path::new("/"));
+        assert_eq!(read(&copy.join("file.txt")), "x");
+    }
+}
@@ -0,0 +1,51 @@
+use crate::scan::HistoryItem;
+use crate::session::write_atomic;
+use crate::writer::OrderedWriter;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+// 保存在设置文件旁 history.json 中的历史记录和基线，重启后恢复
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SavedHistory {
+    pub history: Vec<HistoryItem>,
+    // 规范路径键 -> 基线快照
+    pub baselines: HashMap<String, HistoryItem>,
+}
+
+pub fn history_path(settings_path: &Path) -> PathBuf {
+    settings_path.with_file_name("history.json")
+}
+
+// 历史记录可能很大，在后台按提交顺序写入
+pub struct HistoryFile {
+    path: PathBuf,
+    writer: Arc<OrderedWriter>,
+}
+
+impl HistoryFile {
+    pub fn new(settings_path: &Path) -> Self {
+        HistoryFile {
+            path: history_path(settings_path),
+            writer: Arc::default(),
+        }
+    }
+
+    pub fn load(&self) -> SavedHistory {
+        std::fs::read(&self.path)
+            .ok()
+            .and_then(|json| serde_json::from_slice(&json).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, saved: SavedHistory) {
+        let path = self.path.clone();
+        self.writer.spawn(move || {
+            if let Ok(json) = serde_json::to_vec(&saved) {
+                let _ = write_atomic(&path, &json);
+            }
+        });
+    }
+}
@@ -0,0 +1,100 @@
+// 单实例：首个实例在本机回环地址上监听，端口和随机令牌写入当前用户的配置目录；
+// 后续启动的进程带上令牌把 `--scan` 路径转交给它并直接退出。
+// 其他用户读不到令牌，既不能冒充实例也不能向它注入扫描请求
+use crate::{commands, tray};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{Ipv4Addr, SocketAddr, TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tauri::AppHandle;
+
+// 握手应答，避免端口文件过期后误连到其他程序
+const HANDSHAKE: &str = "search-tool";
+const CONNECT_TIMEOUT: Duration = Duration::from_millis(500);
+// 单行请求的长度上限
+const MAX_LINE: u64 = 64 * 1024;
+
+fn instance_file(config_dir: &Path) -> PathBuf {
+    config_dir.join("instance")
+}
+
+// 文件内容为 "<端口> <令牌>"
+fn read_instance(config_dir: &Path) -> Option<(u16, String)> {
+    let content = std::fs::read_to_string(instance_file(config_dir)).ok()?;
+    let (port, token) = content.trim().split_once(' ')?;
+    Some((port.parse().ok()?, token.to_string()))
+}
+
+// 尝试把启动参数转交给已运行的实例，成功时返回 true
+pub fn forward(config_dir: Option<&Path>, scan_path: Option<&Path>) -> bool {
+    let Some((port, token)) = config_dir.and_then(read_instance) else {
+        return false;
+    };
+
+    let addr = SocketAddr::from((Ipv4Addr::LOCALHOST, port));
+    let Ok(mut stream) = TcpStream::connect_timeout(&addr, CONNECT_TIMEOUT) else {
+        return false;
+    };
+    let _ = stream.set_read_timeout(Some(CONNECT_TIMEOUT));
+
+    // 每个连接发送两行：令牌和要扫描的路径，空路径表示只显示窗口；
+    // 相对路径按当前进程的工作目录解析后再转交
+    let line = scan_path
+        .map(|p| std::path::absolute(p).unwrap_or_else(|_| p.to_path_buf()))
+        .map(|p| p.to_string_lossy().to_string())
+        .unwrap_or_default();
+    if write!(stream, "{}\n{}\n", token, line).is_err() {
+        return false;
+    }
+
+    let mut reply = String::new();
+    BufReader::new(stream).read_line(&mut reply).is_ok() && reply.trim() == HANDSHAKE
+}
+
+// 作为首个实例开始监听，返回监听器和本次的令牌；
+// 失败时返回空（程序照常运行，只是不做单实例限制）
+pub fn bind(config_dir: Option<&Path>) -> Option<(TcpListener, String)> {
+    let config_dir = config_dir?;
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).ok()?;
+    let port = listener.local_addr().ok()?.port();
+    let token = uuid::Uuid::new_v4().simple().to_string();
+    write_instance(config_dir, &format!("{} {}", port, token)).ok()?;
+    Some((listener, token))
+}
+
+// 重新创建文件，Unix 上只允许当前用户读写
+fn write_instance(config_dir: &Path, content: &str) -> std::io::Result<()> {
+    std::fs::create_dir_all(config_dir)?;
+    let path = instance_file(config_dir);
+    let _ = std::fs::remove_file(&path);
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    options.open(path)?.write_all(content.as_bytes())
+}
+
+pub fn serve(listener: TcpListener, token: String, app: AppHandle) {
+    std::thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            // 不发送数据的连接不能一直占住监听线程
+            let _ = stream.set_read_timeout(Some(CONNECT_TIMEOUT));
+            let mut reader = BufReader::new(stream.take(MAX_LINE));
+            let mut presented = String::new();
+            let mut line = String::new();
+            if reader.read_line(&mut presented).is_err() || presented.trim_end() != token {
+                continue;
+            }
+            if reader.read_line(&mut line).is_err() {
+                continue;
+            }
+            let _ = writeln!(reader.get_mut().get_mut(), "{}", HANDSHAKE);
+
+            let path = line.trim_end_matches(['\r', '\n']);
+            if !path.is_empty() {
+                commands::queue_scan_paths(&app, vec![PathBuf::from(path)]);
+            }
+            tray::show_window(&app);
+        }
+    });
+}
@@ -0,0 +1,219 @@
+use crate::scan::{ScanControl, ScanOptions, ScanSnapshot};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+// 已结束的任务最多保留的数量
+const MAX_FINISHED_JOBS: usize = 100;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JobPriority {
+    Low,
+    #[default]
+    Normal,
+    High,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JobStatus {
+    Queued,
+    Running,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+impl JobStatus {
+    pub fn is_finished(self) -> bool {
+        matches!(self, JobStatus::Completed | JobStatus::Failed | JobStatus::Cancelled)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Job {
+    pub id: String,
+    pub path: String,
+    pub priority: JobPriority,
+    pub status: JobStatus,
+    #[serde(with = "chrono::serde::ts_seconds")]
+    pub queued_at: DateTime<Utc>,
+    #[serde(default, with = "chrono::serde::ts_seconds_option")]
+    pub started_at: Option<DateTime<Utc>>,
+    #[serde(default, with = "chrono::serde::ts_seconds_option")]
+    pub finished_at: Option<DateTime<Utc>>,
+    // 运行中的进度：已发现的文件数和已读取的目录数
+    pub files_scanned: usize,
+    pub dirs_scanned: usize,
+    // 完成后结果存储中的 ID
+    pub result_id: Option<String>,
+    pub error: Option<String>,
+}
+
+// 运行中任务的阶段性结果
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JobSnapshot {
+    pub job_id: String,
+    #[serde(flatten)]
+    pub snapshot: ScanSnapshot,
+}
+
+// 调度器交给执行方的任务
+pub struct StartedJob {
+    pub job: Job,
+    pub options: ScanOptions,
+    pub control: Arc<ScanControl>,
+}
+
+// 扫描任务队列：按优先级调度并限制同时运行的数量，本身不负责执行
+pub struct JobQueue {
+    // 按入队顺序排列
+    jobs: Vec<Job>,
+    // 排队中任务的扫描选项
+    pending: HashMap<String, ScanOptions>,
+    // 运行中任务的控制句柄
+    running: HashMap<String, Arc<ScanControl>>,
+    max_parallel: usize,
+}
+
+impl JobQueue {
+    pub fn new(max_parallel: usize) -> Self {
+        JobQueue {
+            jobs: Vec::new(),
+            pending: HashMap::new(),
+            running: HashMap::new(),
+            max_parallel: max_parallel.max(1),
+        }
+    }
+
+    pub fn set_max_parallel(&mut self, max_parallel: usize) {
+        self.max_parallel = max_parallel.max(1);
+    }
+
+    pub fn enqueue(&mut self, path: String, priority: JobPriority, options: ScanOptions) -> Job {
+        let job = Job {
+            id: uuid::Uuid::new_v4().to_string(),
+            path,
+            priority,
+            status: JobStatus::Queued,
+            queued_at: Utc::now(),
+            started_at: None,
+            finished_at: None,
+            files_scanned: 0,
+            dirs_scanned: 0,
+            result_id: None,
+            error: None,
+        };
+        self.pending.insert(job.id.clone(), options);
+        self.jobs.push(job.clone());
+        job
+    }
+
+    // 在并行上限内启动排队的任务，优先级高的先启动，同优先级按入队顺序
+    pub fn start_next(&mut self) -> Vec<StartedJob> {
+        let mut started = Vec::new();
+
+        while self.running.len() < self.max_parallel {
+            let next = self
+                .jobs
+                .iter_mut()
+                .filter(|job| job.status == JobStatus::Queued)
+                .rev()
+                .max_by_key(|job| job.priority);
+            let Some(job) = next else { break };
+
+            job.status = JobStatus::Running;
+            job.started_at = Some(Utc::now());
+            let control = Arc::new(ScanControl::default());
+            self.running.insert(job.id.clone(), control.clone());
+            started.push(StartedJob {
+                options: self.pending.remove(&job.id).unwrap_or_default(),
+                job: job.clone(),
+                control,
+            });
+        }
+
+        started
+    }
+
+    // 把运行中任务的进度同步到任务信息
+    pub fn update_progress(&mut self, id: &str) -> Option<Job> {
+        let control = self.running.get(id)?;
+        let (files, dirs) = control.progress();
+        let job = self.jobs.iter_mut().find(|job| job.id == id)?;
+        job.files_scanned = files;
+        job.dirs_scanned = dirs;
+        Some(job.clone())
+    }
+
+    // 记录运行结果：成功时为结果 ID，失败时为错误信息
+    pub fn finish(&mut self, id: &str, outcome: Result<String, String>) -> Option<Job> {
+        let control = self.running.remove(id)?;
+        let (files, dirs) = control.progress();
+        let job = self.jobs.iter_mut().find(|job| job.id == id)?;
+
+        job.finished_at = Some(Utc::now());
+        job.files_scanned = files;
+        job.dirs_scanned = dirs;
+        match outcome {
+            Ok(result_id) => {
+                job.status = JobStatus::Completed;
+                job.result_id = Some(result_id);
+            }
+            Err(_) if control.is_cancelled() => job.status = JobStatus::Cancelled,
+            Err(error) => {
+                job.status = JobStatus::Failed;
+                job.error = Some(error);
+            }
+        }
+
+        let job = job.clone();
+        self.prune();
+        Some(job)
+    }
+
+    // 排队中的任务立即取消；运行中的任务发出取消请求，由执行方在扫描停止后调用 finish
+    pub fn cancel(&mut self, id: &str) -> Result<Job, String> {
+        let job = self
+            .jobs
+            .iter_mut()
+            .find(|job| job.id == id)
+            .ok_or_else(|| "任务不存在".to_string())?;
+
+        match job.status {
+            JobStatus::Queued => {
+                job.status = JobStatus::Cancelled;
+                job.finished_at = Some(Utc::now());
+                self.pending.remove(id);
+            }
+            JobStatus::Running => {
+                if let Some(control) = self.running.get(id) {
+                    control.cancel();
+                }
+            }
+            _ => return Err("任务已结束".to_string()),
+        }
+
+        Ok(job.clone())
+    }
+
+    pub fn list(&self) -> Vec<Job> {
+        self.jobs.clone()
+    }
+
+    fn prune(&mut self) {
+        let finished = self.jobs.iter().filter(|job| job.status.is_finished()).count();
+        let mut excess = finished.saturating_sub(MAX_FINISHED_JOBS);
+        self.jobs.retain(|job| {
+            if excess > 0 && job.status.is_finished() {
+                excess -= 1;
+                return false;
+            }
+            true
+        });
+    }
+}
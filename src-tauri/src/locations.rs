@@ -0,0 +1,205 @@
+use crate::scan::{format_size, SizeUnit};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum LocationKind {
+    Temp,
+    Downloads,
+    Cache,
+    Browser,
+    PackageManager,
+}
+
+// 常见的临时文件、缓存和下载目录
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KnownLocation {
+    pub name: String,
+    pub kind: LocationKind,
+    pub path: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LocationUsage {
+    #[serde(flatten)]
+    pub location: KnownLocation,
+    pub size: i64,
+    pub size_formatted: String,
+    pub files: usize,
+    // 位于列表中另一个位置之内，不计入总大小
+    pub nested: bool,
+    // 完整结果在结果存储中的 ID，可据此进入该目录
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub result_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl LocationUsage {
+    pub fn new(location: KnownLocation, size: i64, files: usize, unit: SizeUnit) -> Self {
+        LocationUsage {
+            location,
+            size,
+            size_formatted: format_size(size, unit),
+            files,
+            nested: false,
+            result_id: None,
+            error: None,
+        }
+    }
+
+    pub fn failed(location: KnownLocation, error: String) -> Self {
+        LocationUsage {
+            error: Some(error),
+            ..LocationUsage::new(location, 0, 0, SizeUnit::default())
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LocationsOverview {
+    pub locations: Vec<LocationUsage>,
+    // 不含嵌套位置，避免重复统计
+    pub total: i64,
+    pub total_formatted: String,
+    pub scan_time: f64,
+}
+
+impl LocationsOverview {
+    pub fn new(mut locations: Vec<LocationUsage>, unit: SizeUnit, scan_time: f64) -> Self {
+        let paths: Vec<PathBuf> = locations
+            .iter()
+            .map(|usage| PathBuf::from(&usage.location.path))
+            .collect();
+        for (usage, path) in locations.iter_mut().zip(&paths) {
+            usage.nested = paths.iter().any(|other| other != path && path.starts_with(other));
+        }
+        // 大的在前
+        locations.sort_by_key(|usage| std::cmp::Reverse(usage.size));
+
+        let total = locations
+            .iter()
+            .filter(|usage| !usage.nested)
+            .map(|usage| usage.size)
+            .sum();
+        LocationsOverview {
+            locations,
+            total,
+            total_formatted: format_size(total, unit),
+            scan_time,
+        }
+    }
+}
+
+// 当前系统上存在的已知位置，同一路径只保留第一次出现的名称
+pub fn known_locations() -> Vec<KnownLocation> {
+    let mut locations: Vec<KnownLocation> = Vec::new();
+    for (name, kind, path) in candidates() {
+        let Some(path) = path else {
+            continue;
+        };
+        let path = path.to_string_lossy().to_string();
+        if Path::new(&path).is_dir() && !locations.iter().any(|l| l.path == path) {
+            locations.push(KnownLocation {
+                name: name.to_string(),
+                kind,
+                path,
+            });
+        }
+    }
+    locations
+}
+
+fn env_dir(name: &str) -> Option<PathBuf> {
+    std::env::var_os(name)
+        .filter(|value| !value.is_empty())
+        .map(PathBuf::from)
+}
+
+fn under(base: &Option<PathBuf>, rel: &str) -> Option<PathBuf> {
+    base.as_ref().map(|base| base.join(rel))
+}
+
+type Candidate = (&'static str, LocationKind, Option<PathBuf>);
+
+#[cfg(windows)]
+fn candidates() -> Vec<Candidate> {
+    use LocationKind::*;
+
+    let local = env_dir("LOCALAPPDATA");
+    let roaming = env_dir("APPDATA");
+    let profile = env_dir("USERPROFILE");
+    vec![
+        ("临时文件", Temp, env_dir("TEMP")),
+        ("系统临时文件", Temp, under(&env_dir("SystemRoot"), "Temp")),
+        ("下载", Downloads, under(&profile, "Downloads")),
+        ("Internet 缓存", Cache, under(&local, "Microsoft\\Windows\\INetCache")),
+        ("崩溃转储", Cache, under(&local, "CrashDumps")),
+        ("Chrome", Browser, under(&local, "Google\\Chrome\\User Data\\Default\\Cache")),
+        ("Edge", Browser, under(&local, "Microsoft\\Edge\\User Data\\Default\\Cache")),
+        ("Firefox", Browser, under(&local, "Mozilla\\Firefox\\Profiles")),
+        ("npm", PackageManager, under(&local, "npm-cache")),
+        ("npm", PackageManager, under(&roaming, "npm-cache")),
+        ("Yarn", PackageManager, under(&local, "Yarn\\Cache")),
+        ("pip", PackageManager, under(&local, "pip\\Cache")),
+        ("NuGet", PackageManager, under(&profile, ".nuget\\packages")),
+        ("Cargo", PackageManager, under(&profile, ".cargo\\registry")),
+        ("Gradle", PackageManager, under(&profile, ".gradle\\caches")),
+        ("Maven", PackageManager, under(&profile, ".m2\\repository")),
+    ]
+}
+
+#[cfg(target_os = "macos")]
+fn candidates() -> Vec<Candidate> {
+    use LocationKind::*;
+
+    let home = env_dir("HOME");
+    vec![
+        ("临时文件", Temp, env_dir("TMPDIR")),
+        ("下载", Downloads, under(&home, "Downloads")),
+        ("应用缓存", Cache, under(&home, "Library/Caches")),
+        ("日志", Cache, under(&home, "Library/Logs")),
+        ("Chrome", Browser, under(&home, "Library/Caches/Google/Chrome")),
+        ("Safari", Browser, under(&home, "Library/Caches/com.apple.Safari")),
+        ("Firefox", Browser, under(&home, "Library/Caches/Firefox")),
+        ("Homebrew", PackageManager, under(&home, "Library/Caches/Homebrew")),
+        ("npm", PackageManager, under(&home, ".npm")),
+        ("pip", PackageManager, under(&home, "Library/Caches/pip")),
+        ("Cargo", PackageManager, under(&home, ".cargo/registry")),
+        ("Gradle", PackageManager, under(&home, ".gradle/caches")),
+        ("Maven", PackageManager, under(&home, ".m2/repository")),
+    ]
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn candidates() -> Vec<Candidate> {
+    use LocationKind::*;
+
+    let home = env_dir("HOME");
+    let cache = env_dir("XDG_CACHE_HOME").or_else(|| under(&home, ".cache"));
+    vec![
+        ("临时文件", Temp, Some(PathBuf::from("/tmp"))),
+        ("系统临时文件", Temp, Some(PathBuf::from("/var/tmp"))),
+        ("下载", Downloads, under(&home, "Downloads")),
+        ("应用缓存", Cache, cache.clone()),
+        ("Chrome", Browser, under(&cache, "google-chrome")),
+        ("Chromium", Browser, under(&cache, "chromium")),
+        ("Firefox", Browser, under(&cache, "mozilla")),
+        ("apt", PackageManager, Some(PathBuf::from("/var/cache/apt/archives"))),
+        ("npm", PackageManager, under(&home, ".npm")),
+        ("Yarn", PackageManager, under(&cache, "yarn")),
+        ("pip", PackageManager, under(&cache, "pip")),
+        ("Cargo", PackageManager, under(&home, ".cargo/registry")),
+        ("Gradle", PackageManager, under(&home, ".gradle/caches")),
+        ("Maven", PackageManager, under(&home, ".m2/repository")),
+    ]
+}
+
+#[cfg(not(any(unix, windows)))]
+fn candidates() -> Vec<Candidate> {
+    Vec::new()
+}
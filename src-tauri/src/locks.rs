@@ -0,0 +1,138 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LockingProcess {
+    pub pid: u32,
+    pub name: String,
+    // Windows 服务短名，非服务进程或其他平台为空
+    pub service: Option<String>,
+}
+
+pub fn who_locks(path: &str) -> Result<Vec<LockingProcess>, String> {
+    #[cfg(target_os = "windows")]
+    {
+        restart_manager::who_locks(path)
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    {
+        lsof_who_locks(path)
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod restart_manager {
+    use super::LockingProcess;
+    use windows_sys::Win32::Foundation::{ERROR_MORE_DATA, ERROR_SUCCESS};
+    use windows_sys::Win32::System::RestartManager::{
+        RmEndSession, RmGetList, RmRegisterResources, RmStartSession, CCH_RM_SESSION_KEY,
+        RM_PROCESS_INFO,
+    };
+
+    fn from_wide(buf: &[u16]) -> String {
+        let len = buf.iter().position(|&c| c == 0).unwrap_or(buf.len());
+        String::from_utf16_lossy(&buf[..len])
+    }
+
+    pub fn who_locks(path: &str) -> Result<Vec<LockingProcess>, String> {
+        let wide_path: Vec<u16> = path.encode_utf16().chain(std::iter::once(0)).collect();
+        let mut session = 0u32;
+        let mut session_key = [0u16; CCH_RM_SESSION_KEY as usize + 1];
+
+        // SAFETY: 所有缓冲区在调用期间有效，会话在返回前结束
+        unsafe {
+            let code = RmStartSession(&mut session, 0, session_key.as_mut_ptr());
+            if code != ERROR_SUCCESS {
+                return Err(format!("无法启动 Restart Manager 会话: {}", code));
+            }
+
+            let result = (|| {
+                let files = [wide_path.as_ptr()];
+                let code = RmRegisterResources(
+                    session,
+                    1,
+                    files.as_ptr(),
+                    0,
+                    std::ptr::null(),
+                    0,
+                    std::ptr::null(),
+                );
+                if code != ERROR_SUCCESS {
+                    return Err(format!("无法注册文件: {}", code));
+                }
+
+                let mut infos: Vec<RM_PROCESS_INFO> = Vec::new();
+                loop {
+                    let mut needed = 0u32;
+                    let mut count = infos.len() as u32;
+                    let mut reasons = 0u32;
+                    let code = RmGetList(
+                        session,
+                        &mut needed,
+                        &mut count,
+                        infos.as_mut_ptr(),
+                        &mut reasons,
+                    );
+                    if code == ERROR_MORE_DATA {
+                        // 进程列表在两次调用之间可能变化，按需要的数量重新分配
+                        infos = vec![RM_PROCESS_INFO::default(); needed as usize];
+                        continue;
+                    }
+                    if code != ERROR_SUCCESS {
+                        return Err(format!("无法获取占用进程: {}", code));
+                    }
+                    infos.truncate(count as usize);
+                    break;
+                }
+
+                Ok(infos
+                    .iter()
+                    .map(|info| {
+                        let service = from_wide(&info.strServiceShortName);
+                        LockingProcess {
+                            pid: info.Process.dwProcessId,
+                            name: from_wide(&info.strAppName),
+                            service: (!service.is_empty()).then_some(service),
+                        }
+                    })
+                    .collect())
+            })();
+
+            RmEndSession(session);
+            result
+        }
+    }
+}
+
+// 其他平台通过 lsof 查询占用文件的进程
+#[cfg(not(target_os = "windows"))]
+fn lsof_who_locks(path: &str) -> Result<Vec<LockingProcess>, String> {
+    use std::process::Command;
+
+    let output = Command::new("lsof")
+        .args(["-F", "pc", "--", path])
+        .output()
+        .map_err(|e| format!("无法执行 lsof: {}", e))?;
+
+    // lsof 在没有进程占用时以状态码 1 退出且没有输出
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let mut processes: Vec<LockingProcess> = Vec::new();
+    for line in stdout.lines() {
+        if let Some(pid) = line.strip_prefix('p') {
+            if let Ok(pid) = pid.parse() {
+                processes.push(LockingProcess {
+                    pid,
+                    name: String::new(),
+                    service: None,
+                });
+            }
+        } else if let Some(name) = line.strip_prefix('c') {
+            if let Some(process) = processes.last_mut() {
+                process.name = name.to_string();
+            }
+        }
+    }
+
+    Ok(processes)
+}
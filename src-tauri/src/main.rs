@@ -3,29 +3,191 @@
     windows_subsystem = "windows"
 )]
 
-use std::sync::Mutex;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use tauri::{FileDropEvent, Manager, WindowEvent};
 
+mod archive;
+mod audit;
+mod cleanup;
 mod commands;
+mod deletions;
+mod diff;
+mod estimate;
+mod fileops;
+mod filter;
+mod history;
+mod instance;
+mod jobs;
+mod locations;
+mod locks;
+mod paths;
+mod priority;
+mod recycle;
+mod report;
 mod scan;
+mod session;
+mod settings;
+mod shell;
+mod store;
+mod system;
+mod tray;
+mod treemap;
+mod volumes;
+mod writer;
 
 struct AppState {
     history: Mutex<Vec<scan::HistoryItem>>,
+    // 历史记录和基线保存在设置文件旁的 history.json，无法确定配置目录时为空
+    history_file: Option<Arc<history::HistoryFile>>,
+    // 规范路径键 -> 基线快照
+    baselines: Mutex<HashMap<String, scan::HistoryItem>>,
+    results: Mutex<store::ResultStore>,
+    jobs: Mutex<jobs::JobQueue>,
+    // 后台轮询得到的卷剩余空间
+    volumes: Mutex<Vec<volumes::Volume>>,
+    settings: Mutex<settings::Settings>,
+    // 应用内删除的记录，用于撤销
+    deletions: Mutex<deletions::DeletionJournal>,
+    // 界面状态，保存在设置文件旁的 session.json
+    session: Mutex<session::Session>,
+    // session.json 和 last-result.json 按提交顺序写入
+    session_writer: Arc<writer::OrderedWriter>,
+    // 设置文件位置，无法确定配置目录时为空（设置只在本次运行有效）
+    settings_path: Option<PathBuf>,
+}
+
+// 解析 `--scan <path>` 启动参数，右键菜单通过它传入要扫描的文件夹
+fn startup_scan_path() -> Option<PathBuf> {
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == "--scan" {
+            return args.next().map(|arg| shell::scan_arg(&arg));
+        }
+    }
+    None
 }
 
 #[tokio::main]
 async fn main() {
+    let startup_scan = startup_scan_path();
+
+    let context = tauri::generate_context!();
+    let config_dir = tauri::api::path::app_config_dir(context.config());
+
+    // 已有实例在运行时把路径交给它处理，避免出现两份独立的历史和缓存
+    if instance::forward(config_dir.as_deref(), startup_scan.as_deref()) {
+        return;
+    }
+    let listener = instance::bind(config_dir.as_deref());
+
+    let settings_path = config_dir.map(|dir| dir.join("settings.json"));
+    let settings = settings_path
+        .as_deref()
+        .map(settings::Settings::load)
+        .unwrap_or_default();
+    scan::set_cache_limits(settings.cache_max_entries, settings.cache_max_size_mb);
+    scan::set_prewarm(settings.prewarm_subdirectories);
+    let deletions = settings_path
+        .as_deref()
+        .map(|path| deletions::DeletionJournal::load(&deletions::journal_path(path)))
+        .unwrap_or_default();
+    let session = settings_path
+        .as_deref()
+        .map(session::Session::load)
+        .unwrap_or_default();
+    let history_file = settings_path
+        .as_deref()
+        .map(|path| Arc::new(history::HistoryFile::new(path)));
+    let saved = history_file
+        .as_ref()
+        .map(|file| file.load())
+        .unwrap_or_default();
+
     tauri::Builder::default()
         .manage(AppState {
-            history: Mutex::new(Vec::new()),
+            history: Mutex::new(saved.history),
+            history_file,
+            baselines: Mutex::new(saved.baselines),
+            results: Mutex::new(store::ResultStore::new(20)),
+            jobs: Mutex::new(jobs::JobQueue::new(settings.max_parallel_scans)),
+            volumes: Mutex::new(Vec::new()),
+            settings: Mutex::new(settings),
+            deletions: Mutex::new(deletions),
+            session: Mutex::new(session),
+            session_writer: Arc::default(),
+            settings_path,
+        })
+        .system_tray(tray::build_tray())
+        .on_system_tray_event(tray::handle_tray_event)
+        .setup(move |app| {
+            tray::spawn_volume_poller(app.handle());
+            if let Some((listener, token)) = listener {
+                instance::serve(listener, token, app.handle());
+            }
+            if let Some(path) = startup_scan {
+                commands::queue_scan_paths(&app.handle(), vec![path]);
+            }
+            Ok(())
+        })
+        .on_window_event(|event| {
+            // 拖放文件夹到窗口时直接开始扫描
+            if let WindowEvent::FileDrop(FileDropEvent::Dropped(paths)) = event.event() {
+                let app = event.window().app_handle();
+                commands::queue_scan_paths(&app, paths.clone());
+            }
         })
-        .setup(|_app| Ok(()))
         .invoke_handler(tauri::generate_handler![
             commands::scan_directory,
+            commands::get_result,
+            commands::get_result_page,
+            commands::search_result,
+            commands::filter_result,
+            commands::get_result_treemap,
+            commands::export_report,
+            commands::diff_results,
+            commands::top_by_extension,
+            commands::stale_files,
+            commands::unused_files,
+            commands::find_empty,
+            commands::delete_empty,
+            commands::rename_path,
+            commands::move_paths,
+            commands::delete_paths,
+            commands::list_deleted,
+            commands::restore_deleted,
+            commands::get_trash_usage,
+            commands::empty_trash,
             commands::get_history,
             commands::get_history_item,
+            commands::annotate_history,
+            commands::get_trend,
             commands::clear_history,
+            commands::set_baseline,
+            commands::compare_to_baseline,
+            commands::scan_dropped_paths,
+            commands::queue_scan,
+            commands::list_jobs,
+            commands::cancel_job,
+            commands::scan_last_path,
+            commands::get_volumes,
+            commands::scan_system,
+            commands::scan_known_locations,
+            commands::toggle_window,
+            commands::list_presets,
+            commands::run_preset,
+            commands::get_settings,
+            commands::save_session,
+            commands::restore_session,
+            commands::update_settings,
+            commands::export_state,
+            commands::import_state,
             commands::open_in_explorer,
+            commands::register_shell_integration,
+            commands::unregister_shell_integration,
+            commands::who_locks,
         ])
-        .run(tauri::generate_context!())
+        .run(context)
         .expect("error while running tauri application");
 }
@@ -0,0 +1,77 @@
+use std::path::{Path, MAIN_SEPARATOR};
+
+// 路径写法的统一处理：分隔符、盘符大小写、末尾分隔符、UNC 共享和 \\?\ 前缀。
+// 扫描、缓存键、历史查找和在资源管理器中打开路径都经过这里
+
+// 统一为系统的路径分隔符。Unix 上反斜杠是合法的文件名字符，保持不变
+pub fn native_separators(path: &str) -> String {
+    if cfg!(windows) {
+        path.replace('/', "\\")
+    } else {
+        path.to_string()
+    }
+}
+
+// 统一为正斜杠，用于界面显示和正则匹配。Unix 上保持不变
+pub fn forward_slashes(path: &str) -> String {
+    if cfg!(windows) {
+        path.replace('\\', "/")
+    } else {
+        path.to_string()
+    }
+}
+
+// 资源管理器等工具能识别的写法：系统分隔符，去掉 \\?\ 前缀
+// （\\?\UNC\server\share 还原为 \\server\share），盘符大写，去掉根目录以外的末尾分隔符
+pub fn display(path: &str) -> String {
+    let mut path = native_separators(path.trim());
+    if cfg!(windows) {
+        if let Some(rest) = path.strip_prefix(r"\\?\UNC\") {
+            path = format!(r"\\{}", rest);
+        } else if let Some(rest) = path.strip_prefix(r"\\?\") {
+            path = rest.to_string();
+        }
+        if is_drive(&path) {
+            path[..1].make_ascii_uppercase();
+        }
+    }
+    while path.len() > 1 && path.ends_with(MAIN_SEPARATOR) && !is_drive_root(&path) {
+        path.pop();
+    }
+    path
+}
+
+// 以正斜杠显示的路径，用于返回给界面的报告
+pub fn slash(path: &Path) -> String {
+    forward_slashes(&display(&path.to_string_lossy()))
+}
+
+// 历史记录和缓存使用的键：display 的写法统一为正斜杠，
+// 在大小写不敏感的平台上同时统一为小写，使等价的写法得到相同的键
+pub fn key(path: &str) -> String {
+    let key = forward_slashes(&display(path));
+    if cfg!(any(target_os = "windows", target_os = "macos")) {
+        key.to_lowercase()
+    } else {
+        key
+    }
+}
+
+// 先解析为规范路径再生成键；路径已不存在时（例如已删除的历史目录）退回到原始写法
+pub fn path_key(path: &str) -> String {
+    let path = path.trim();
+    match std::fs::canonicalize(path) {
+        Ok(p) => key(&p.to_string_lossy()),
+        Err(_) => key(path),
+    }
+}
+
+// C: 或 C:\ 开头
+fn is_drive(path: &str) -> bool {
+    let bytes = path.as_bytes();
+    bytes.len() >= 2 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':'
+}
+
+fn is_drive_root(path: &str) -> bool {
+    cfg!(windows) && path.len() == 3 && is_drive(path)
+}
@@ -0,0 +1,71 @@
+// 低优先级扫描：在当前线程上降低 IO 优先级，守卫销毁时恢复（阻塞线程池中的线程会被复用）
+pub struct BackgroundIo {
+    #[cfg(target_os = "linux")]
+    previous: Option<libc::c_long>,
+    #[cfg(target_os = "windows")]
+    entered: bool,
+}
+
+#[cfg(target_os = "linux")]
+mod ioprio {
+    pub const WHO_PROCESS: libc::c_int = 1;
+    pub const CLASS_SHIFT: libc::c_int = 13;
+    pub const CLASS_IDLE: libc::c_int = 3;
+}
+
+impl BackgroundIo {
+    pub fn enter() -> Self {
+        #[cfg(target_os = "linux")]
+        {
+            // who 为 0 时作用于调用线程，等价于 ionice -c 3
+            // SAFETY: ioprio_get/ioprio_set 只读写内核中的线程属性
+            let previous = unsafe {
+                let previous = libc::syscall(libc::SYS_ioprio_get, ioprio::WHO_PROCESS, 0);
+                let idle = ioprio::CLASS_IDLE << ioprio::CLASS_SHIFT;
+                let ok = libc::syscall(libc::SYS_ioprio_set, ioprio::WHO_PROCESS, 0, idle) == 0;
+                (ok && previous >= 0).then_some(previous)
+            };
+            BackgroundIo { previous }
+        }
+
+        #[cfg(target_os = "windows")]
+        {
+            use windows_sys::Win32::System::Threading::{
+                GetCurrentThread, SetThreadPriority, THREAD_MODE_BACKGROUND_BEGIN,
+            };
+            // 后台模式同时降低线程的 CPU、IO 和内存优先级
+            // SAFETY: GetCurrentThread 返回的伪句柄始终有效
+            let entered =
+                unsafe { SetThreadPriority(GetCurrentThread(), THREAD_MODE_BACKGROUND_BEGIN) != 0 };
+            BackgroundIo { entered }
+        }
+
+        #[cfg(not(any(target_os = "linux", target_os = "windows")))]
+        {
+            BackgroundIo {}
+        }
+    }
+}
+
+impl Drop for BackgroundIo {
+    fn drop(&mut self) {
+        #[cfg(target_os = "linux")]
+        if let Some(previous) = self.previous {
+            // SAFETY: 同 enter
+            unsafe {
+                libc::syscall(libc::SYS_ioprio_set, ioprio::WHO_PROCESS, 0, previous);
+            }
+        }
+
+        #[cfg(target_os = "windows")]
+        if self.entered {
+            use windows_sys::Win32::System::Threading::{
+                GetCurrentThread, SetThreadPriority, THREAD_MODE_BACKGROUND_END,
+            };
+            // SAFETY: 同 enter
+            unsafe {
+                SetThreadPriority(GetCurrentThread(), THREAD_MODE_BACKGROUND_END);
+            }
+        }
+    }
+}
@@ -0,0 +1,201 @@
+use crate::scan::{format_size, SizeUnit};
+use serde::{Deserialize, Serialize};
+
+// 某个卷上回收站的占用
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrashUsage {
+    pub mount_point: String,
+    pub size: u64,
+    // 回收站中的顶层条目数
+    pub items: u64,
+    pub size_formatted: String,
+}
+
+impl TrashUsage {
+    fn new(mount_point: String, size: u64, items: u64, unit: SizeUnit) -> Self {
+        TrashUsage {
+            mount_point,
+            size,
+            items,
+            size_formatted: format_size(size as i64, unit),
+        }
+    }
+}
+
+// 逐个驱动器查询回收站，空回收站也会列出
+#[cfg(windows)]
+pub fn trash_usage(unit: SizeUnit) -> Vec<TrashUsage> {
+    use windows_sys::Win32::Storage::FileSystem::GetLogicalDrives;
+    use windows_sys::Win32::UI::Shell::{SHQueryRecycleBinW, SHQUERYRBINFO};
+
+    let mask = unsafe { GetLogicalDrives() };
+    let mut usage = Vec::new();
+
+    for i in 0..26u8 {
+        if mask & (1 << i) == 0 {
+            continue;
+        }
+        let root = format!("{}:\\", (b'A' + i) as char);
+        let wide: Vec<u16> = root.encode_utf16().chain(std::iter::once(0)).collect();
+        let mut info = SHQUERYRBINFO {
+            cbSize: std::mem::size_of::<SHQUERYRBINFO>() as u32,
+            i64Size: 0,
+            i64NumItems: 0,
+        };
+        // 光驱、网络驱动器等没有回收站时返回错误
+        if unsafe { SHQueryRecycleBinW(wide.as_ptr(), &mut info) } >= 0 {
+            usage.push(TrashUsage::new(
+                root,
+                info.i64Size.max(0) as u64,
+                info.i64NumItems.max(0) as u64,
+                unit,
+            ));
+        }
+    }
+
+    usage
+}
+
+// 回收站按所在卷汇总，不存在回收站目录的卷不列出
+#[cfg(unix)]
+pub fn trash_usage(unit: SizeUnit) -> Vec<TrashUsage> {
+    let mut usage: Vec<TrashUsage> = Vec::new();
+    for (mount_point, dir) in trash_dirs() {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        let (mut size, mut items) = (0, 0);
+        for entry in entries.flatten() {
+            items += 1;
+            size += tree_size(&entry.path());
+        }
+        match usage.iter_mut().find(|u| u.mount_point == mount_point) {
+            Some(existing) => {
+                existing.size += size;
+                existing.items += items;
+                existing.size_formatted = format_size(existing.size as i64, unit);
+            }
+            None => usage.push(TrashUsage::new(mount_point, size, items, unit)),
+        }
+    }
+    usage
+}
+
+// 不跟随符号链接
+#[cfg(unix)]
+fn tree_size(path: &std::path::Path) -> u64 {
+    let Ok(metadata) = std::fs::symlink_metadata(path) else {
+        return 0;
+    };
+    if !metadata.is_dir() {
+        return metadata.len();
+    }
+    std::fs::read_dir(path)
+        .map(|entries| entries.flatten().map(|entry| tree_size(&entry.path())).sum())
+        .unwrap_or(0)
+}
+
+// (所在卷, 存放被删除文件的目录)，遵循 freedesktop 回收站规范
+#[cfg(target_os = "linux")]
+fn trash_dirs() -> Vec<(String, std::path::PathBuf)> {
+    use std::path::PathBuf;
+
+    let mount_points = crate::volumes::mount_points();
+    let mut dirs = Vec::new();
+
+    let data_home = std::env::var_os("XDG_DATA_HOME").map(PathBuf::from).or_else(|| {
+        std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share"))
+    });
+    if let Some(data_home) = data_home {
+        let home_trash = data_home.join("Trash");
+        // 主目录回收站归入包含它的最长挂载点
+        let mount_point = mount_points
+            .iter()
+            .filter(|mp| home_trash.starts_with(mp.as_str()))
+            .max_by_key(|mp| mp.len())
+            .cloned()
+            .unwrap_or_else(|| "/".to_string());
+        dirs.push((mount_point, home_trash.join("files")));
+    }
+
+    let uid = unsafe { libc::getuid() };
+    for mount_point in mount_points {
+        let root = PathBuf::from(&mount_point);
+        let shared = root.join(".Trash").join(uid.to_string());
+        let private = root.join(format!(".Trash-{uid}"));
+        for trash in [shared, private] {
+            dirs.push((mount_point.clone(), trash.join("files")));
+        }
+    }
+    dirs
+}
+
+#[cfg(all(unix, not(target_os = "linux")))]
+fn trash_dirs() -> Vec<(String, std::path::PathBuf)> {
+    use std::path::PathBuf;
+
+    let mut dirs = Vec::new();
+    if let Some(home) = std::env::var_os("HOME") {
+        dirs.push(("/".to_string(), PathBuf::from(home).join(".Trash")));
+    }
+    let uid = unsafe { libc::getuid() };
+    for mount_point in crate::volumes::mount_points() {
+        if mount_point != "/" {
+            let trash = PathBuf::from(&mount_point).join(".Trashes").join(uid.to_string());
+            dirs.push((mount_point, trash));
+        }
+    }
+    dirs
+}
+
+// 清空所有卷的回收站，不弹出确认
+#[cfg(windows)]
+pub fn empty_trash() -> Result<(), String> {
+    use windows_sys::Win32::UI::Shell::{
+        SHEmptyRecycleBinW, SHERB_NOCONFIRMATION, SHERB_NOPROGRESSUI, SHERB_NOSOUND,
+    };
+
+    let flags = SHERB_NOCONFIRMATION | SHERB_NOPROGRESSUI | SHERB_NOSOUND;
+    let hr = unsafe { SHEmptyRecycleBinW(std::ptr::null_mut(), std::ptr::null(), flags) };
+    // 回收站本来就为空时返回 E_UNEXPECTED
+    if hr >= 0 || hr == 0x8000_FFFFu32 as i32 {
+        Ok(())
+    } else {
+        Err(format!("清空回收站失败: 0x{:08X}", hr as u32))
+    }
+}
+
+#[cfg(target_os = "linux")]
+pub fn empty_trash() -> Result<(), String> {
+    let items = trash::os_limited::list().map_err(|e| e.to_string())?;
+    trash::os_limited::purge_all(items).map_err(|e| e.to_string())
+}
+
+// 逐项删除回收站目录的内容，保留目录本身
+#[cfg(all(unix, not(target_os = "linux")))]
+pub fn empty_trash() -> Result<(), String> {
+    let mut errors = Vec::new();
+    for (_, dir) in trash_dirs() {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let is_dir = std::fs::symlink_metadata(&path).is_ok_and(|m| m.is_dir());
+            let removed = if is_dir {
+                std::fs::remove_dir_all(&path)
+            } else {
+                std::fs::remove_file(&path)
+            };
+            if let Err(e) = removed {
+                errors.push(format!("{}: {}", path.display(), e));
+            }
+        }
+    }
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors.join("; "))
+    }
+}
@@ -0,0 +1,224 @@
+use crate::scan::{format_size, Item, ScanResult, SizeUnit};
+use crate::treemap::{build_treemap, TreemapNode, TreemapOptions};
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::path::Path;
+
+// 报告中列出的最大条目数
+const TOP_ITEMS: usize = 100;
+// 类型分布中单独列出的扩展名数量，其余合并为一行
+const TOP_TYPES: usize = 20;
+const TREEMAP_WIDTH: f64 = 960.0;
+const TREEMAP_HEIGHT: f64 = 540.0;
+
+const STYLE: &str = "body{font-family:system-ui,sans-serif;margin:2em;color:#222}\
+table{border-collapse:collapse;margin-bottom:2em}\
+th,td{padding:4px 12px;border-bottom:1px solid #ddd;text-align:left}\
+td.size{text-align:right;white-space:nowrap}\
+svg text{font-size:11px;fill:#fff;pointer-events:none}";
+
+// 生成不依赖外部资源的 HTML 报告：摘要、最大的条目、类型分布和树图
+pub fn render_html(result: &ScanResult, unit: SizeUnit) -> String {
+    let mut html = String::new();
+    let title = format!("扫描报告 - {}", escape(&result.path));
+
+    let _ = write!(
+        html,
+        "<!DOCTYPE html><html><head><meta charset=\"utf-8\"><title>{}</title><style>{}</style></head><body>",
+        title, STYLE
+    );
+    let _ = write!(html, "<h1>{}</h1>", title);
+    render_summary(&mut html, result, unit);
+    render_treemap(&mut html, result);
+    render_top_items(&mut html, result, unit);
+    render_types(&mut html, result, unit);
+    html.push_str("</body></html>");
+
+    html
+}
+
+fn render_summary(html: &mut String, result: &ScanResult, unit: SizeUnit) {
+    let files = result.items.iter().filter(|item| !item.is_dir).count();
+    let dirs = result.items.len() - files;
+
+    html.push_str("<h2>摘要</h2><table>");
+    let rows = [
+        ("路径", escape(&result.path)),
+        ("总大小", format_size(result.total_size, unit)),
+        ("文件数", files.to_string()),
+        ("目录数", dirs.to_string()),
+        ("扫描耗时", format!("{:.2} 秒", result.scan_time)),
+        (
+            "生成时间",
+            chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+        ),
+    ];
+    for (label, value) in rows {
+        let _ = write!(html, "<tr><th>{}</th><td>{}</td></tr>", label, value);
+    }
+    html.push_str("</table>");
+}
+
+fn render_top_items(html: &mut String, result: &ScanResult, unit: SizeUnit) {
+    let mut items: Vec<&Item> = result.items.iter().collect();
+    items.sort_by_key(|item| std::cmp::Reverse(item.size));
+
+    let _ = write!(
+        html,
+        "<h2>最大的 {} 项</h2><table><tr><th>大小</th><th>类型</th><th>路径</th></tr>",
+        TOP_ITEMS
+    );
+    for item in items.into_iter().take(TOP_ITEMS) {
+        let _ = write!(
+            html,
+            "<tr><td class=\"size\">{}</td><td>{}</td><td>{}</td></tr>",
+            format_size(item.size, unit),
+            if item.is_dir { "目录" } else { "文件" },
+            escape(&item.path)
+        );
+    }
+    html.push_str("</table>");
+}
+
+fn render_types(html: &mut String, result: &ScanResult, unit: SizeUnit) {
+    // 扩展名 -> (总大小, 文件数)
+    let mut by_extension: HashMap<String, (i64, usize)> = HashMap::new();
+    for item in result.items.iter().filter(|item| !item.is_dir) {
+        let extension = Path::new(&item.path)
+            .extension()
+            .map(|e| e.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        let entry = by_extension.entry(extension).or_default();
+        entry.0 += item.size;
+        entry.1 += 1;
+    }
+
+    let mut types: Vec<(String, i64, usize)> = by_extension
+        .into_iter()
+        .map(|(extension, (size, count))| (extension, size, count))
+        .collect();
+    types.sort_by_key(|(_, size, _)| std::cmp::Reverse(*size));
+    if types.len() > TOP_TYPES {
+        let rest = types.split_off(TOP_TYPES);
+        let size = rest.iter().map(|(_, size, _)| size).sum();
+        let count = rest.iter().map(|(_, _, count)| count).sum();
+        types.push((format!("其他 {} 种", rest.len()), size, count));
+    }
+
+    html.push_str("<h2>类型分布</h2><table><tr><th>类型</th><th>大小</th><th>文件数</th></tr>");
+    for (extension, size, count) in types {
+        let label = if extension.is_empty() {
+            "(无扩展名)".to_string()
+        } else {
+            escape(&extension)
+        };
+        let _ = write!(
+            html,
+            "<tr><td>{}</td><td class=\"size\">{}</td><td>{}</td></tr>",
+            label,
+            format_size(size, unit),
+            count
+        );
+    }
+    html.push_str("</table>");
+}
+
+fn render_treemap(html: &mut String, result: &ScanResult) {
+    let options = TreemapOptions {
+        max_depth: 2,
+        ..TreemapOptions::default()
+    };
+    let root = build_treemap(result, &options);
+
+    let _ = write!(
+        html,
+        "<h2>树图</h2><svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" viewBox=\"0 0 {w} {h}\">",
+        w = TREEMAP_WIDTH,
+        h = TREEMAP_HEIGHT
+    );
+    let rect = Rect {
+        x: 0.0,
+        y: 0.0,
+        width: TREEMAP_WIDTH,
+        height: TREEMAP_HEIGHT,
+    };
+    render_nodes(html, &root.children, root.value, rect, 0);
+    html.push_str("</svg>");
+}
+
+#[derive(Clone, Copy)]
+struct Rect {
+    x: f64,
+    y: f64,
+    width: f64,
+    height: f64,
+}
+
+// 按层交替横向和纵向切分（slice-and-dice）
+fn render_nodes(html: &mut String, nodes: &[TreemapNode], total: i64, area: Rect, depth: usize) {
+    if total <= 0 {
+        return;
+    }
+
+    let horizontal = depth.is_multiple_of(2);
+    let mut offset = 0.0;
+    for (i, node) in nodes.iter().enumerate() {
+        let fraction = node.value as f64 / total as f64;
+        let rect = if horizontal {
+            let width = area.width * fraction;
+            let rect = Rect {
+                x: area.x + offset,
+                width,
+                ..area
+            };
+            offset += width;
+            rect
+        } else {
+            let height = area.height * fraction;
+            let rect = Rect {
+                y: area.y + offset,
+                height,
+                ..area
+            };
+            offset += height;
+            rect
+        };
+        if rect.width < 1.0 || rect.height < 1.0 {
+            continue;
+        }
+
+        let hue = (i * 47 + depth * 90) % 360;
+        let _ = write!(
+            html,
+            "<rect x=\"{:.1}\" y=\"{:.1}\" width=\"{:.1}\" height=\"{:.1}\" fill=\"hsl({},55%,{}%)\" stroke=\"#fff\"><title>{}</title></rect>",
+            rect.x,
+            rect.y,
+            rect.width,
+            rect.height,
+            hue,
+            45 + depth * 10,
+            escape(&node.name)
+        );
+        if node.children.is_empty() {
+            if rect.width > 60.0 && rect.height > 16.0 {
+                let _ = write!(
+                    html,
+                    "<text x=\"{:.1}\" y=\"{:.1}\">{}</text>",
+                    rect.x + 4.0,
+                    rect.y + 13.0,
+                    escape(&node.name)
+                );
+            }
+        } else {
+            render_nodes(html, &node.children, node.value, rect, depth + 1);
+        }
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
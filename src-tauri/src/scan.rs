@@ -1,19 +1,229 @@
-use anyhow;
 use dashmap::DashMap;
 use rayon::prelude::*;
+use regex::RegexSet;
+use crate::audit::{AuditCollector, AuditReport};
+use crate::estimate::{SizeEstimate, Sampling};
+use crate::paths::{self, native_separators, path_key};
+use crate::priority::BackgroundIo;
+use crate::treemap::TreemapNode;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
 use tokio::fs;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Item {
+    // 相对于扫描根目录的路径
     pub path: String,
     pub name: String,
+    // 绝对路径，使用系统的路径分隔符；阶段性结果中为空
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub abs_path: String,
+    // 父目录相对于扫描根目录的路径，一级子项为空字符串
+    #[serde(default)]
+    pub parent: String,
+    // 路径含无效的 UTF-8，上面的路径字段经过有损转换，不能直接用于文件操作
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub encoding_lossy: bool,
     pub size: i64,
+    // format=none 时为空并从响应中省略
+    #[serde(default, skip_serializing_if = "String::is_empty")]
     pub size_formatted: String,
     pub is_dir: bool,
+    // 文件的修改时间（Unix 秒），目录为空
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub modified: Option<i64>,
+    // 文件的最后访问时间（Unix 秒），只在 recordAccessTime 时记录
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub accessed: Option<i64>,
+    // 命中的大小阈值规则名称
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub flags: Vec<String>,
+    // 目录下最大的文件，用于在不展开目录时说明大小的主要来源
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub largest_child: Option<LargestChild>,
+    // 快速估算时按抽样推算大小的目录及其置信区间，精确统计的条目为空
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub estimate: Option<SizeEstimate>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LargestChild {
+    // 相对于扫描根目录的路径
+    pub path: String,
+    pub size: i64,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FlagTarget {
+    #[default]
+    Any,
+    File,
+    Dir,
+}
+
+// 大小阈值规则，例如“单个文件超过 1 GB”或“目录超过 50 GB”
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FlagRule {
+    pub name: String,
+    #[serde(default)]
+    pub target: FlagTarget,
+    // 大小超过该字节数时标记
+    pub threshold: i64,
+}
+
+impl FlagRule {
+    pub fn matches(&self, size: i64, is_dir: bool) -> bool {
+        let target = match self.target {
+            FlagTarget::Any => true,
+            FlagTarget::File => !is_dir,
+            FlagTarget::Dir => is_dir,
+        };
+        target && size > self.threshold
+    }
+}
+
+// 返回条目命中的规则名称
+pub fn item_flags(rules: &[FlagRule], size: i64, is_dir: bool) -> Vec<String> {
+    rules
+        .iter()
+        .filter(|rule| rule.matches(size, is_dir))
+        .map(|rule| rule.name.clone())
+        .collect()
+}
+
+// 按完整路径（分隔符统一为 /）匹配的正则
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct PathRegex {
+    // 只统计匹配任一表达式的文件
+    pub include: Vec<String>,
+    // 匹配任一表达式的文件和目录被跳过，目录不再进入
+    pub exclude: Vec<String>,
+}
+
+impl PathRegex {
+    fn compile(&self) -> Result<PathFilter, anyhow::Error> {
+        let build = |patterns: &[String]| -> Result<Option<RegexSet>, anyhow::Error> {
+            if patterns.is_empty() {
+                return Ok(None);
+            }
+            RegexSet::new(patterns)
+                .map(Some)
+                .map_err(|e| anyhow::anyhow!("无效的路径正则: {}", e))
+        };
+        Ok(PathFilter {
+            include: build(&self.include)?,
+            exclude: build(&self.exclude)?,
+        })
+    }
+}
+
+// 扫描开始前编译一次，遍历时复用
+#[derive(Default)]
+struct PathFilter {
+    include: Option<RegexSet>,
+    exclude: Option<RegexSet>,
+}
+
+impl PathFilter {
+    fn excludes(&self, path: &Path) -> bool {
+        self.exclude
+            .as_ref()
+            .is_some_and(|set| set.is_match(&paths::slash(path)))
+    }
+
+    fn includes(&self, path: &Path) -> bool {
+        self.include
+            .as_ref()
+            .is_none_or(|set| set.is_match(&paths::slash(path)))
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ScanOptions {
+    // 只统计超过该天数未修改的文件
+    pub older_than_days: Option<u64>,
+    // 只统计该天数内修改过的文件
+    pub newer_than_days: Option<u64>,
+    // 后台模式：降低 IO 优先级、单线程聚合并限制目录读取速率
+    pub low_priority: bool,
+    // 聚合使用的线程数，不超过 CPU 核心数；为空时使用 SEARCH_TOOL_THREADS 或默认值
+    pub threads: Option<usize>,
+    // 排除的文件和目录：名称，或含路径分隔符的完整路径
+    pub excludes: Option<Vec<String>>,
+    // 是否跟随符号链接，为空时跟随
+    pub follow_symlinks: Option<bool>,
+    // 聚合时标记超过阈值的条目
+    pub flag_rules: Option<Vec<FlagRule>>,
+    // 只统计这些扩展名的文件（不区分大小写，可带前导点）
+    pub include_ext: Option<Vec<String>>,
+    // 不统计这些扩展名的文件
+    pub exclude_ext: Option<Vec<String>>,
+    // 遍历时按路径正则过滤
+    pub path_regex: Option<PathRegex>,
+    // 记录文件的最后访问时间，见 unused_since
+    pub record_access_time: bool,
+    // 检查权限过宽的文件和目录，结果见 ScanResult::audit
+    pub audit: bool,
+    // 快速估算：条目很多的目录只抽样统计，推算出的大小带置信区间
+    pub quick_estimate: bool,
+}
+
+impl ScanOptions {
+    // 带过滤条件的扫描结果只反映部分文件，估算结果也不是精确值，都不能与缓存互相替代
+    pub fn is_unfiltered(&self) -> bool {
+        self.older_than_days.is_none()
+            && self.newer_than_days.is_none()
+            && self.extension_filter().is_empty()
+            && self.path_regex.as_ref().is_none_or(|regex| regex.include.is_empty())
+            && !self.quick_estimate
+    }
+
+    pub fn extension_filter(&self) -> ExtensionFilter {
+        ExtensionFilter::new(self.include_ext.as_deref(), self.exclude_ext.as_deref())
+    }
+
+    // 影响遍历范围和结果内容的选项，缓存条目只在这些选项相同时复用
+    fn cache_variant(&self) -> String {
+        serde_json::to_string(&(
+            &self.excludes,
+            self.follow_symlinks,
+            &self.flag_rules,
+            &self.path_regex,
+            self.record_access_time,
+            self.audit,
+        ))
+        .unwrap_or_default()
+    }
+
+    // 将天数换算为修改时间（Unix 秒）的下界和上界
+    fn modified_range(&self) -> (Option<i64>, Option<i64>) {
+        let now = chrono::Utc::now().timestamp();
+        let cutoff = |days: u64| now - days as i64 * 86400;
+        (
+            self.newer_than_days.map(cutoff),
+            self.older_than_days.map(cutoff),
+        )
+    }
+}
+
+fn in_modified_range(range: (Option<i64>, Option<i64>), modified: Option<i64>) -> bool {
+    match (range, modified) {
+        ((None, None), _) => true,
+        // 设置了时间过滤但无法获取修改时间时排除该文件
+        (_, None) => false,
+        ((min, max), Some(modified)) => {
+            min.is_none_or(|min| modified >= min) && max.is_none_or(|max| modified < max)
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -21,9 +231,84 @@ pub struct Item {
 pub struct ScanResult {
     pub items: Vec<Item>,
     pub total_size: i64,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
     pub total_size_formatted: String,
     pub scan_time: f64,
     pub path: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub treemap: Option<TreemapNode>,
+    // 结果存储中的 ID，后续操作可直接引用
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub result_id: Option<String>,
+    // 本次扫描的性能统计，来自缓存或历史的结果为空
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stats: Option<ScanStats>,
+    // 根目录本身的统计，区分直接位于根目录下的文件和子目录中的文件
+    #[serde(default)]
+    pub root: RootSummary,
+    // 权限审计结果，只在开启 audit 时提供
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub audit: Option<AuditReport>,
+    // 快速估算时总大小的置信区间
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub estimate: Option<SizeEstimate>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RootSummary {
+    // 直接位于根目录下的文件
+    pub direct_files: usize,
+    pub direct_size: i64,
+    // 根目录下的直接子目录
+    pub direct_dirs: usize,
+    // 整个子树中的文件和（非空）目录
+    pub files: usize,
+    pub dirs: usize,
+    pub subtree_size: i64,
+}
+
+impl RootSummary {
+    // items 需要是完整的扫描结果，按层级截断后的结果会少算
+    pub fn from_items(items: &[Item]) -> Self {
+        let mut summary = RootSummary::default();
+        for item in items {
+            let direct = Path::new(&item.path).components().count() == 1;
+            if item.is_dir {
+                summary.dirs += 1;
+                summary.direct_dirs += direct as usize;
+            } else {
+                summary.files += 1;
+                summary.subtree_size += item.size;
+                if direct {
+                    summary.direct_files += 1;
+                    summary.direct_size += item.size;
+                }
+            }
+        }
+        summary
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScanStats {
+    pub files: usize,
+    pub dirs_visited: usize,
+    // 无法读取的目录或元数据数量
+    pub errors: usize,
+    pub files_per_sec: f64,
+    // 各阶段耗时（秒）
+    pub walk_time: f64,
+    pub aggregate_time: f64,
+    pub sort_time: f64,
+    pub wall_time: f64,
+    // 进程峰值内存（字节），平台不支持时为空
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub peak_memory: Option<u64>,
+    // 因目录循环、重复到达或超过深度上限而跳过的目录，也计入 errors
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub skipped_dirs: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -34,18 +319,76 @@ pub struct HistoryItem {
     pub total_size: i64,
     pub size_format: String,
     pub items: Vec<Item>,
+    // 用户附加的备注，如“清理 node_modules 之前”
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+}
+
+impl HistoryItem {
+    // 是否为该路径（规范路径键）在该时间（Unix 秒）的扫描
+    pub fn is_snapshot(&self, key: &str, timestamp: i64) -> bool {
+        self.scan_time.timestamp() == timestamp && path_key(&self.path) == key
+    }
+
+    // 空白备注视为删除备注
+    pub fn set_note(&mut self, note: &str) {
+        let note = note.trim();
+        self.note = (!note.is_empty()).then(|| note.to_string());
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrendPoint {
+    #[serde(with = "chrono::serde::ts_seconds")]
+    pub scan_time: chrono::DateTime<chrono::Utc>,
+    pub total_size: i64,
+    // 顶层子项路径 -> 大小
+    pub children: BTreeMap<String, i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Trend {
+    pub path: String,
+    pub points: Vec<TrendPoint>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExtensionReport {
+    pub extension: String,
+    pub total_size: i64,
+    pub total_size_formatted: String,
+    pub count: usize,
+    pub items: Vec<Item>,
+}
+
+// 扫描进行中的阶段性结果：目前已统计到的顶层子项大小
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScanSnapshot {
+    pub files: usize,
+    pub dirs: usize,
+    pub total_size: i64,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub total_size_formatted: String,
+    pub items: Vec<Item>,
 }
 
 #[derive(Debug, Clone)]
 pub struct CacheEntry {
     result: ScanResult,
     dir_mtime: chrono::DateTime<chrono::Local>,
+    variant: String,
 }
 
 pub struct ScanCache {
     cache: DashMap<String, CacheEntry>,
-    max_entries: usize,
-    max_size_bytes: usize,
+    max_entries: AtomicUsize,
+    max_size_bytes: AtomicUsize,
     current_size: DashMap<String, usize>,
 }
 
@@ -53,23 +396,32 @@ impl ScanCache {
     pub fn new(max_entries: usize, max_size_mb: usize) -> Self {
         ScanCache {
             cache: DashMap::new(),
-            max_entries,
-            max_size_bytes: max_size_mb * 1024 * 1024,
+            max_entries: AtomicUsize::new(max_entries),
+            max_size_bytes: AtomicUsize::new(max_size_mb * 1024 * 1024),
             current_size: DashMap::new(),
         }
     }
 
+    pub fn set_limits(&self, max_entries: usize, max_size_mb: usize) {
+        self.max_entries.store(max_entries, Ordering::Relaxed);
+        self.max_size_bytes
+            .store(max_size_mb * 1024 * 1024, Ordering::Relaxed);
+    }
+
     pub fn get(&self, path: &str) -> Option<CacheEntry> {
         self.cache.get(path).map(|entry| entry.clone())
     }
 
-    pub fn insert(&self, path: String, result: ScanResult) {
+    pub fn insert(&self, path: String, result: ScanResult, variant: String) {
         // 估算当前条目大小
         let entry_size = self.estimate_size(&result);
+        let max_entries = self.max_entries.load(Ordering::Relaxed);
+        let max_size_bytes = self.max_size_bytes.load(Ordering::Relaxed);
 
-        // 检查是否超过最大条目数或总大小限制
-        if self.cache.len() >= self.max_entries
-            || self.get_total_size() + entry_size > self.max_size_bytes
+        // 超过最大条目数或总大小限制时淘汰最旧的条目（限制可能刚被调小）
+        while !self.cache.is_empty()
+            && (self.cache.len() >= max_entries
+                || self.get_total_size() + entry_size > max_size_bytes)
         {
             self.evict_oldest();
         }
@@ -80,6 +432,7 @@ impl ScanCache {
             CacheEntry {
                 result,
                 dir_mtime: chrono::Local::now(),
+                variant,
             },
         );
     }
@@ -95,7 +448,7 @@ impl ScanCache {
 
     fn evict_oldest(&self) {
         let mut entries: Vec<_> = self.cache.iter().collect();
-        entries.sort_by(|a, b| a.value().dir_mtime.cmp(&b.value().dir_mtime));
+        entries.sort_by_key(|entry| entry.value().dir_mtime);
         if let Some(entry) = entries.first() {
             let key = entry.key().clone();
             self.current_size.remove(&key);
@@ -104,10 +457,32 @@ impl ScanCache {
     }
 
     pub fn invalidate(&self, path: &str) {
+        // 根目录的键以 / 结尾（如 c:/），拼接前先去掉
+        let prefix = format!("{}/", path.trim_end_matches('/'));
         let keys_to_remove: Vec<String> = self
             .cache
             .iter()
-            .filter(|entry| entry.key().starts_with(path))
+            .filter(|entry| entry.key() == path || entry.key().starts_with(&prefix))
+            .map(|entry| entry.key().clone())
+            .collect();
+        for key in keys_to_remove {
+            self.current_size.remove(&key);
+            self.cache.remove(&key);
+        }
+    }
+
+    // 移除该路径本身、其子目录以及所有祖先目录的缓存（祖先的统计已包含该路径）
+    pub fn invalidate_related(&self, path: &str) {
+        let prefix = format!("{}/", path.trim_end_matches('/'));
+        let keys_to_remove: Vec<String> = self
+            .cache
+            .iter()
+            .filter(|entry| {
+                let key = entry.key();
+                key == path
+                    || key.starts_with(&prefix)
+                    || path.starts_with(&format!("{}/", key.trim_end_matches('/')))
+            })
             .map(|entry| entry.key().clone())
             .collect();
         for key in keys_to_remove {
@@ -123,28 +498,328 @@ impl ScanCache {
     }
 }
 
+// 正在进行的扫描，键为规范路径加扫描选项
+type Flight = Arc<tokio::sync::OnceCell<Result<ScanResult, String>>>;
+
 lazy_static::lazy_static! {
     // 减少缓存条目数量，限制总内存使用为 100MB
     static ref SCAN_CACHE: ScanCache = ScanCache::new(50, 100);
+    static ref IN_FLIGHT: DashMap<String, Flight> = DashMap::new();
+    // 聚合专用的线程池，避免占用全局 rayon 线程池；线程数变化时重建，
+    // 旧线程池在仍在使用它的扫描结束后释放
+    static ref SCAN_POOL: Mutex<Option<Arc<rayon::ThreadPool>>> = Mutex::new(None);
+}
+
+// 默认线程数：SEARCH_TOOL_THREADS 环境变量，否则保留一个核心给界面
+pub fn default_threads() -> usize {
+    std::env::var("SEARCH_TOOL_THREADS")
+        .ok()
+        .and_then(|value| value.trim().parse().ok())
+        .filter(|&threads| threads > 0)
+        .unwrap_or_else(|| {
+            std::thread::available_parallelism()
+                .map(|n| n.get().saturating_sub(1).max(1))
+                .unwrap_or(1)
+        })
+}
+
+// 线程数限制在 1 到 CPU 核心数之间
+fn scan_pool(threads: usize) -> Result<Arc<rayon::ThreadPool>, anyhow::Error> {
+    let max = std::thread::available_parallelism().map_or(1, |n| n.get());
+    let threads = threads.clamp(1, max);
+    let mut current = SCAN_POOL.lock().unwrap();
+    if let Some(pool) = current.as_ref().filter(|p| p.current_num_threads() == threads) {
+        return Ok(pool.clone());
+    }
+    let pool = Arc::new(
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .thread_name(|i| format!("scan-worker-{}", i))
+            .build()?,
+    );
+    *current = Some(pool.clone());
+    Ok(pool)
 }
 
-pub fn format_size(bytes: i64) -> String {
-    if bytes < 1024 {
+pub fn set_cache_limits(max_entries: usize, max_size_mb: usize) {
+    SCAN_CACHE.set_limits(max_entries, max_size_mb);
+}
+
+// 扫描完成后是否为一级子目录预先生成缓存
+static PREWARM_SUBDIRECTORIES: AtomicBool = AtomicBool::new(false);
+
+// 预热时最多缓存的子目录数（按大小取最大的），避免挤占缓存中的其他结果
+const PREWARM_MAX_CHILDREN: usize = 16;
+
+pub fn set_prewarm(enabled: bool) {
+    PREWARM_SUBDIRECTORIES.store(enabled, Ordering::Relaxed);
+}
+
+// 文件系统被修改后调用，使受影响的缓存失效
+pub fn invalidate_cache(path: &str) {
+    SCAN_CACHE.invalidate_related(&path_key(path));
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SizeUnit {
+    // 1024 进制（KiB/MiB）
+    #[default]
+    Binary,
+    // 1000 进制（KB/MB）
+    Decimal,
+}
+
+pub fn format_size(bytes: i64, unit: SizeUnit) -> String {
+    let (base, labels) = match unit {
+        SizeUnit::Binary => (1024.0, ["KiB", "MiB", "GiB", "TiB"]),
+        SizeUnit::Decimal => (1000.0, ["KB", "MB", "GB", "TB"]),
+    };
+    if (bytes.unsigned_abs() as f64) < base {
         return format!("{} B", bytes);
     }
-    let kb = bytes as f64 / 1024.0;
-    if kb < 1024.0 {
-        return format!("{:.1} KB", kb);
+
+    let mut value = bytes as f64 / base;
+    let mut label = labels[0];
+    for next in &labels[1..] {
+        if value.abs() < base {
+            break;
+        }
+        value /= base;
+        label = next;
+    }
+    format!("{:.1} {}", value, label)
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SizeFormat {
+    #[default]
+    Human,
+    // 只返回字节数，由前端自行格式化，可明显减小大结果的传输量
+    None,
+}
+
+// 扫描时统一按二进制单位格式化，返回前按设置的单位和请求的格式重新生成
+pub fn format_sizes(result: &mut ScanResult, unit: SizeUnit, format: SizeFormat) {
+    match format {
+        SizeFormat::None => result.total_size_formatted.clear(),
+        SizeFormat::Human if unit != SizeUnit::Binary => {
+            result.total_size_formatted = format_size(result.total_size, unit);
+        }
+        SizeFormat::Human => {}
+    }
+    format_item_sizes(&mut result.items, unit, format);
+}
+
+pub fn format_item_sizes(items: &mut [Item], unit: SizeUnit, format: SizeFormat) {
+    match format {
+        SizeFormat::None => items.iter_mut().for_each(|item| item.size_formatted.clear()),
+        SizeFormat::Human if unit != SizeUnit::Binary => {
+            for item in items {
+                item.size_formatted = format_size(item.size, unit);
+            }
+        }
+        SizeFormat::Human => {}
+    }
+}
+
+// 相对路径的父目录，一级子项为空字符串
+pub fn parent_of(rel_path: &str) -> String {
+    Path::new(&native_separators(rel_path))
+        .parent()
+        .map(|parent| parent.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+// 条目的绝对路径
+pub fn abs_path(root: &str, rel_path: &str) -> String {
+    Path::new(&paths::display(root))
+        .join(native_separators(rel_path))
+        .to_string_lossy()
+        .to_string()
+}
+
+// 根据扫描根目录填写条目的绝对路径和父目录
+pub fn fill_item_paths(root: &str, items: &mut [Item]) {
+    let root = PathBuf::from(paths::display(root));
+    for item in items {
+        let rel_path = native_separators(&item.path);
+        item.abs_path = root.join(&rel_path).to_string_lossy().to_string();
+        item.parent = parent_of(&rel_path);
+    }
+}
+
+// 从历史快照生成按时间升序排列的增长趋势
+pub fn build_trend(history: &[HistoryItem], path: &str) -> Trend {
+    let key = path_key(path);
+    let mut points: Vec<TrendPoint> = history
+        .iter()
+        .filter(|item| path_key(&item.path) == key)
+        .map(|item| {
+            let children = item
+                .items
+                .iter()
+                .filter(|child| Path::new(&child.path).components().count() == 1)
+                .map(|child| (child.path.clone(), child.size))
+                .collect();
+            TrendPoint {
+                scan_time: item.scan_time,
+                total_size: item.total_size,
+                children,
+                note: item.note.clone(),
+            }
+        })
+        .collect();
+
+    points.sort_by_key(|point| point.scan_time);
+
+    Trend {
+        path: path.to_string(),
+        points,
+    }
+}
+
+// 只保留不超过 depth 层的条目
+pub fn limit_depth(result: &mut ScanResult, depth: usize) {
+    result
+        .items
+        .retain(|item| Path::new(&item.path).components().count() <= depth);
+}
+
+// 统计指定扩展名（不区分大小写，可带前导点）的文件，返回最大的 n 个
+pub fn top_by_extension(
+    result: &ScanResult,
+    ext: &str,
+    n: usize,
+    unit: SizeUnit,
+) -> ExtensionReport {
+    let extension = ext.trim().trim_start_matches('.').to_lowercase();
+
+    // 结果中的条目已按大小降序排列
+    let matching: Vec<&Item> = result
+        .items
+        .iter()
+        .filter(|item| !item.is_dir)
+        .filter(|item| {
+            Path::new(&item.path)
+                .extension()
+                .map(|e| e.to_string_lossy().to_lowercase() == extension)
+                .unwrap_or(false)
+        })
+        .collect();
+
+    let total_size: i64 = matching.iter().map(|item| item.size).sum();
+    let count = matching.len();
+    let mut items: Vec<Item> = matching.into_iter().take(n).cloned().collect();
+    format_item_sizes(&mut items, unit, SizeFormat::Human);
+
+    ExtensionReport {
+        extension,
+        total_size,
+        total_size_formatted: format_size(total_size, unit),
+        count,
+        items,
+    }
+}
+
+// 后台任务用于取消扫描和读取进度
+#[derive(Debug, Default)]
+pub struct ScanControl {
+    cancelled: AtomicBool,
+    files: AtomicUsize,
+    dirs: AtomicUsize,
+    // 顶层子项名称 -> (已统计的大小, 是否为目录)
+    top_level: Mutex<HashMap<String, (i64, bool)>>,
+}
+
+impl ScanControl {
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    // 已发现的文件数和已读取的目录数
+    pub fn progress(&self) -> (usize, usize) {
+        (
+            self.files.load(Ordering::Relaxed),
+            self.dirs.load(Ordering::Relaxed),
+        )
+    }
+
+    // 按目前已发现的文件生成顶层子项的阶段性结果
+    pub fn snapshot(&self, unit: SizeUnit, format: SizeFormat) -> ScanSnapshot {
+        let (files, dirs) = self.progress();
+        let formatted = |size| match format {
+            SizeFormat::Human => format_size(size, unit),
+            SizeFormat::None => String::new(),
+        };
+        let mut items: Vec<Item> = self
+            .top_level
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(name, &(size, is_dir))| Item {
+                path: name.clone(),
+                abs_path: String::new(),
+                parent: String::new(),
+                encoding_lossy: false,
+                name: name.clone(),
+                size,
+                size_formatted: formatted(size),
+                is_dir,
+                modified: None,
+                accessed: None,
+                flags: Vec::new(),
+                largest_child: None,
+                estimate: None,
+            })
+            .collect();
+        items.sort_by_key(|item| std::cmp::Reverse(item.size));
+
+        let total_size = items.iter().map(|item| item.size).sum();
+        ScanSnapshot {
+            files,
+            dirs,
+            total_size,
+            total_size_formatted: formatted(total_size),
+            items,
+        }
     }
-    let mb = kb / 1024.0;
-    if mb < 1024.0 {
-        return format!("{:.1} MB", mb);
+
+    fn record(&self, root: &Path, file: &Path, size: i64) {
+        let Ok(rel_path) = file.strip_prefix(root) else {
+            return;
+        };
+        let mut components = rel_path.components();
+        let Some(first) = components.next() else {
+            return;
+        };
+        let is_dir = components.next().is_some();
+        let name = first.as_os_str().to_string_lossy().to_string();
+        let mut top_level = self.top_level.lock().unwrap();
+        top_level.entry(name).or_insert((0, is_dir)).0 += size;
     }
-    let gb = mb / 1024.0;
-    format!("{:.1} GB", gb)
 }
 
-pub async fn scan_directory(path: &str, force_refresh: bool) -> Result<ScanResult, anyhow::Error> {
+pub async fn scan_directory(
+    path: &str,
+    force_refresh: bool,
+    options: &ScanOptions,
+) -> Result<ScanResult, anyhow::Error> {
+    scan_directory_controlled(path, force_refresh, options, None).await
+}
+
+// 带控制句柄的扫描不与其他请求共享，取消只影响本次扫描
+pub async fn scan_directory_controlled(
+    path: &str,
+    force_refresh: bool,
+    options: &ScanOptions,
+    control: Option<Arc<ScanControl>>,
+) -> Result<ScanResult, anyhow::Error> {
     let start_time = std::time::Instant::now();
 
     if path.trim().is_empty() {
@@ -171,7 +846,8 @@ pub async fn scan_directory(path: &str, force_refresh: bool) -> Result<ScanResul
         }
     };
 
-    let root_dir = canonical_path.to_string_lossy().replace('\\', "/");
+    let root_dir = paths::slash(&canonical_path);
+    let cache_key = paths::key(&root_dir);
 
     let mtime = match metadata.modified() {
         Ok(m) => m,
@@ -179,35 +855,91 @@ pub async fn scan_directory(path: &str, force_refresh: bool) -> Result<ScanResul
     };
     let mtime_datetime: chrono::DateTime<chrono::Local> = mtime.into();
 
-    if !force_refresh {
-        if let Some(cached) = SCAN_CACHE.get(&root_dir) {
-            if cached.dir_mtime >= mtime_datetime {
+    let use_cache = options.is_unfiltered();
+
+    if !force_refresh && use_cache {
+        if let Some(cached) = SCAN_CACHE.get(&cache_key) {
+            if cached.dir_mtime >= mtime_datetime && cached.variant == options.cache_variant() {
                 let mut result = cached.result.clone();
                 result.scan_time = 0.0;
+                result.stats = None;
                 return Ok(result);
             }
         }
     }
 
-    SCAN_CACHE.invalidate(&root_dir);
+    if control.is_some() {
+        let mut result =
+            scan_uncached(canonical_path, root_dir, cache_key, options, start_time, control)
+                .await?;
+        result.path = path.to_string();
+        return Ok(result);
+    }
+
+    // 相同根目录和选项的并发请求共享同一次扫描，避免重复遍历
+    let flight_key = format!(
+        "{}|{}",
+        cache_key,
+        serde_json::to_string(options).unwrap_or_default()
+    );
+    let flight = IN_FLIGHT.entry(flight_key.clone()).or_default().clone();
+    let shared = flight
+        .get_or_init(|| async {
+            scan_uncached(canonical_path, root_dir, cache_key, options, start_time, None)
+                .await
+                .map_err(|e| e.to_string())
+        })
+        .await
+        .clone();
+    IN_FLIGHT.remove_if(&flight_key, |_, f| Arc::ptr_eq(f, &flight));
+
+    let mut result = shared.map_err(|e| anyhow::anyhow!(e))?;
+    result.path = path.to_string();
+    Ok(result)
+}
 
-    let root_dir_for_processing = root_dir.clone();
+async fn scan_uncached(
+    canonical_path: PathBuf,
+    root_dir: String,
+    cache_key: String,
+    options: &ScanOptions,
+    start_time: std::time::Instant,
+    control: Option<Arc<ScanControl>>,
+) -> Result<ScanResult, anyhow::Error> {
+    let use_cache = options.is_unfiltered();
 
-    let (dir_sizes, file_sizes) = tokio::task::spawn_blocking(move || {
-        scan_directory_blocking(&canonical_path, &root_dir_for_processing)
+    if use_cache {
+        SCAN_CACHE.invalidate(&cache_key);
+    }
+
+    let root_path = canonical_path.clone();
+    let options_for_processing = options.clone();
+
+    let ScanOutput {
+        dir_sizes,
+        file_sizes,
+        mut stats,
+        audit,
+        estimates,
+        estimated_total,
+    } = tokio::task::spawn_blocking(move || {
+        scan_directory_blocking(&canonical_path, &options_for_processing, control.as_deref())
     })
     .await??;
 
+    let aggregate_start = std::time::Instant::now();
+
     // 预分配容量以减少重新分配
     let mut items = Vec::with_capacity(dir_sizes.len() + file_sizes.len());
-    let mut total_size = 0i64;
+    let flag_rules = options.flag_rules.as_deref().unwrap_or_default();
 
-    for (dir, size) in dir_sizes.iter() {
-        if dir == &root_dir {
+    for (dir, aggregate) in dir_sizes.iter() {
+        if dir == &root_path {
             continue;
         }
+        let size = &aggregate.size;
 
-        if let Ok(rel_path) = Path::new(dir).strip_prefix(&root_dir) {
+        if let Ok(rel_path) = dir.strip_prefix(&root_path) {
             let rel_path_str = rel_path.to_string_lossy().to_string();
             if !rel_path_str.is_empty() {
                 let name = Path::new(&rel_path_str)
@@ -217,18 +949,31 @@ pub async fn scan_directory(path: &str, force_refresh: bool) -> Result<ScanResul
                     .to_string();
                 items.push(Item {
                     path: rel_path_str,
+                    abs_path: String::new(),
+                    parent: String::new(),
+                    encoding_lossy: rel_path.to_str().is_none(),
                     name,
                     size: *size,
-                    size_formatted: format_size(*size),
+                    size_formatted: format_size(*size, SizeUnit::Binary),
                     is_dir: true,
+                    modified: None,
+                    accessed: None,
+                    flags: item_flags(flag_rules, *size, true),
+                    largest_child: aggregate.largest_file.as_ref().and_then(|(file, size)| {
+                        let rel_path = file.strip_prefix(&root_path).ok()?;
+                        Some(LargestChild {
+                            path: rel_path.to_string_lossy().to_string(),
+                            size: *size,
+                        })
+                    }),
+                    estimate: estimates.get(dir).copied(),
                 });
-                total_size += size;
             }
         }
     }
 
-    for (file, size) in file_sizes.iter() {
-        if let Ok(rel_path) = Path::new(file).strip_prefix(&root_dir) {
+    for (file, (size, modified, accessed)) in file_sizes.iter() {
+        if let Ok(rel_path) = file.strip_prefix(&root_path) {
             let rel_path_str = rel_path.to_string_lossy().to_string();
             if !rel_path_str.is_empty() {
                 let name = Path::new(&rel_path_str)
@@ -238,69 +983,446 @@ pub async fn scan_directory(path: &str, force_refresh: bool) -> Result<ScanResul
                     .to_string();
                 items.push(Item {
                     path: rel_path_str,
+                    abs_path: String::new(),
+                    parent: String::new(),
+                    encoding_lossy: rel_path.to_str().is_none(),
                     name,
                     size: *size,
-                    size_formatted: format_size(*size),
+                    size_formatted: format_size(*size, SizeUnit::Binary),
                     is_dir: false,
+                    modified: *modified,
+                    accessed: *accessed,
+                    flags: item_flags(flag_rules, *size, false),
+                    largest_child: None,
+                    estimate: None,
                 });
-                total_size += size;
             }
         }
     }
 
-    items.sort_by(|a, b| b.size.cmp(&a.size));
+    // 总大小只累计文件，目录大小已包含其中的文件
+    fill_item_paths(&root_dir, &mut items);
+    let mut root = RootSummary::from_items(&items);
+    // 估算模式下条目只包含抽到的文件，总大小取按权重推算的值，文件数仍为实际统计的数量
+    if let Some(total) = estimated_total {
+        root.subtree_size = total;
+    }
+    let total_size = root.subtree_size;
+    stats.aggregate_time += aggregate_start.elapsed().as_secs_f64();
+
+    let sort_start = std::time::Instant::now();
+    items.sort_by_key(|item| std::cmp::Reverse(item.size));
+    stats.sort_time = sort_start.elapsed().as_secs_f64();
 
     let scan_time = start_time.elapsed().as_secs_f64();
+    stats.wall_time = scan_time;
+    if scan_time > 0.0 {
+        stats.files_per_sec = stats.files as f64 / scan_time;
+    }
+    stats.peak_memory = peak_memory();
 
     let result = ScanResult {
         items,
         total_size,
-        total_size_formatted: format_size(total_size),
+        total_size_formatted: format_size(total_size, SizeUnit::Binary),
         scan_time,
-        path: path.to_string(),
+        path: root_dir.clone(),
+        treemap: None,
+        result_id: None,
+        stats: Some(stats),
+        root,
+        audit,
+        estimate: estimates.get(Path::new(ROOT_ESTIMATE)).copied(),
     };
 
-    SCAN_CACHE.insert(root_dir.clone(), result.clone());
+    if use_cache {
+        // 子目录先写入，避免按时间淘汰时先移除根目录的结果；审计结果不按子目录拆分
+        if PREWARM_SUBDIRECTORIES.load(Ordering::Relaxed) && !options.audit {
+            prewarm_children(&result, &cache_key, &options.cache_variant());
+        }
+        SCAN_CACHE.insert(cache_key, result.clone(), options.cache_variant());
+    }
 
     Ok(result)
 }
 
+// 从根目录的完整结果中截取各一级子目录的结果写入缓存，进入子目录时无需重新遍历
+fn prewarm_children(result: &ScanResult, cache_key: &str, variant: &str) {
+    let max_entries = SCAN_CACHE.max_entries.load(Ordering::Relaxed);
+    let limit = PREWARM_MAX_CHILDREN.min(max_entries / 2);
+
+    // 结果已按大小降序排列
+    let children = result
+        .items
+        .iter()
+        .filter(|item| item.is_dir && Path::new(&item.path).components().count() == 1)
+        .take(limit);
+
+    for child in children {
+        let items: Vec<Item> = result
+            .items
+            .iter()
+            .filter_map(|item| {
+                let mut item = item.clone();
+                item.path = strip_parent(&item.path, &child.path)?;
+                item.parent = parent_of(&item.path);
+                if let Some(largest) = &mut item.largest_child {
+                    largest.path = strip_parent(&largest.path, &child.path)?;
+                }
+                Some(item)
+            })
+            .collect();
+
+        let root = RootSummary::from_items(&items);
+        let child_result = ScanResult {
+            items,
+            total_size: root.subtree_size,
+            total_size_formatted: format_size(root.subtree_size, SizeUnit::Binary),
+            scan_time: 0.0,
+            path: format!("{}/{}", result.path.trim_end_matches('/'), child.path),
+            treemap: None,
+            result_id: None,
+            stats: None,
+            root,
+            audit: None,
+            estimate: None,
+        };
+        SCAN_CACHE.insert(
+            paths::key(&format!("{}/{}", cache_key.trim_end_matches('/'), child.path)),
+            child_result,
+            variant.to_string(),
+        );
+    }
+}
+
+// parent 下条目相对于 parent 的路径，不在其下时返回 None
+fn strip_parent(path: &str, parent: &str) -> Option<String> {
+    let rest = path.strip_prefix(parent)?.strip_prefix(['/', '\\'])?;
+    Some(rest.to_string())
+}
+
+type SizeMap = HashMap<PathBuf, DirAggregate>;
+
+// 目录的累计大小及其下最大的文件
+#[derive(Default)]
+struct DirAggregate {
+    size: i64,
+    largest_file: Option<(PathBuf, i64)>,
+}
+// 按扩展名过滤文件，没有扩展名的文件对应空字符串
+#[derive(Debug, Clone, Default)]
+pub struct ExtensionFilter {
+    include: Option<HashSet<String>>,
+    exclude: HashSet<String>,
+}
+
+impl ExtensionFilter {
+    pub fn new(include: Option<&[String]>, exclude: Option<&[String]>) -> Self {
+        let normalize = |ext: &String| ext.trim().trim_start_matches('.').to_lowercase();
+        ExtensionFilter {
+            include: include.map(|exts| exts.iter().map(normalize).collect()),
+            exclude: exclude.iter().copied().flatten().map(normalize).collect(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.include.is_none() && self.exclude.is_empty()
+    }
+
+    pub fn matches(&self, path: &Path) -> bool {
+        let extension = path
+            .extension()
+            .map(|e| e.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        self.include
+            .as_ref()
+            .is_none_or(|include| include.contains(&extension))
+            && !self.exclude.contains(&extension)
+    }
+}
+
+// 文件路径 -> (大小, 修改时间, 访问时间)
+type FileMap = HashMap<PathBuf, (i64, Option<i64>, Option<i64>)>;
+
+struct WalkedFile {
+    path: PathBuf,
+    size: i64,
+    modified: Option<i64>,
+    accessed: Option<i64>,
+    // 快速估算时该文件代表的文件数，精确统计时为 1
+    weight: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UnusedReport {
+    pub path: String,
+    pub days: u64,
+    // 访问时间不可靠时的提示
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub warning: Option<String>,
+    pub total_size: i64,
+    pub total_size_formatted: String,
+    pub items: Vec<Item>,
+}
+
+// 超过 days 天未被读取且不小于 min_size 的文件，按大小降序。
+// 访问时间只是尽力而为：noatime 挂载下从不更新，relatime（Linux 默认）下
+// 每天最多更新一次，Windows 默认也可能关闭了最后访问时间的更新
+pub fn unused_since(
+    result: &ScanResult,
+    days: u64,
+    min_size: i64,
+    unit: SizeUnit,
+) -> UnusedReport {
+    let cutoff = chrono::Utc::now().timestamp() - days as i64 * 86400;
+    let mut items: Vec<Item> = result
+        .items
+        .iter()
+        .filter(|item| {
+            !item.is_dir
+                && item.size >= min_size
+                && item.accessed.is_some_and(|accessed| accessed < cutoff)
+        })
+        .cloned()
+        .collect();
+    items.sort_by_key(|item| std::cmp::Reverse(item.size));
+
+    let total_size = items.iter().map(|item| item.size).sum();
+    UnusedReport {
+        path: result.path.clone(),
+        days,
+        warning: atime_warning(Path::new(&result.path)),
+        total_size,
+        total_size_formatted: format_size(total_size, unit),
+        items,
+    }
+}
+
+// 路径所在文件系统以 noatime 挂载时访问时间不会更新
+#[cfg(target_os = "linux")]
+fn atime_warning(path: &Path) -> Option<String> {
+    let path = std::fs::canonicalize(path).ok()?;
+    let mounts = std::fs::read_to_string("/proc/self/mounts").ok()?;
+    let (_, options) = mounts
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let mount_point = fields.nth(1)?.replace("\\040", " ");
+            let options = fields.nth(1)?;
+            Some((mount_point, options))
+        })
+        .filter(|(mount_point, _)| path.starts_with(mount_point))
+        .max_by_key(|(mount_point, _)| mount_point.len())?;
+    options
+        .split(',')
+        .any(|option| option == "noatime")
+        .then(|| "该路径所在的文件系统以 noatime 挂载，访问时间不会更新".to_string())
+}
+
+// NtfsDisableLastAccessUpdate 的最低位为 1 时 NTFS 不更新访问时间
+#[cfg(target_os = "windows")]
+fn atime_warning(_path: &Path) -> Option<String> {
+    use windows_sys::Win32::Foundation::ERROR_SUCCESS;
+    use windows_sys::Win32::System::Registry::{
+        RegGetValueW, HKEY_LOCAL_MACHINE, RRF_RT_REG_DWORD,
+    };
+
+    let wide = |s: &str| s.encode_utf16().chain(std::iter::once(0)).collect::<Vec<u16>>();
+    let subkey = wide(r"SYSTEM\CurrentControlSet\Control\FileSystem");
+    let name = wide("NtfsDisableLastAccessUpdate");
+    let mut value: u32 = 0;
+    let mut size = std::mem::size_of::<u32>() as u32;
+    // SAFETY: 字符串以 0 结尾，value 和 size 在调用期间有效
+    let code = unsafe {
+        RegGetValueW(
+            HKEY_LOCAL_MACHINE,
+            subkey.as_ptr(),
+            name.as_ptr(),
+            RRF_RT_REG_DWORD,
+            std::ptr::null_mut(),
+            &mut value as *mut u32 as *mut _,
+            &mut size,
+        )
+    };
+    (code == ERROR_SUCCESS && value & 1 == 1)
+        .then(|| "系统已关闭 NTFS 最后访问时间的更新，访问时间不可靠".to_string())
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "windows")))]
+fn atime_warning(_path: &Path) -> Option<String> {
+    None
+}
+
+#[derive(Default)]
+struct WalkStats {
+    dirs_visited: usize,
+    errors: usize,
+    skipped_dirs: Vec<String>,
+    audit: Option<AuditReport>,
+    sampling: Option<Sampling>,
+}
+
+impl WalkStats {
+    fn skip_dir(&mut self, path: &Path, reason: &str) {
+        self.errors += 1;
+        if self.skipped_dirs.len() < MAX_SKIPPED_DIRS {
+            self.skipped_dirs.push(format!("{}: {}", path.display(), reason));
+        }
+    }
+}
+
+// 遍历的最大目录深度，兜底无法识别的循环
+const MAX_WALK_DEPTH: usize = 256;
+// 最多记录的跳过目录数
+const MAX_SKIPPED_DIRS: usize = 100;
+
+// 已进入的目录，用于发现绑定挂载或符号链接造成的目录循环和重复目录
+#[derive(Default)]
+struct VisitedDirs {
+    // 目录 ID -> 首次进入时的路径
+    #[cfg(unix)]
+    ids: HashMap<(u64, u64), PathBuf>,
+    #[cfg(not(unix))]
+    ids: HashSet<PathBuf>,
+}
+
+enum Visit {
+    First,
+    // 重新进入当前路径的祖先，继续遍历不会结束
+    Cycle,
+    // 从另一条路径（绑定挂载、链接）再次到达已统计的目录
+    Duplicate,
+}
+
+impl Visit {
+    fn skip_reason(&self) -> Option<&'static str> {
+        match self {
+            Visit::First => None,
+            Visit::Cycle => Some("目录循环"),
+            Visit::Duplicate => Some("重复目录"),
+        }
+    }
+}
+
+impl VisitedDirs {
+    // 祖先总是先于子孙进入，首次进入的路径是当前路径的前缀即为循环
+    #[cfg(unix)]
+    fn enter(&mut self, path: &Path, metadata: &std::fs::Metadata) -> Visit {
+        use std::os::unix::fs::MetadataExt;
+        let id = (metadata.dev(), metadata.ino());
+        match self.ids.get(&id) {
+            None => {
+                self.ids.insert(id, path.to_path_buf());
+                Visit::First
+            }
+            Some(first) if path.starts_with(first) => Visit::Cycle,
+            Some(_) => Visit::Duplicate,
+        }
+    }
+
+    // 没有稳定的文件 ID 接口：普通目录不会成环，只解析链接（含目录联接）的目标，
+    // 目标是链接所在目录的祖先时为循环，已经进入过时为重复
+    #[cfg(not(unix))]
+    fn enter(&mut self, path: &Path, _metadata: &std::fs::Metadata) -> Visit {
+        if !std::fs::symlink_metadata(path).is_ok_and(|m| m.file_type().is_symlink()) {
+            return Visit::First;
+        }
+        let Ok(target) = std::fs::canonicalize(path) else {
+            return Visit::First;
+        };
+        let parent = path.parent().and_then(|parent| std::fs::canonicalize(parent).ok());
+        if parent.is_some_and(|parent| parent.starts_with(&target)) {
+            return Visit::Cycle;
+        }
+        if self.ids.insert(target) {
+            Visit::First
+        } else {
+            Visit::Duplicate
+        }
+    }
+}
+
+// estimates 中根目录使用的键
+const ROOT_ESTIMATE: &str = "";
+
+struct ScanOutput {
+    dir_sizes: SizeMap,
+    file_sizes: FileMap,
+    stats: ScanStats,
+    audit: Option<AuditReport>,
+    // 快速估算时受抽样影响的目录的置信区间，键与 dir_sizes 相同，根目录为 ROOT_ESTIMATE
+    estimates: HashMap<PathBuf, SizeEstimate>,
+    // 快速估算时按权重推算的总大小
+    estimated_total: Option<i64>,
+}
+
 fn scan_directory_blocking(
     path: &Path,
-    root_dir: &str,
-) -> Result<(HashMap<String, i64>, HashMap<String, i64>), anyhow::Error> {
+    options: &ScanOptions,
+    control: Option<&ScanControl>,
+) -> Result<ScanOutput, anyhow::Error> {
     // 使用流式处理，避免一次性收集所有文件
     let dir_sizes = DashMap::new();
     let file_sizes = DashMap::new();
-    let root_path = Path::new(root_dir).to_path_buf();
+
+    // 低优先级模式下降低当前线程的 IO 优先级，并用单线程池完成聚合
+    let _background = options.low_priority.then(BackgroundIo::enter);
+    let threads = if options.low_priority {
+        1
+    } else {
+        options.threads.filter(|&n| n > 0).unwrap_or_else(default_threads)
+    };
+    let pool = scan_pool(threads)?;
+    let aggregate = |batch: &[(PathBuf, i64, i64)]| {
+        pool.install(|| process_batch(batch, &dir_sizes, path))
+    };
 
     // 分批处理文件以减少内存压力
     let batch_size = 10000;
-    let mut batch: Vec<(PathBuf, i64)> = Vec::with_capacity(batch_size);
+    let mut batch: Vec<(PathBuf, i64, i64)> = Vec::with_capacity(batch_size);
+    let mut weighted_total = 0i64;
 
     // 使用优化的文件收集方法
-    for entry in collect_files_optimized(path)? {
-        let (file_path, size) = entry;
+    let walk_start = std::time::Instant::now();
+    let (files, walk_stats) = collect_files_optimized(path, options, control)?;
+    let walk_time = walk_start.elapsed().as_secs_f64();
+    let aggregate_start = std::time::Instant::now();
+    let file_count = files.len();
 
-        // 添加到文件大小映射
-        if let Some(path_str) = file_path.to_str() {
-            let normalized_path = path_str.replace('\\', "/");
-            file_sizes.insert(normalized_path, size);
-        }
+    for entry in files {
+        let WalkedFile {
+            path: file_path,
+            size,
+            modified,
+            accessed,
+            weight,
+        } = entry;
+
+        // 添加到文件大小映射，键保留原始路径，非 UTF-8 的文件名也能统计
+        file_sizes.insert(file_path.clone(), (size, modified, accessed));
+
+        // 抽样得到的文件按其代表的文件数计入上层目录
+        let weighted = if weight == 1.0 {
+            size
+        } else {
+            (size as f64 * weight).round() as i64
+        };
+        weighted_total += weighted;
 
         // 添加到批次
-        batch.push((file_path, size));
+        batch.push((file_path, size, weighted));
 
         // 批次满了就处理
         if batch.len() >= batch_size {
-            process_batch(&batch, &dir_sizes, &root_path);
+            aggregate(&batch);
             batch.clear();
         }
     }
 
     // 处理剩余的文件
     if !batch.is_empty() {
-        process_batch(&batch, &dir_sizes, &root_path);
+        aggregate(&batch);
     }
 
     // 转换为普通 HashMap
@@ -314,44 +1436,229 @@ fn scan_directory_blocking(
         file_sizes_map.insert(key, value);
     }
 
-    Ok((dir_sizes_map, file_sizes_map))
+    // 累计值除以目录自身的权重，得到各目录的估算大小，再计算置信区间
+    let mut estimates = HashMap::new();
+    if let Some(sampling) = &walk_stats.sampling {
+        for (dir, aggregate) in dir_sizes_map.iter_mut() {
+            let weight = sampling.weight(dir);
+            aggregate.size = (aggregate.size as f64 / weight).round() as i64;
+        }
+        let bounds = sampling.bounds(path, |entry| {
+            if entry == path {
+                return Some(weighted_total);
+            }
+            dir_sizes_map
+                .get(entry)
+                .map(|aggregate| aggregate.size)
+                .or_else(|| file_sizes_map.get(entry).map(|&(size, _, _)| size))
+        });
+        for (dir, estimate) in bounds {
+            let key = if dir == path { PathBuf::from(ROOT_ESTIMATE) } else { dir };
+            estimates.insert(key, estimate);
+        }
+    }
+
+    let stats = ScanStats {
+        files: file_count,
+        dirs_visited: walk_stats.dirs_visited,
+        errors: walk_stats.errors,
+        skipped_dirs: walk_stats.skipped_dirs,
+        walk_time,
+        aggregate_time: aggregate_start.elapsed().as_secs_f64(),
+        ..Default::default()
+    };
+
+    Ok(ScanOutput {
+        dir_sizes: dir_sizes_map,
+        file_sizes: file_sizes_map,
+        stats,
+        audit: walk_stats.audit,
+        estimated_total: walk_stats.sampling.is_some().then_some(weighted_total),
+        estimates,
+    })
 }
 
-fn process_batch(batch: &[(PathBuf, i64)], dir_sizes: &DashMap<String, i64>, root_path: &Path) {
-    batch.par_iter().for_each(|(file_path, size)| {
+// 批次中每项为（文件路径, 大小, 按抽样权重计入上层目录的大小）
+fn process_batch(
+    batch: &[(PathBuf, i64, i64)],
+    dir_sizes: &DashMap<PathBuf, DirAggregate>,
+    root_path: &Path,
+) {
+    batch.par_iter().for_each(|(file_path, size, weighted)| {
         if let Some(parent) = file_path.parent() {
             for ancestor in parent.ancestors() {
                 if ancestor == root_path || ancestor == Path::new("") {
                     break;
                 }
-                if let Some(dir_path) = ancestor.to_str() {
-                    let mut sizes = dir_sizes.entry(dir_path.to_string()).or_default();
-                    *sizes += size;
+                let mut aggregate = dir_sizes.entry(ancestor.to_path_buf()).or_default();
+                aggregate.size += weighted;
+                if aggregate.largest_file.as_ref().is_none_or(|(_, largest)| size > largest) {
+                    aggregate.largest_file = Some((file_path.clone(), *size));
                 }
             }
         }
     });
 }
 
+// 低优先级模式下每读取这么多目录暂停一次，给其他程序让出磁盘
+const LOW_PRIORITY_DIR_BATCH: usize = 64;
+const LOW_PRIORITY_PAUSE: std::time::Duration = std::time::Duration::from_millis(10);
+
 // 备用方案：使用更高效的文件收集方法
-fn collect_files_optimized(path: &Path) -> Result<Vec<(PathBuf, i64)>, anyhow::Error> {
+fn collect_files_optimized(
+    root: &Path,
+    options: &ScanOptions,
+    control: Option<&ScanControl>,
+) -> Result<(Vec<WalkedFile>, WalkStats), anyhow::Error> {
+    let modified_range = options.modified_range();
     let mut files = Vec::new();
-    let mut stack = vec![path.to_path_buf()];
-
-    while let Some(current_path) = stack.pop() {
-        if let Ok(entries) = std::fs::read_dir(&current_path) {
-            for entry in entries.flatten() {
-                let path = entry.path();
-                if let Ok(metadata) = path.metadata() {
-                    if metadata.is_dir() {
-                        stack.push(path);
-                    } else if metadata.is_file() {
-                        files.push((path, metadata.len() as i64));
-                    }
+    let mut stack = vec![(root.to_path_buf(), 0)];
+    let mut stats = WalkStats::default();
+    let mut visited = VisitedDirs::default();
+    if let Ok(metadata) = root.metadata() {
+        visited.enter(root, &metadata);
+    }
+    // 含路径分隔符的排除项按完整路径匹配，其余按名称匹配
+    let (excluded_paths, excludes): (Vec<&str>, Vec<&str>) = options
+        .excludes
+        .iter()
+        .flatten()
+        .map(String::as_str)
+        .partition(|exclude| exclude.contains(['/', '\\']));
+    let excludes: HashSet<&str> = excludes.into_iter().collect();
+    let excluded_paths: Vec<&Path> = excluded_paths.into_iter().map(Path::new).collect();
+    let follow_symlinks = options.follow_symlinks.unwrap_or(true);
+    let extensions = options.extension_filter();
+    let path_filter = match &options.path_regex {
+        Some(regex) => regex.compile()?,
+        None => PathFilter::default(),
+    };
+    let audit = options.audit.then(AuditCollector::default);
+    let sampling = options.quick_estimate.then(|| Sampling::new(root));
+
+    while let Some((current_path, depth)) = stack.pop() {
+        stats.dirs_visited += 1;
+        if let Some(control) = control {
+            if control.is_cancelled() {
+                return Err(anyhow::anyhow!("扫描已取消"));
+            }
+            control.dirs.fetch_add(1, Ordering::Relaxed);
+            control.files.store(files.len(), Ordering::Relaxed);
+        }
+        if options.low_priority && stats.dirs_visited.is_multiple_of(LOW_PRIORITY_DIR_BATCH) {
+            std::thread::sleep(LOW_PRIORITY_PAUSE);
+        }
+
+        let entries = match std::fs::read_dir(&current_path) {
+            Ok(entries) => entries,
+            Err(_) => {
+                stats.errors += 1;
+                continue;
+            }
+        };
+
+        let mut paths: Vec<PathBuf> = entries
+            .flatten()
+            .filter(|entry| {
+                !entry
+                    .file_name()
+                    .to_str()
+                    .is_some_and(|name| excludes.contains(name))
+                    && (follow_symlinks || !entry.file_type().is_ok_and(|t| t.is_symlink()))
+            })
+            .map(|entry| entry.path())
+            .filter(|path| {
+                !excluded_paths.contains(&path.as_path()) && !path_filter.excludes(path)
+            })
+            .collect();
+
+        // 快速估算时条目过多的目录只处理抽到的部分
+        let weight = match &sampling {
+            Some(sampling) => sampling.sample(&current_path, &mut paths, PathBuf::clone),
+            None => 1.0,
+        };
+
+        for path in paths {
+            let Ok(metadata) = path.metadata() else {
+                stats.errors += 1;
+                continue;
+            };
+            if let Some(audit) = &audit {
+                audit.check(&path, &metadata);
+            }
+            if metadata.is_dir() {
+                if depth + 1 > MAX_WALK_DEPTH {
+                    stats.skip_dir(&path, "超过最大目录深度");
+                    continue;
+                }
+                if let Some(reason) = visited.enter(&path, &metadata).skip_reason() {
+                    stats.skip_dir(&path, reason);
+                    continue;
+                }
+                if let Some(sampling) = &sampling {
+                    sampling.enter(&path, weight);
+                }
+                stack.push((path, depth + 1));
+            } else if metadata.is_file() {
+                let modified = metadata
+                    .modified()
+                    .ok()
+                    .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+                    .map(|d| d.as_secs() as i64);
+                if !in_modified_range(modified_range, modified)
+                    || !extensions.matches(&path)
+                    || !path_filter.includes(&path)
+                {
+                    continue;
+                }
+                let accessed = options
+                    .record_access_time
+                    .then(|| metadata.accessed().ok())
+                    .flatten()
+                    .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+                    .map(|d| d.as_secs() as i64);
+                if let Some(control) = control {
+                    control.record(root, &path, metadata.len() as i64);
                 }
+                files.push(WalkedFile {
+                    path,
+                    size: metadata.len() as i64,
+                    modified,
+                    accessed,
+                    weight,
+                });
             }
         }
     }
 
-    Ok(files)
+    stats.audit = audit.map(|audit| audit.report(root));
+    stats.sampling = sampling;
+    Ok((files, stats))
+}
+
+// 进程的峰值常驻内存（字节）
+#[cfg(target_os = "linux")]
+fn peak_memory() -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find(|l| l.starts_with("VmHWM:"))?;
+    let kb: u64 = line.split_whitespace().nth(1)?.parse().ok()?;
+    Some(kb * 1024)
+}
+
+#[cfg(windows)]
+fn peak_memory() -> Option<u64> {
+    use windows_sys::Win32::System::ProcessStatus::{
+        GetProcessMemoryInfo, PROCESS_MEMORY_COUNTERS,
+    };
+    use windows_sys::Win32::System::Threading::GetCurrentProcess;
+
+    let mut counters: PROCESS_MEMORY_COUNTERS = unsafe { std::mem::zeroed() };
+    let cb = std::mem::size_of::<PROCESS_MEMORY_COUNTERS>() as u32;
+    let ok = unsafe { GetProcessMemoryInfo(GetCurrentProcess(), &mut counters, cb) };
+    (ok != 0).then_some(counters.PeakWorkingSetSize as u64)
+}
+
+#[cfg(not(any(target_os = "linux", windows)))]
+fn peak_memory() -> Option<u64> {
+    None
 }
@@ -0,0 +1,86 @@
+use crate::scan::ScanResult;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+// 上次退出时的界面状态，单独保存在 session.json，不随设置一起读写
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Session {
+    pub last_path: Option<String>,
+    // 结果视图中已展开的目录（相对路径）
+    pub expanded_paths: Vec<String>,
+    pub sort_by: String,
+    pub sort_desc: bool,
+}
+
+impl Default for Session {
+    fn default() -> Self {
+        Session {
+            last_path: None,
+            expanded_paths: Vec::new(),
+            sort_by: "size".to_string(),
+            sort_desc: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RestoredSession {
+    pub session: Session,
+    // 上次的扫描结果，文件缺失或与上次路径不符时为空
+    pub result: Option<ScanResult>,
+}
+
+// 界面状态和上次扫描结果与设置文件放在同一目录
+pub fn session_path(settings_path: &Path) -> PathBuf {
+    settings_path.with_file_name("session.json")
+}
+
+pub fn last_result_path(settings_path: &Path) -> PathBuf {
+    settings_path.with_file_name("last-result.json")
+}
+
+impl Session {
+    // 旧版本把界面状态保存在设置文件的 session 字段中，没有 session.json 时从那里读取
+    pub fn load(settings_path: &Path) -> Self {
+        if let Ok(json) = std::fs::read(session_path(settings_path)) {
+            return serde_json::from_slice(&json).unwrap_or_default();
+        }
+        std::fs::read(settings_path)
+            .ok()
+            .and_then(|json| serde_json::from_slice::<serde_json::Value>(&json).ok())
+            .and_then(|mut settings| settings.get_mut("session").map(serde_json::Value::take))
+            .and_then(|session| serde_json::from_value(session).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, settings_path: &Path) -> Result<(), String> {
+        let json = serde_json::to_vec_pretty(self).map_err(|e| e.to_string())?;
+        write_atomic(&session_path(settings_path), &json)
+    }
+}
+
+pub fn save_last_result(path: &Path, result: &ScanResult) -> Result<(), String> {
+    let json = serde_json::to_vec(result).map_err(|e| e.to_string())?;
+    write_atomic(path, &json)
+}
+
+// 先写临时文件再替换，写入中途崩溃不会留下不完整的文件；
+// 临时文件名各不相同，后台的并发写入互不干扰
+pub fn write_atomic(path: &Path, content: &[u8]) -> Result<(), String> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    }
+    let temp = path.with_extension(format!("{}.tmp", uuid::Uuid::new_v4().simple()));
+    std::fs::write(&temp, content).map_err(|e| e.to_string())?;
+    std::fs::rename(&temp, path).map_err(|e| {
+        let _ = std::fs::remove_file(&temp);
+        e.to_string()
+    })
+}
+
+pub fn load_last_result(path: &Path) -> Option<ScanResult> {
+    let json = std::fs::read(path).ok()?;
+    serde_json::from_slice(&json).ok()
+}
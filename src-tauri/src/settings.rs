@@ -0,0 +1,154 @@
+use crate::scan::{FlagRule, ScanOptions, SizeUnit};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SymlinkPolicy {
+    #[default]
+    Follow,
+    Skip,
+}
+
+// 命名的扫描预设：根目录、排除项、层级和过滤条件
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScanPreset {
+    pub name: String,
+    pub path: String,
+    #[serde(default)]
+    pub depth: Option<usize>,
+    #[serde(flatten)]
+    pub options: ScanOptions,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Settings {
+    // 扫描时默认排除的文件或目录名
+    pub default_excludes: Vec<String>,
+    pub size_unit: SizeUnit,
+    pub locale: String,
+    pub cache_max_entries: usize,
+    pub cache_max_size_mb: usize,
+    // 扫描完成后为一级子目录预先生成缓存，进入子目录时无需重新扫描
+    pub prewarm_subdirectories: bool,
+    // 最多保留的历史记录条数
+    pub history_limit: usize,
+    // 同时运行的扫描任务数
+    pub max_parallel_scans: usize,
+    pub symlink_policy: SymlinkPolicy,
+    pub theme: String,
+    pub presets: Vec<ScanPreset>,
+    // 未在请求中指定时使用的大小阈值规则
+    pub flag_rules: Vec<FlagRule>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            default_excludes: Vec::new(),
+            size_unit: SizeUnit::default(),
+            locale: "zh-CN".to_string(),
+            cache_max_entries: 50,
+            cache_max_size_mb: 100,
+            prewarm_subdirectories: false,
+            history_limit: 20,
+            max_parallel_scans: 1,
+            symlink_policy: SymlinkPolicy::default(),
+            theme: "system".to_string(),
+            presets: default_presets(),
+            flag_rules: Vec::new(),
+        }
+    }
+}
+
+fn default_presets() -> Vec<ScanPreset> {
+    let home = std::env::var("HOME")
+        .or_else(|_| std::env::var("USERPROFILE"))
+        .unwrap_or_default();
+    let in_home = |dir: &str| {
+        Path::new(&home)
+            .join(dir)
+            .to_string_lossy()
+            .to_string()
+    };
+    let drive_root = if cfg!(windows) { "C:\\" } else { "/" };
+
+    vec![
+        ScanPreset {
+            name: "Downloads cleanup".to_string(),
+            path: in_home("Downloads"),
+            depth: Some(1),
+            options: ScanOptions {
+                older_than_days: Some(90),
+                ..ScanOptions::default()
+            },
+        },
+        ScanPreset {
+            name: "Dev projects".to_string(),
+            path: in_home("projects"),
+            depth: Some(2),
+            options: ScanOptions {
+                excludes: Some(vec![".git".to_string()]),
+                ..ScanOptions::default()
+            },
+        },
+        ScanPreset {
+            name: "Whole drive".to_string(),
+            path: drive_root.to_string(),
+            depth: Some(2),
+            options: ScanOptions {
+                low_priority: true,
+                ..ScanOptions::default()
+            },
+        },
+    ]
+}
+
+impl Settings {
+    // 文件不存在或无法解析时使用默认设置
+    pub fn load(path: &Path) -> Self {
+        std::fs::read_to_string(path)
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+        }
+        let json = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        std::fs::write(path, json).map_err(|e| e.to_string())
+    }
+
+    // 只覆盖 patch 中出现的字段
+    pub fn merge(&self, patch: serde_json::Value) -> Result<Settings, String> {
+        let mut current = serde_json::to_value(self).map_err(|e| e.to_string())?;
+        match (current.as_object_mut(), patch) {
+            (Some(current), serde_json::Value::Object(patch)) => current.extend(patch),
+            _ => return Err("设置必须是 JSON 对象".to_string()),
+        }
+        serde_json::from_value(current).map_err(|e| format!("无效的设置: {}", e))
+    }
+
+    pub fn preset(&self, name: &str) -> Option<&ScanPreset> {
+        self.presets
+            .iter()
+            .find(|preset| preset.name.eq_ignore_ascii_case(name.trim()))
+    }
+
+    // 用设置补全请求中未指定的扫描选项
+    pub fn apply_scan_defaults(&self, options: &mut ScanOptions) {
+        if options.excludes.is_none() && !self.default_excludes.is_empty() {
+            options.excludes = Some(self.default_excludes.clone());
+        }
+        if options.follow_symlinks.is_none() && self.symlink_policy == SymlinkPolicy::Skip {
+            options.follow_symlinks = Some(false);
+        }
+        if options.flag_rules.is_none() && !self.flag_rules.is_empty() {
+            options.flag_rules = Some(self.flag_rules.clone());
+        }
+    }
+}
@@ -0,0 +1,211 @@
+// 文件管理器右键菜单集成：选中文件夹后以 `--scan <path>` 启动本程序
+use crate::paths;
+use std::path::PathBuf;
+
+const MENU_LABEL: &str = "Analyze size with Search-tool";
+
+// 解析 `--scan` 的参数。资源管理器对驱动器根目录传入 "C:\"，末尾的反斜杠会转义右引号，
+// 参数变成 C:"，这里还原为 C:\；其余写法统一去掉末尾分隔符
+pub fn scan_arg(arg: &str) -> PathBuf {
+    let arg = match arg.strip_suffix('"') {
+        Some(rest) if cfg!(windows) => format!("{}\\", rest),
+        _ => arg.to_string(),
+    };
+    PathBuf::from(paths::display(&arg))
+}
+
+fn current_exe() -> Result<String, String> {
+    std::env::current_exe()
+        .map(|p| p.to_string_lossy().to_string())
+        .map_err(|e| format!("无法获取程序路径: {}", e))
+}
+
+pub fn register() -> Result<(), String> {
+    let exe = current_exe()?;
+
+    #[cfg(target_os = "windows")]
+    {
+        registry::register(&exe)
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        desktop_entry::register(&exe)
+    }
+
+    #[cfg(not(any(target_os = "windows", target_os = "linux")))]
+    {
+        let _ = exe;
+        Err(unsupported())
+    }
+}
+
+pub fn unregister() -> Result<(), String> {
+    #[cfg(target_os = "windows")]
+    {
+        registry::unregister()
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        desktop_entry::unregister()
+    }
+
+    #[cfg(not(any(target_os = "windows", target_os = "linux")))]
+    {
+        Err(unsupported())
+    }
+}
+
+// macOS 的 Finder 服务需要随应用包安装，无法在运行时注册
+#[cfg(not(any(target_os = "windows", target_os = "linux")))]
+fn unsupported() -> String {
+    if cfg!(target_os = "macos") {
+        "macOS 暂不支持 Finder 右键菜单集成".to_string()
+    } else {
+        "当前平台暂不支持右键菜单集成".to_string()
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod registry {
+    use super::MENU_LABEL;
+    use windows_sys::Win32::Foundation::{ERROR_FILE_NOT_FOUND, ERROR_SUCCESS};
+    use windows_sys::Win32::System::Registry::{
+        RegCloseKey, RegCreateKeyExW, RegDeleteTreeW, RegSetValueExW, HKEY, HKEY_CURRENT_USER,
+        KEY_WRITE, REG_OPTION_NON_VOLATILE, REG_SZ,
+    };
+
+    const KEY_NAME: &str = "SearchTool.Analyze";
+
+    // 文件夹、文件夹空白处和驱动器根目录各自的菜单位置及路径参数。
+    // 驱动器根目录以反斜杠结尾，加引号后反斜杠会转义右引号；根目录不含空格，不加引号。
+    // 在根目录空白处打开的菜单仍会传入 "C:\"，由 scan_arg 还原
+    const TARGETS: [(&str, &str); 3] = [
+        (r"Software\Classes\Directory\shell", "\"%V\""),
+        (r"Software\Classes\Directory\Background\shell", "\"%V\""),
+        (r"Software\Classes\Drive\shell", "%1"),
+    ];
+
+    fn wide(s: &str) -> Vec<u16> {
+        s.encode_utf16().chain(std::iter::once(0)).collect()
+    }
+
+    // 在 HKCU 下创建键并写入字符串值，name 为空时写默认值
+    fn set_value(subkey: &str, name: Option<&str>, value: &str) -> Result<(), String> {
+        let subkey_w = wide(subkey);
+        let name_w = name.map(wide);
+        let value_w = wide(value);
+        let mut key: HKEY = std::ptr::null_mut();
+
+        // SAFETY: 所有字符串以 0 结尾并在调用期间有效，打开的键在返回前关闭
+        unsafe {
+            let code = RegCreateKeyExW(
+                HKEY_CURRENT_USER,
+                subkey_w.as_ptr(),
+                0,
+                std::ptr::null(),
+                REG_OPTION_NON_VOLATILE,
+                KEY_WRITE,
+                std::ptr::null(),
+                &mut key,
+                std::ptr::null_mut(),
+            );
+            if code != ERROR_SUCCESS {
+                return Err(format!("无法创建注册表项 {}: {}", subkey, code));
+            }
+
+            let code = RegSetValueExW(
+                key,
+                name_w.as_ref().map_or(std::ptr::null(), |n| n.as_ptr()),
+                0,
+                REG_SZ,
+                value_w.as_ptr() as *const u8,
+                (value_w.len() * 2) as u32,
+            );
+            RegCloseKey(key);
+            if code != ERROR_SUCCESS {
+                return Err(format!("无法写入注册表项 {}: {}", subkey, code));
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn register(exe: &str) -> Result<(), String> {
+        for (parent, argument) in TARGETS {
+            let key = format!(r"{}\{}", parent, KEY_NAME);
+            set_value(&key, None, MENU_LABEL)?;
+            set_value(&key, Some("Icon"), exe)?;
+            let command = format!("\"{}\" --scan {}", exe, argument);
+            set_value(&format!(r"{}\command", key), None, &command)?;
+        }
+        Ok(())
+    }
+
+    pub fn unregister() -> Result<(), String> {
+        for (parent, _) in TARGETS {
+            let key = wide(&format!(r"{}\{}", parent, KEY_NAME));
+            // SAFETY: 键名以 0 结尾并在调用期间有效
+            let code = unsafe { RegDeleteTreeW(HKEY_CURRENT_USER, key.as_ptr()) };
+            if code != ERROR_SUCCESS && code != ERROR_FILE_NOT_FOUND {
+                return Err(format!("无法删除注册表项: {}", code));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod desktop_entry {
+    use super::MENU_LABEL;
+    use std::path::PathBuf;
+
+    const FILE_NAME: &str = "search-tool-analyze.desktop";
+
+    fn applications_dir() -> Result<PathBuf, String> {
+        let data_home = std::env::var_os("XDG_DATA_HOME")
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|h| PathBuf::from(h).join(".local/share")))
+            .ok_or_else(|| "无法确定用户数据目录".to_string())?;
+        Ok(data_home.join("applications"))
+    }
+
+    // 注册为文件夹的“打开方式”，文件管理器右键菜单中即可选择
+    pub fn register(exe: &str) -> Result<(), String> {
+        let dir = applications_dir()?;
+        std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+
+        let entry = format!(
+            "[Desktop Entry]\n\
+             Type=Application\n\
+             Name={}\n\
+             Exec=\"{}\" --scan %f\n\
+             MimeType=inode/directory;\n\
+             NoDisplay=true\n\
+             Terminal=false\n",
+            MENU_LABEL, exe
+        );
+        std::fs::write(dir.join(FILE_NAME), entry).map_err(|e| e.to_string())?;
+        refresh_database(&dir);
+        Ok(())
+    }
+
+    pub fn unregister() -> Result<(), String> {
+        let dir = applications_dir()?;
+        match std::fs::remove_file(dir.join(FILE_NAME)) {
+            Ok(()) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e.to_string()),
+        }
+        refresh_database(&dir);
+        Ok(())
+    }
+
+    // 部分桌面环境依赖 mimeinfo 缓存，工具不存在时忽略
+    fn refresh_database(dir: &std::path::Path) {
+        let _ = std::process::Command::new("update-desktop-database")
+            .arg(dir)
+            .status();
+    }
+}
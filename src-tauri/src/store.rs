@@ -0,0 +1,60 @@
+use crate::scan::{Item, ScanResult};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResultPage {
+    pub result_id: String,
+    pub offset: usize,
+    pub total: usize,
+    pub items: Vec<Item>,
+}
+
+// 按 ID 保存最近的扫描结果，后续的分页、搜索、树图、比较等操作只需传递 ID
+pub struct ResultStore {
+    results: HashMap<String, Arc<ScanResult>>,
+    order: VecDeque<String>,
+    max_entries: usize,
+}
+
+impl ResultStore {
+    pub fn new(max_entries: usize) -> Self {
+        ResultStore {
+            results: HashMap::new(),
+            order: VecDeque::new(),
+            max_entries,
+        }
+    }
+
+    pub fn insert(&mut self, result: ScanResult) -> String {
+        let id = uuid::Uuid::new_v4().to_string();
+
+        while self.order.len() >= self.max_entries {
+            match self.order.pop_front() {
+                Some(oldest) => {
+                    self.results.remove(&oldest);
+                }
+                None => break,
+            }
+        }
+
+        self.order.push_back(id.clone());
+        self.results.insert(id.clone(), Arc::new(result));
+        id
+    }
+
+    pub fn get(&self, id: &str) -> Option<Arc<ScanResult>> {
+        self.results.get(id).cloned()
+    }
+}
+
+pub fn page(result_id: &str, result: &ScanResult, offset: usize, limit: usize) -> ResultPage {
+    ResultPage {
+        result_id: result_id.to_string(),
+        offset,
+        total: result.items.len(),
+        items: result.items.iter().skip(offset).take(limit).cloned().collect(),
+    }
+}
@@ -0,0 +1,81 @@
+use crate::scan::{ScanOptions, ScanResult};
+use crate::volumes::Volume;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+// 系统概览中每个卷保留的层级
+pub const OVERVIEW_DEPTH: usize = 2;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VolumeOverview {
+    pub volume: Volume,
+    // 截断到 OVERVIEW_DEPTH 层的扫描结果，result_id 指向完整结果
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub result: Option<ScanResult>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SystemOverview {
+    pub volumes: Vec<VolumeOverview>,
+    // 所有卷的容量和剩余空间之和
+    pub total: u64,
+    pub free: u64,
+    // 扫描统计到的文件总大小
+    pub scanned_size: i64,
+    pub scan_time: f64,
+}
+
+impl SystemOverview {
+    pub fn new(volumes: Vec<VolumeOverview>, scan_time: f64) -> Self {
+        SystemOverview {
+            total: volumes.iter().map(|v| v.volume.total).sum(),
+            free: volumes.iter().map(|v| v.volume.free).sum(),
+            scanned_size: volumes
+                .iter()
+                .filter_map(|v| v.result.as_ref())
+                .map(|result| result.total_size)
+                .sum(),
+            volumes,
+            scan_time,
+        }
+    }
+}
+
+// 交换文件、休眠文件和虚拟文件系统不计入概览
+fn system_excludes() -> Vec<String> {
+    let excludes: &[&str] = if cfg!(windows) {
+        &[
+            "pagefile.sys",
+            "hiberfil.sys",
+            "swapfile.sys",
+            "System Volume Information",
+        ]
+    } else {
+        &["/proc", "/sys", "/dev", "/run"]
+    };
+    excludes.iter().map(|s| s.to_string()).collect()
+}
+
+// 在基础选项上追加系统排除项，并排除挂载在该卷之下的其他卷（它们单独统计）
+pub fn volume_options(volume: &Volume, volumes: &[Volume], base: &ScanOptions) -> ScanOptions {
+    let mut excludes = base.excludes.clone().unwrap_or_default();
+    excludes.extend(system_excludes());
+    excludes.extend(
+        volumes
+            .iter()
+            .filter(|other| {
+                other.mount_point != volume.mount_point
+                    && Path::new(&other.mount_point).starts_with(&volume.mount_point)
+            })
+            .map(|other| other.mount_point.clone()),
+    );
+
+    ScanOptions {
+        excludes: Some(excludes),
+        ..base.clone()
+    }
+}
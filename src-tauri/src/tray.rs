@@ -0,0 +1,111 @@
+use crate::commands;
+use crate::volumes::{self, Volume};
+use crate::AppState;
+use std::path::PathBuf;
+use std::time::Duration;
+use tauri::api::dialog::FileDialogBuilder;
+use tauri::{
+    AppHandle, CustomMenuItem, Manager, SystemTray, SystemTrayEvent, SystemTrayMenu,
+    SystemTrayMenuItem,
+};
+
+// 卷剩余空间的刷新间隔
+const VOLUME_POLL_INTERVAL: Duration = Duration::from_secs(60);
+
+pub fn build_tray() -> SystemTray {
+    SystemTray::new().with_menu(tray_menu(&[]))
+}
+
+fn tray_menu(volumes: &[Volume]) -> SystemTrayMenu {
+    let mut menu = SystemTrayMenu::new()
+        .add_item(CustomMenuItem::new("scan_last", "扫描上次的路径"))
+        .add_item(CustomMenuItem::new("scan_drive", "扫描磁盘…"))
+        .add_native_item(SystemTrayMenuItem::Separator);
+
+    // 每个卷一行剩余空间摘要，仅用于展示
+    for volume in volumes {
+        let title = format!(
+            "{}  可用 {} / 共 {}",
+            volume.mount_point, volume.free_formatted, volume.total_formatted
+        );
+        let id = format!("volume:{}", volume.mount_point);
+        menu = menu.add_item(CustomMenuItem::new(id, title).disabled());
+    }
+    if !volumes.is_empty() {
+        menu = menu.add_native_item(SystemTrayMenuItem::Separator);
+    }
+
+    menu.add_item(CustomMenuItem::new("toggle_window", "显示/隐藏窗口"))
+        .add_item(CustomMenuItem::new("quit", "退出"))
+}
+
+pub fn handle_tray_event(app: &AppHandle, event: SystemTrayEvent) {
+    match event {
+        SystemTrayEvent::LeftClick { .. } => toggle_window(app),
+        SystemTrayEvent::MenuItemClick { id, .. } => match id.as_str() {
+            "scan_last" => {
+                scan_last_path(app);
+                show_window(app);
+            }
+            "scan_drive" => {
+                let app = app.clone();
+                FileDialogBuilder::new().pick_folder(move |path| {
+                    if let Some(path) = path {
+                        commands::queue_scan_paths(&app, vec![path]);
+                        show_window(&app);
+                    }
+                });
+            }
+            "toggle_window" => toggle_window(app),
+            "quit" => app.exit(0),
+            _ => {}
+        },
+        _ => {}
+    }
+}
+
+// 将最近一次扫描的路径加入扫描队列，没有历史记录时返回空
+pub fn scan_last_path(app: &AppHandle) -> Option<String> {
+    let path = {
+        let state = app.state::<AppState>();
+        let history = state.history.lock().unwrap();
+        history.last()?.path.clone()
+    };
+    commands::queue_scan_paths(app, vec![PathBuf::from(&path)]);
+    Some(path)
+}
+
+pub fn toggle_window(app: &AppHandle) {
+    if let Some(window) = app.get_window("main") {
+        if window.is_visible().unwrap_or(false) {
+            let _ = window.hide();
+        } else {
+            show_window(app);
+        }
+    }
+}
+
+pub fn show_window(app: &AppHandle) {
+    if let Some(window) = app.get_window("main") {
+        let _ = window.show();
+        let _ = window.unminimize();
+        let _ = window.set_focus();
+    }
+}
+
+// 后台定期刷新各卷剩余空间，同步到托盘菜单并通知前端
+pub fn spawn_volume_poller(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let unit = app.state::<AppState>().settings.lock().unwrap().size_unit;
+            if let Ok(volumes) =
+                tokio::task::spawn_blocking(move || volumes::list_volumes(unit)).await
+            {
+                let _ = app.tray_handle().set_menu(tray_menu(&volumes));
+                let _ = app.emit_all("volumes-updated", &volumes);
+                *app.state::<AppState>().volumes.lock().unwrap() = volumes;
+            }
+            tokio::time::sleep(VOLUME_POLL_INTERVAL).await;
+        }
+    });
+}
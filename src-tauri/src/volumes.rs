@@ -0,0 +1,105 @@
+use crate::scan::{format_size, SizeUnit};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Volume {
+    pub mount_point: String,
+    pub total: u64,
+    pub free: u64,
+    pub total_formatted: String,
+    pub free_formatted: String,
+}
+
+impl Volume {
+    fn new(mount_point: String, total: u64, free: u64, unit: SizeUnit) -> Self {
+        Volume {
+            mount_point,
+            total,
+            free,
+            total_formatted: format_size(total as i64, unit),
+            free_formatted: format_size(free as i64, unit),
+        }
+    }
+}
+
+// 枚举本机卷及其剩余空间，无法读取的卷被忽略
+#[cfg(windows)]
+pub fn list_volumes(unit: SizeUnit) -> Vec<Volume> {
+    use windows_sys::Win32::Storage::FileSystem::{GetDiskFreeSpaceExW, GetLogicalDrives};
+
+    let mask = unsafe { GetLogicalDrives() };
+    let mut volumes = Vec::new();
+
+    for i in 0..26u8 {
+        if mask & (1 << i) == 0 {
+            continue;
+        }
+        let root = format!("{}:\\", (b'A' + i) as char);
+        let wide: Vec<u16> = root.encode_utf16().chain(std::iter::once(0)).collect();
+        let (mut free, mut total) = (0u64, 0u64);
+        let ok = unsafe {
+            GetDiskFreeSpaceExW(wide.as_ptr(), &mut free, &mut total, std::ptr::null_mut())
+        };
+        if ok != 0 && total > 0 {
+            volumes.push(Volume::new(root, total, free, unit));
+        }
+    }
+
+    volumes
+}
+
+#[cfg(unix)]
+pub fn list_volumes(unit: SizeUnit) -> Vec<Volume> {
+    mount_points()
+        .into_iter()
+        .filter_map(|mount_point| {
+            let (total, free) = statvfs(&mount_point)?;
+            (total > 0).then(|| Volume::new(mount_point, total, free, unit))
+        })
+        .collect()
+}
+
+#[cfg(unix)]
+fn statvfs(path: &str) -> Option<(u64, u64)> {
+    let c_path = std::ffi::CString::new(path).ok()?;
+    let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+    if unsafe { libc::statvfs(c_path.as_ptr(), &mut stat) } != 0 {
+        return None;
+    }
+    let block = stat.f_frsize as u64;
+    Some((stat.f_blocks as u64 * block, stat.f_bavail as u64 * block))
+}
+
+// 只列出挂载在块设备上的文件系统，跳过 proc、tmpfs 等虚拟文件系统
+#[cfg(target_os = "linux")]
+pub fn mount_points() -> Vec<String> {
+    let mounts = std::fs::read_to_string("/proc/mounts").unwrap_or_default();
+    let mut points: Vec<String> = mounts
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let device = fields.next()?;
+            let mount_point = fields.next()?;
+            // /proc/mounts 中的空格等字符以八进制转义
+            device
+                .starts_with("/dev/")
+                .then(|| mount_point.replace("\\040", " "))
+        })
+        .collect();
+    points.dedup();
+    points
+}
+
+#[cfg(all(unix, not(target_os = "linux")))]
+pub fn mount_points() -> Vec<String> {
+    let mut points = vec!["/".to_string()];
+    if let Ok(entries) = std::fs::read_dir("/Volumes") {
+        points.extend(
+            entries
+                .flatten()
+                .map(|entry| entry.path().to_string_lossy().to_string()),
+        );
+    }
+    points
+}
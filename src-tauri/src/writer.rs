@@ -0,0 +1,41 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+// 按提交顺序写入同一组文件：每次写入领取递增的版本号，较早的快照在较新的写完后
+// 才轮到时直接丢弃。调用方在持有快照所在的锁时提交，版本号才与快照的先后一致
+#[derive(Default)]
+pub struct OrderedWriter {
+    version: AtomicU64,
+    written: Mutex<u64>,
+}
+
+impl OrderedWriter {
+    // 内容可能很大，在后台写入
+    pub fn spawn(self: &Arc<Self>, write: impl FnOnce() + Send + 'static) {
+        let version = self.next_version();
+        let writer = self.clone();
+        tauri::async_runtime::spawn_blocking(move || {
+            writer.run(version, write);
+        });
+    }
+
+    // 立即写入并返回结果；已有更新的快照写完时跳过
+    pub fn write<E>(&self, write: impl FnOnce() -> Result<(), E>) -> Result<(), E> {
+        let version = self.next_version();
+        self.run(version, write).unwrap_or(Ok(()))
+    }
+
+    fn next_version(&self) -> u64 {
+        self.version.fetch_add(1, Ordering::SeqCst) + 1
+    }
+
+    fn run<T>(&self, version: u64, write: impl FnOnce() -> T) -> Option<T> {
+        let mut written = self.written.lock().unwrap();
+        if *written > version {
+            return None;
+        }
+        let outcome = write();
+        *written = version;
+        Some(outcome)
+    }
+}